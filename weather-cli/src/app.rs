@@ -1,32 +1,321 @@
-use weather_core::error::Error;
+use std::{net::SocketAddr, path::PathBuf, process::ExitCode, time::Duration};
 
+use clap::ArgMatches;
+use weather_core::{
+  alert::Filter,
+  cache::Cache,
+  config::Config,
+  error::Error,
+  http,
+  keys::{Cooldowns, KeyRing},
+  locale::{self, Locale},
+  location::Location,
+  quota::{Quota, UsageLog},
+  redact,
+  store::{self, Store},
+  template::Template,
+};
+
+use crate::bar::{handle_bar, Protocol, DEFAULT_TEMPLATE};
+use crate::chart::Mode;
 use crate::command::cli;
-use crate::handler::{handle_alerts, handle_astronomy, handle_current, handle_forecast};
+use crate::exporter::handle_exporter;
+use crate::handler::{
+  handle_alerts, handle_astronomy, handle_current, handle_export_ical, handle_forecast, handle_log,
+  handle_quota, handle_stats, Output, Source,
+};
+use crate::locate::{locate_place, resolve_location, LocationQuery};
+use crate::logging::init_logging;
+use crate::mqtt::handle_mqtt;
+use crate::serve::handle_serve;
+use crate::tui::handle_tui;
+use crate::validate::{validate_days, validate_interval};
+use crate::watch::{handle_watch, View};
 
-pub async fn run() -> Result<(), Error> {
+pub async fn run() -> Result<ExitCode, Error> {
   dotenv::dotenv().ok();
 
   let matches = cli().get_matches();
+  init_logging(
+    matches.get_count("verbose"),
+    matches
+      .get_one::<String>("log_format")
+      .unwrap()
+      .as_str()
+      .into(),
+    matches.get_one::<PathBuf>("log_file"),
+  )?;
+
+  let config = Config::load()?;
+  redact::set_redact_ip(config.http.redact_ip);
+  http::set_provider(
+    matches
+      .get_one::<String>("provider")
+      .map_or("weatherapi", |provider| provider.as_str())
+      .parse()?,
+  );
+  http::set_cache(cache(&matches, &config));
+  http::set_policy((&config.http).into());
+  http::set_quota(Some(Quota::new(
+    &config.quota,
+    UsageLog::default_path(),
+    |message| tracing::warn!("{}", message),
+  )));
+  if !config.keys.is_empty() {
+    http::set_keys(Some(key_ring(&config)));
+  }
+  if config.store.record || matches.subcommand_name() == Some("log") {
+    store::set_recorder(Some(Store::from_config(&config.store)?));
+  }
+  locale::set_locale(match matches.get_one::<String>("lang") {
+    Some(lang) => lang.parse()?,
+    None => Locale::from_env(),
+  });
 
   match matches.subcommand() {
-    Some(("alert", args)) => match handle_alerts(*args.get_one::<i32>("days").unwrap()).await {
-      Ok(_) => Ok(()),
-      Err(err) => Err(err),
-    },
-    Some(("astronomy", _)) => match handle_astronomy().await {
-      Ok(_) => Ok(()),
-      Err(err) => Err(err),
-    },
-    Some(("current", _)) => match handle_current().await {
-      Ok(_) => Ok(()),
+    Some(("alert", args)) => {
+      let mut filter = alert_filter(args)?;
+      let new_only = args.get_flag("new");
+      let notify = args.get_flag("notify");
+      let output = match (
+        template(args, &config)?,
+        args.get_one::<String>("format").unwrap().as_str(),
+      ) {
+        (Some(template), _) => Output::Template(template),
+        (None, "text") => Output::Text,
+        (None, format) => Output::Feed(format.parse()?),
+      };
+      let days = *args.get_one::<i32>("days").unwrap();
+      let geofence = args.get_flag("geofence");
+      let source = match args.get_one::<String>("cap") {
+        Some(cap) => {
+          if geofence {
+            filter.location = Some(resolve_location(args, &config).await?);
+          }
+          Source::Cap(cap.clone())
+        }
+        None => {
+          let location = resolve_location(args, &config).await?;
+          if geofence {
+            filter.location = Some(location.clone());
+          }
+          Source::Provider(location, days)
+        }
+      };
+      handle_alerts(source, filter, new_only, notify, output, &config).await
+    }
+    Some(("astronomy", args)) => {
+      match handle_astronomy(
+        resolve_location(args, &config).await?,
+        template(args, &config)?,
+      )
+      .await
+      {
+        Ok(_) => Ok(ExitCode::SUCCESS),
+        Err(err) => Err(err),
+      }
+    }
+    Some(("current", args)) => match handle_current(
+      resolve_location(args, &config).await?,
+      args.get_count("verbose") > 0,
+      template(args, &config)?,
+    )
+    .await
+    {
+      Ok(_) => Ok(ExitCode::SUCCESS),
       Err(err) => Err(err),
     },
     Some(("forecast", args)) => {
-      match handle_forecast(*args.get_one::<i32>("days").unwrap()).await {
-        Ok(_) => Ok(()),
+      let location = resolve_location(args, &config).await?;
+      let chart = match (args.get_flag("chart"), args.get_flag("braille")) {
+        (true, true) => Some(Mode::Braille),
+        (true, false) => Some(Mode::Block),
+        _ => None,
+      };
+      let verbose = args.get_count("verbose") > 0;
+      match handle_forecast(
+        location,
+        *args.get_one::<i32>("days").unwrap(),
+        chart,
+        verbose,
+        template(args, &config)?,
+      )
+      .await
+      {
+        Ok(_) => Ok(ExitCode::SUCCESS),
         Err(err) => Err(err),
       }
     }
+    Some(("bar", args)) => {
+      let days = *args.get_one::<i32>("days").unwrap();
+      validate_days(days)?;
+      let protocol = Protocol::from(args.get_one::<String>("protocol").unwrap().as_str());
+      let template = template(args, &config)?.unwrap_or(DEFAULT_TEMPLATE.parse()?);
+      let location = resolve_location(args, &config).await?;
+      handle_bar(location, days, &template, protocol).await?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("watch", args)) => {
+      let view = View::from(args.get_one::<String>("view").unwrap().as_str());
+      let interval = *args.get_one::<Duration>("interval").unwrap();
+      let days = *args.get_one::<i32>("days").unwrap();
+      validate_days(days)?;
+      validate_interval(interval)?;
+      let location = resolve_location(args, &config).await?;
+      handle_watch(location, view, interval, days).await?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("tui", args)) => {
+      let interval = *args.get_one::<Duration>("interval").unwrap();
+      validate_interval(interval)?;
+      let location = resolve_location(args, &config).await?;
+      let place = args.get_one::<String>("place").map(String::as_str);
+      handle_tui(location, place, &config, interval).await?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("serve", args)) => {
+      let bind = *args.get_one::<SocketAddr>("bind").unwrap();
+      let public_url = args.get_one::<String>("public_url").cloned();
+      handle_serve(bind, public_url, config).await?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("exporter", args)) => {
+      let bind = *args.get_one::<SocketAddr>("bind").unwrap();
+      let interval = *args.get_one::<Duration>("interval").unwrap();
+      validate_interval(interval)?;
+      let places = configured_places(args, &config).await?;
+      handle_exporter(bind, places, interval).await?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("mqtt", args)) => {
+      let interval = *args.get_one::<Duration>("interval").unwrap();
+      let days = *args.get_one::<i32>("days").unwrap();
+      validate_days(days)?;
+      validate_interval(interval)?;
+
+      let mut mqtt = config.mqtt.clone();
+      if let Some(broker) = args.get_one::<String>("broker") {
+        mqtt.broker = broker.clone();
+      }
+      mqtt.discovery |= args.get_flag("discovery");
+
+      let places = configured_places(args, &config).await?;
+      handle_mqtt(places, days, interval, &mqtt).await?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("log", args)) => {
+      let places = logged_places(args, &config).await?;
+      handle_log(places).await?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("stats", args)) => {
+      let store = Store::from_config(&config.store)?;
+      let place = args.get_one::<String>("place").unwrap();
+      let since = *args.get_one::<Duration>("since").unwrap();
+      handle_stats(&store, place, since)?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("quota", _)) => {
+      handle_quota(&config)?;
+      Ok(ExitCode::SUCCESS)
+    }
+    Some(("export", args)) => match args.subcommand() {
+      Some(("ical", args)) => {
+        let days = *args.get_one::<i32>("days").unwrap();
+        let output = args.get_one::<PathBuf>("output");
+        let location = resolve_location(args, &config).await?;
+        handle_export_ical(location, days, output).await?;
+        Ok(ExitCode::SUCCESS)
+      }
+      _ => unreachable!(),
+    },
     _ => unreachable!(),
   }
 }
+
+/// Response cache of the command, if it uses one.
+/// Only commands that run often or for long use the cache, and `watch` and `tui` never keep
+/// responses longer than their interval so each refresh shows new weather.
+fn cache(matches: &ArgMatches, config: &Config) -> Option<Cache> {
+  let cache: Option<Cache> = (&config.cache).into();
+  match matches.subcommand() {
+    Some(("watch" | "tui", args)) => {
+      cache.map(|cache| cache.limit_ttl(*args.get_one::<Duration>("interval").unwrap()))
+    }
+    Some(("bar" | "serve", _)) => cache,
+    _ => None,
+  }
+}
+
+/// Key ring of the configured keys.
+/// The key that served each request is logged from `-v` on.
+fn key_ring(config: &Config) -> KeyRing {
+  KeyRing::new(
+    &config.keys,
+    Duration::from_secs(config.quota.cooldown),
+    Cooldowns::default_path(),
+    |message| tracing::warn!("{}", message),
+  )
+}
+
+/// Template given with `--template`, looked up by name in the config file first.
+fn template(args: &ArgMatches, config: &Config) -> Result<Option<Template>, Error> {
+  match args.get_one::<String>("template") {
+    Some(template) => Ok(Some(config.template(template).parse()?)),
+    None => Ok(None),
+  }
+}
+
+/// Build the alert filter from the alert command arguments.
+fn alert_filter(args: &ArgMatches) -> Result<Filter, Error> {
+  let min_severity = match args.get_one::<String>("min_severity") {
+    Some(severity) => Some(severity.parse()?),
+    None => None,
+  };
+
+  let events = args
+    .get_many::<String>("event")
+    .map(|events| events.cloned().collect())
+    .unwrap_or_default();
+
+  Ok(Filter {
+    min_severity,
+    events,
+    active_only: args.get_flag("active_only"),
+    location: None,
+  })
+}
+
+/// Places reported by the exporter and mqtt commands.
+/// Every configured place, or the location given on the command line when there are none.
+async fn configured_places(
+  args: &ArgMatches,
+  config: &Config,
+) -> Result<Vec<(String, Location)>, Error> {
+  if config.places.is_empty() {
+    let location = resolve_location(args, config).await?;
+    return Ok(vec![(location.to_string(), location)]);
+  }
+
+  let mut places = Vec::new();
+  for place in &config.places {
+    places.push((place.name.clone(), locate_place(&place.name, config).await?));
+  }
+  Ok(places)
+}
+
+/// Places sampled by the log command.
+/// The location given on the command line, otherwise every configured place.
+async fn logged_places(
+  args: &ArgMatches,
+  config: &Config,
+) -> Result<Vec<(String, Location)>, Error> {
+  let query = LocationQuery::from(args);
+  match query.resolve(config).await? {
+    Some(location) => {
+      let name = query.place.unwrap_or_else(|| location.to_string());
+      Ok(vec![(name, location)])
+    }
+    None => configured_places(args, config).await,
+  }
+}
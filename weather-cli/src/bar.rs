@@ -0,0 +1,334 @@
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use weather_core::{
+  alert::{Alerts, Filter, Severity},
+  error::Error,
+  forecast::{get_outlook, Forecast},
+  locale,
+  location::Location,
+  redact::describe,
+  template::{weather_context, Template},
+  weather::{Units, Weather},
+};
+
+/// Template of the bar text when none is given.
+pub const DEFAULT_TEMPLATE: &str = "{condition.glyph} {temp|round}°{unit}";
+
+/// Output formats understood by status bars.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+  /// The text alone.
+  Plain,
+  /// A JSON object for a waybar custom module with `return-type` json.
+  Waybar,
+  /// A JSON block of the i3bar protocol, for i3blocks or a status wrapper.
+  I3bar,
+  /// Text with tmux style escapes, for `status-right`.
+  Tmux,
+}
+
+impl From<&str> for Protocol {
+  fn from(protocol: &str) -> Self {
+    match protocol {
+      "waybar" => Protocol::Waybar,
+      "i3bar" => Protocol::I3bar,
+      "tmux" => Protocol::Tmux,
+      _ => Protocol::Plain,
+    }
+  }
+}
+
+/// What a bar shows: a line of text, a longer tooltip, and classes to style it by.
+#[derive(Debug, Clone, PartialEq)]
+struct Bar {
+  text: String,
+  tooltip: String,
+  /// Kind of the current condition, and the highest alert severity if there are alerts.
+  classes: Vec<String>,
+  severity: Option<Severity>,
+}
+
+/// Handle the bar command.
+/// Prints a single line, so it can be run every few seconds by a status bar.
+/// The weather, forecast and alerts come from a single request, and from the cache when it is
+/// enabled, so frequent runs do not use up the quota.
+/// Failures are shown in the bar rather than exiting, so the bar keeps its layout.
+pub async fn handle_bar(
+  location: Location,
+  days: i32,
+  template: &Template,
+  protocol: Protocol,
+) -> Result<(), Error> {
+  let bar = match fetch(&location, days).await {
+    Ok((weather, forecast, alerts)) => Bar::new(&weather, &forecast, &alerts, template)?,
+    Err(err) => Bar::error(&err),
+  };
+  println!("{}", bar.render(protocol));
+  Ok(())
+}
+
+async fn fetch(location: &Location, days: i32) -> Result<(Weather, Forecast, Alerts), Error> {
+  let outlook = get_outlook(location.clone(), Units::Metric, days).await?;
+  Ok((
+    outlook.weather,
+    outlook.forecast,
+    active(outlook.alerts, Utc::now()),
+  ))
+}
+
+fn active(alerts: Alerts, now: DateTime<Utc>) -> Alerts {
+  let filter = Filter {
+    active_only: true,
+    ..Filter::default()
+  };
+  alerts.dedup().filter(&filter, now)
+}
+
+impl Bar {
+  fn new(
+    weather: &Weather,
+    forecast: &Forecast,
+    alerts: &Alerts,
+    template: &Template,
+  ) -> Result<Self, Error> {
+    let locale = locale::current();
+    let mut lines: Vec<String> = forecast
+      .days
+      .iter()
+      .map(|day| {
+        format!(
+          "{} {} {}, {}–{}°C, {} {}%",
+          locale.date(&day.date),
+          day.condition.kind().glyph(true),
+          day.condition.text,
+          locale.number(day.min_temperature),
+          locale.number(day.max_temperature),
+          locale.label("Chance of Rain"),
+          day.chance_of_rain
+        )
+      })
+      .collect();
+    lines.extend(
+      alerts
+        .alerts
+        .iter()
+        .map(|alert| format!("⚠ {}", alert.headline)),
+    );
+
+    let severity = alerts
+      .alerts
+      .iter()
+      .map(|alert| alert.severity_level())
+      .max();
+    let mut classes = vec![weather.condition.kind().name().to_string()];
+    if let Some(severity) = severity {
+      classes.push(severity.to_string().to_lowercase());
+    }
+
+    Ok(Bar {
+      text: template.render(&weather_context(weather))?,
+      tooltip: lines.join("\n"),
+      classes,
+      severity,
+    })
+  }
+
+  fn error(err: &Error) -> Self {
+    Bar {
+      text: "weather unavailable".to_string(),
+      tooltip: describe(err),
+      classes: vec!["error".to_string()],
+      severity: None,
+    }
+  }
+
+  fn render(&self, protocol: Protocol) -> String {
+    match protocol {
+      Protocol::Plain => self.text.clone(),
+      Protocol::Waybar => json!({
+        "text": pango(&self.text),
+        "tooltip": pango(&self.tooltip),
+        "class": self.classes,
+        "alt": self.classes[0],
+      })
+      .to_string(),
+      Protocol::I3bar => {
+        let mut block = json!({
+          "name": "weather",
+          "full_text": self.text,
+          "short_text": self.text,
+          "urgent": self.severity >= Some(Severity::Severe),
+        });
+        if let Some(colour) = self.colour() {
+          block["color"] = json!(colour);
+        }
+        block.to_string()
+      }
+      Protocol::Tmux => {
+        let text = self.text.replace('#', "##");
+        match self.tmux_colour() {
+          Some(colour) => format!("#[fg={}]{}#[default]", colour, text),
+          None => text,
+        }
+      }
+    }
+  }
+
+  /// Colour of the text in i3bar, by alert severity.
+  fn colour(&self) -> Option<&'static str> {
+    match self.severity? {
+      Severity::Extreme => Some("#FF0000"),
+      Severity::Severe => Some("#FF8800"),
+      Severity::Moderate => Some("#FFFF00"),
+      _ => None,
+    }
+  }
+
+  fn tmux_colour(&self) -> Option<&'static str> {
+    match self.severity? {
+      Severity::Extreme => Some("red"),
+      Severity::Severe => Some("colour208"),
+      Severity::Moderate => Some("yellow"),
+      _ => None,
+    }
+  }
+}
+
+/// Text escaped for Pango markup, which waybar parses its text and tooltip as.
+fn pango(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+  use serde_json::Value;
+  use weather_core::{
+    alert::Alert, astronomy::Astronomy, forecast::ForecastDay, weather::Condition,
+  };
+
+  fn forecast() -> Forecast {
+    Forecast {
+      days: vec![ForecastDay {
+        date: "2024-12-28".to_string(),
+        max_temperature: 7.2,
+        min_temperature: 4.1,
+        avg_temperature: 5.6,
+        max_wind_speed: 10.1,
+        total_precipitation: 0.2,
+        avg_humidity: 91.0,
+        chance_of_rain: 40,
+        chance_of_snow: 0,
+        condition: Condition::new("Light rain".to_string(), String::new(), 1183),
+        uv: 0.1,
+        astronomy: Astronomy::new(
+          "08:06 AM".to_string(),
+          "03:58 PM".to_string(),
+          "06:11 AM".to_string(),
+          "01:21 PM".to_string(),
+          "Waning Crescent".to_string(),
+          9,
+          0,
+          0,
+        ),
+        hours: Vec::new(),
+      }],
+    }
+  }
+
+  fn alerts(severity: &str, expires: &str) -> Alerts {
+    Alerts::new(vec![Alert {
+      headline: "Flood warning".to_string(),
+      severity: severity.to_string(),
+      event: "Flood Warning".to_string(),
+      effective: "2024-12-28T00:00:00+00:00".to_string(),
+      expires: expires.to_string(),
+      ..Alert::sample()
+    }])
+  }
+
+  fn sample(alerts: &Alerts) -> Bar {
+    let template: Template = DEFAULT_TEMPLATE.parse().unwrap();
+    Bar::new(&Weather::sample(), &forecast(), alerts, &template).unwrap()
+  }
+
+  #[test]
+  fn test_bar() {
+    let bar = sample(&alerts("Severe", "2024-12-29T00:00:00+00:00"));
+    assert_eq!(bar.text, "≡ 5°C");
+    assert_eq!(bar.classes, vec!["fog", "severe"]);
+    assert_eq!(
+      bar.tooltip,
+      "2024-12-28 ☂ Light rain, 4.1–7.2°C, Chance of Rain 40%\n⚠ Flood warning"
+    );
+
+    let calm = sample(&Alerts::new(Vec::new()));
+    assert_eq!(calm.classes, vec!["fog"]);
+    assert_eq!(calm.severity, None);
+  }
+
+  #[test]
+  fn test_active() {
+    let now = Utc.with_ymd_and_hms(2024, 12, 28, 12, 0, 0).unwrap();
+    assert_eq!(
+      active(alerts("Severe", "2024-12-29T00:00:00+00:00"), now).len(),
+      1
+    );
+    assert!(active(alerts("Severe", "2024-12-28T06:00:00+00:00"), now).is_empty());
+  }
+
+  #[test]
+  fn test_render_waybar() {
+    let bar = sample(&alerts("Extreme", "2024-12-29T00:00:00+00:00"));
+    let output: Value = serde_json::from_str(&bar.render(Protocol::Waybar)).unwrap();
+    assert_eq!(output["text"], "≡ 5°C");
+    assert_eq!(output["class"], json!(["fog", "extreme"]));
+    assert_eq!(output["alt"], "fog");
+    assert!(output["tooltip"]
+      .as_str()
+      .unwrap()
+      .contains("Flood warning"));
+  }
+
+  #[test]
+  fn test_render_waybar_escaped() {
+    let mut bar = sample(&alerts("Severe", "2024-12-29T00:00:00+00:00"));
+    bar.tooltip = "⚠ Wind & rain <coast>".to_string();
+    let output: Value = serde_json::from_str(&bar.render(Protocol::Waybar)).unwrap();
+    assert_eq!(output["tooltip"], "⚠ Wind &amp; rain &lt;coast&gt;");
+    assert_eq!(bar.render(Protocol::Plain), "≡ 5°C");
+  }
+
+  #[test]
+  fn test_render_i3bar() {
+    let severe = sample(&alerts("Severe", "2024-12-29T00:00:00+00:00"));
+    let output: Value = serde_json::from_str(&severe.render(Protocol::I3bar)).unwrap();
+    assert_eq!(output["full_text"], "≡ 5°C");
+    assert_eq!(output["color"], "#FF8800");
+    assert_eq!(output["urgent"], true);
+
+    let minor = sample(&alerts("Minor", "2024-12-29T00:00:00+00:00"));
+    let output: Value = serde_json::from_str(&minor.render(Protocol::I3bar)).unwrap();
+    assert!(output.get("color").is_none());
+    assert_eq!(output["urgent"], false);
+  }
+
+  #[test]
+  fn test_render_tmux() {
+    let mut bar = sample(&alerts("Moderate", "2024-12-29T00:00:00+00:00"));
+    bar.text = "#1 rain".to_string();
+    assert_eq!(bar.render(Protocol::Tmux), "#[fg=yellow]##1 rain#[default]");
+    assert_eq!(bar.render(Protocol::Plain), "#1 rain");
+  }
+
+  #[test]
+  fn test_error() {
+    let bar = Bar::error(&Error::NoLocation);
+    assert_eq!(bar.classes, vec!["error"]);
+    assert_eq!(bar.render(Protocol::Plain), "weather unavailable");
+  }
+}
@@ -0,0 +1,401 @@
+use weather_core::forecast::{Forecast, Hour};
+
+/// Characters used for sparklines and bars, from empty to full.
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Arrows pointing the way the wind blows, indexed by the compass octant it comes from.
+const ARROWS: [char; 8] = ['↓', '↙', '←', '↖', '↑', '↗', '→', '↘'];
+
+/// Width of the y axis labels, including the axis itself.
+const AXIS_WIDTH: usize = 8;
+
+/// Height of line and bar charts in rows.
+const CHART_HEIGHT: usize = 8;
+
+/// How line charts are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+  /// One point per character cell.
+  Block,
+  /// Braille patterns, giving 2x4 points per character cell.
+  Braille,
+}
+
+/// A series of values drawn on a line chart with its own mark.
+pub struct Series<'a> {
+  pub values: &'a [f64],
+  pub mark: char,
+}
+
+/// Width of the terminal in columns, falling back to 80 when it can not be determined.
+pub fn terminal_width() -> usize {
+  match ratatui::crossterm::terminal::size() {
+    Ok((columns, _)) if columns > 0 => columns as usize,
+    _ => 80,
+  }
+}
+
+/// Render the hourly charts for a forecast at the given width.
+/// `units` are the labels for temperature, speed and pressure.
+pub fn forecast_charts(forecast: &Forecast, width: usize, mode: Mode, units: [&str; 3]) -> String {
+  let hours: Vec<&Hour> = forecast.days.iter().flat_map(|day| &day.hours).collect();
+  if hours.is_empty() {
+    return String::new();
+  }
+
+  let [temperature_unit, speed_unit, pressure_unit] = units;
+  let plot_width = width.saturating_sub(AXIS_WIDTH).max(8);
+  let collect = |value: fn(&Hour) -> f64| hours.iter().map(|hour| value(hour)).collect::<Vec<_>>();
+
+  let temperature = collect(|hour| hour.temperature);
+  let feels_like = collect(|hour| hour.feels_like);
+  let rain = collect(|hour| hour.chance_of_rain as f64);
+  let wind = collect(|hour| hour.wind_speed);
+  let degrees = collect(|hour| hour.wind_degree as f64);
+  let pressure = collect(|hour| hour.pressure);
+
+  let mut output = String::new();
+
+  output.push_str(&format!(
+    "Temperature ({}) ● Feels Like ○\n",
+    temperature_unit
+  ));
+  output.push_str(&line_chart(
+    &[
+      Series {
+        values: &temperature,
+        mark: '●',
+      },
+      Series {
+        values: &feels_like,
+        mark: '○',
+      },
+    ],
+    plot_width,
+    CHART_HEIGHT,
+    mode,
+  ));
+  output.push_str(&time_axis(&hours, plot_width));
+
+  output.push_str("\nChance of Rain (%)\n");
+  output.push_str(&bar_chart(&rain, 100.0, plot_width, CHART_HEIGHT / 2));
+
+  output.push_str(&format!("\nWind ({})\n", speed_unit));
+  output.push_str(&format!(
+    "{:>width$}{}\n",
+    format!("{:.0} ", max(&wind)),
+    sparkline(&resample(&wind, plot_width)),
+    width = AXIS_WIDTH
+  ));
+  output.push_str(&format!(
+    "{:>width$}{}\n",
+    "",
+    arrows(&nearest(&degrees, plot_width)),
+    width = AXIS_WIDTH
+  ));
+
+  let first = pressure[0];
+  let last = pressure[pressure.len() - 1];
+  output.push_str(&format!(
+    "\nPressure ({}) {} → {}, {}\n",
+    pressure_unit,
+    first,
+    last,
+    trend(first, last)
+  ));
+  output.push_str(&format!(
+    "{:>width$}{}\n",
+    "",
+    sparkline(&resample(&pressure, plot_width)),
+    width = AXIS_WIDTH
+  ));
+
+  output
+}
+
+/// Render values as a single line of block characters scaled between their minimum and maximum.
+pub fn sparkline(values: &[f64]) -> String {
+  let (low, high) = (min(values), max(values));
+  values
+    .iter()
+    .map(|value| {
+      let level = match high > low {
+        true => ((value - low) / (high - low) * 7.0).round() as usize + 1,
+        false => 4,
+      };
+      BLOCKS[level.min(8)]
+    })
+    .collect()
+}
+
+/// Render one arrow per wind direction, in degrees the wind comes from.
+pub fn arrows(degrees: &[f64]) -> String {
+  degrees
+    .iter()
+    .map(|degree| ARROWS[((degree.rem_euclid(360.0) / 45.0).round() as usize) % 8])
+    .collect()
+}
+
+/// Render a line chart with a labelled y axis.
+/// Every series is resampled to fit `width` columns and `height` rows.
+pub fn line_chart(series: &[Series], width: usize, height: usize, mode: Mode) -> String {
+  let all: Vec<f64> = series
+    .iter()
+    .flat_map(|s| s.values.iter().copied())
+    .collect();
+  if all.is_empty() || width == 0 || height == 0 {
+    return String::new();
+  }
+
+  let (low, high) = (min(&all), max(&all));
+  let range = match high > low {
+    true => high - low,
+    false => 1.0,
+  };
+
+  let (x_scale, y_scale) = match mode {
+    Mode::Block => (1, 1),
+    Mode::Braille => (2, 4),
+  };
+  let (pixels_x, pixels_y) = (width * x_scale, height * y_scale);
+
+  // Row of the pixel grid for a value, with row 0 at the top.
+  let row =
+    |value: f64| pixels_y - 1 - (((value - low) / range) * (pixels_y - 1) as f64).round() as usize;
+
+  let mut cells = vec![vec![' '; width]; height];
+  let mut dots = vec![vec![0u8; width]; height];
+
+  for s in series {
+    let points = resample(s.values, pixels_x);
+    let mut previous: Option<usize> = None;
+    for (x, value) in points.iter().enumerate() {
+      let y = row(*value);
+      match mode {
+        Mode::Block => cells[y][x] = s.mark,
+        Mode::Braille => {
+          // Join to the previous point so steep changes stay connected.
+          let (from, to) = match previous {
+            Some(previous) => (previous.min(y), previous.max(y)),
+            None => (y, y),
+          };
+          for y in from..=to {
+            dots[y / 4][x / 2] |= braille_bit(x % 2, y % 4);
+          }
+        }
+      }
+      previous = Some(y);
+    }
+  }
+
+  let mut output = String::new();
+  for line in 0..height {
+    let label = match line {
+      0 => format!("{:.1}", high),
+      line if line == height - 1 => format!("{:.1}", low),
+      _ => String::new(),
+    };
+    output.push_str(&format!("{:>width$} ┤", label, width = AXIS_WIDTH - 2));
+
+    for column in 0..width {
+      let cell = match mode {
+        Mode::Block => cells[line][column],
+        Mode::Braille => char::from_u32(0x2800 + dots[line][column] as u32).unwrap_or(' '),
+      };
+      output.push(cell);
+    }
+    output.push('\n');
+  }
+  output
+}
+
+/// Render a vertical bar chart, one bar per column, scaled from zero to `scale`.
+pub fn bar_chart(values: &[f64], scale: f64, width: usize, height: usize) -> String {
+  let bars = resample(values, width);
+  let mut output = String::new();
+
+  for line in 0..height {
+    let label = match line {
+      0 => format!("{:.0}", scale),
+      _ => String::new(),
+    };
+    output.push_str(&format!("{:>width$} ┤", label, width = AXIS_WIDTH - 2));
+
+    let floor = (height - 1 - line) * 8;
+    for value in &bars {
+      let eighths = ((value / scale).clamp(0.0, 1.0) * (height * 8) as f64).round() as usize;
+      output.push(BLOCKS[eighths.saturating_sub(floor).min(8)]);
+    }
+    output.push('\n');
+  }
+  output
+}
+
+/// Axis under an hourly chart, marking the start of each day and every six hours.
+fn time_axis(hours: &[&Hour], width: usize) -> String {
+  let mut axis = vec![' '; width];
+  let mut labels = vec![' '; width];
+
+  for (index, hour) in hours.iter().enumerate() {
+    let column = match hours.len() > 1 {
+      true => index * (width - 1) / (hours.len() - 1),
+      false => 0,
+    };
+    let time = hour.time.split(' ').nth(1).unwrap_or("");
+    if time.ends_with(":00") && time[..2].parse::<u32>().is_ok_and(|hour| hour % 6 == 0) {
+      axis[column] = '┬';
+      for (offset, c) in time[..2].chars().enumerate() {
+        if column + offset < width {
+          labels[column + offset] = c;
+        }
+      }
+    }
+  }
+
+  format!(
+    "{:>width$}{}\n{:>width$}{}\n",
+    "└",
+    axis.into_iter().collect::<String>(),
+    "",
+    labels.into_iter().collect::<String>(),
+    width = AXIS_WIDTH
+  )
+}
+
+/// Bit of a braille pattern for a dot at column `x` (0-1) and row `y` (0-3).
+fn braille_bit(x: usize, y: usize) -> u8 {
+  match (x, y) {
+    (0, 3) => 0x40,
+    (1, 3) => 0x80,
+    (0, y) => 1 << y,
+    (_, y) => 1 << (y + 3),
+  }
+}
+
+/// Resample values to `count` points using linear interpolation.
+fn resample(values: &[f64], count: usize) -> Vec<f64> {
+  match (values.len(), count) {
+    (0, _) | (_, 0) => vec![],
+    (1, _) => vec![values[0]; count],
+    (_, 1) => vec![values[0]],
+    (len, _) => (0..count)
+      .map(|i| {
+        let position = i as f64 * (len - 1) as f64 / (count - 1) as f64;
+        let index = position.floor() as usize;
+        let next = (index + 1).min(len - 1);
+        let fraction = position - index as f64;
+        values[index] + (values[next] - values[index]) * fraction
+      })
+      .collect(),
+  }
+}
+
+/// Resample values to `count` points by taking the nearest value, for data that can not be interpolated.
+fn nearest(values: &[f64], count: usize) -> Vec<f64> {
+  match (values.len(), count) {
+    (0, _) | (_, 0) => vec![],
+    (len, 1) => vec![values[len / 2]],
+    (len, _) => (0..count)
+      .map(|i| values[((i * (len - 1)) as f64 / (count - 1) as f64).round() as usize])
+      .collect(),
+  }
+}
+
+fn trend(first: f64, last: f64) -> String {
+  let change = last - first;
+  match change {
+    change if change > 0.5 => format!("rising {:.1}", change),
+    change if change < -0.5 => format!("falling {:.1}", -change),
+    _ => "steady".to_string(),
+  }
+}
+
+fn min(values: &[f64]) -> f64 {
+  values.iter().copied().fold(f64::INFINITY, f64::min)
+}
+
+fn max(values: &[f64]) -> f64 {
+  values.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sparkline() {
+    assert_eq!(sparkline(&[0.0, 1.0, 2.0, 7.0]), "▁▂▃█");
+    assert_eq!(sparkline(&[3.0, 3.0]), "▄▄");
+  }
+
+  #[test]
+  fn test_arrows() {
+    assert_eq!(arrows(&[0.0, 90.0, 180.0, 270.0, 360.0]), "↓←↑→↓");
+    assert_eq!(arrows(&[190.0]), "↑");
+    assert_eq!(arrows(&[206.0]), "↗");
+  }
+
+  #[test]
+  fn test_resample() {
+    assert_eq!(resample(&[0.0, 10.0], 3), vec![0.0, 5.0, 10.0]);
+    assert_eq!(resample(&[4.0], 2), vec![4.0, 4.0]);
+    assert_eq!(
+      nearest(&[0.0, 90.0, 180.0], 5),
+      vec![0.0, 90.0, 90.0, 180.0, 180.0]
+    );
+  }
+
+  #[test]
+  fn test_line_chart_block() {
+    let values = [0.0, 1.0, 2.0, 3.0];
+    let chart = line_chart(
+      &[Series {
+        values: &values,
+        mark: '●',
+      }],
+      4,
+      4,
+      Mode::Block,
+    );
+
+    let lines: Vec<&str> = chart.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("   3.0 ┤"));
+    assert!(lines[0].ends_with("   ●"));
+    assert!(lines[3].starts_with("   0.0 ┤"));
+    assert!(lines[3].ends_with("●   "));
+  }
+
+  #[test]
+  fn test_line_chart_braille() {
+    let values = [0.0, 1.0];
+    let chart = line_chart(
+      &[Series {
+        values: &values,
+        mark: '●',
+      }],
+      1,
+      1,
+      Mode::Braille,
+    );
+
+    // Left column at the bottom, right column joined from the bottom to the top.
+    let expected = char::from_u32(0x2800 + (0x40 | 0x08 | 0x10 | 0x20 | 0x80)).unwrap();
+    assert!(chart.trim_end().ends_with(expected));
+  }
+
+  #[test]
+  fn test_bar_chart() {
+    let chart = bar_chart(&[0.0, 50.0, 100.0], 100.0, 3, 2);
+    let lines: Vec<&str> = chart.lines().collect();
+    assert!(lines[0].ends_with("  █"));
+    assert!(lines[1].ends_with(" ██"));
+  }
+
+  #[test]
+  fn test_trend() {
+    assert_eq!(trend(1012.0, 1005.0), "falling 7.0");
+    assert_eq!(trend(1005.0, 1006.0), "rising 1.0");
+    assert_eq!(trend(1005.0, 1005.2), "steady");
+  }
+}
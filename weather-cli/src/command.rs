@@ -1,16 +1,43 @@
-use clap::{value_parser, Arg, Command};
+use std::{net::SocketAddr, path::PathBuf};
+
+use clap::{value_parser, Arg, ArgAction, Command};
+
+use weather_core::template::FILTERS;
 
 use crate::style::style;
 
 pub fn cli() -> Command {
-  // Verbose option. Defaults to false.
-  let verbose_option = Arg::new("verbose")
+  // Verbosity flag, counted. Given once for derived values and request logs, more for debug and trace logs.
+  let verbose_flag = Arg::new("verbose")
     .short('v')
     .long("verbose")
-    .value_parser(value_parser!(bool))
-    .default_value("false")
+    .action(ArgAction::Count)
+    .global(true)
+    .help("Output information more verbosely, -vv and -vvv log requests in more detail.");
+
+  // Log format option. Defaults to text.
+  let log_format_option = Arg::new("log_format")
+    .long("log-format")
+    .value_parser(["text", "json"])
+    .default_value("text")
+    .global(true)
+    .help("Format of log lines.");
+
+  // Log file option. Defaults to stderr.
+  let log_file_option = Arg::new("log_file")
+    .long("log-file")
+    .value_parser(value_parser!(PathBuf))
     .required(false)
-    .help("Output information more verbosely.");
+    .global(true)
+    .help("Append logs to a file instead of printing them to stderr.");
+
+  // Provider option. Defaults to the provider API.
+  let provider_option = Arg::new("provider")
+    .long("provider")
+    .value_parser(["weatherapi", "fake"])
+    .default_value("weatherapi")
+    .global(true)
+    .help("Where weather comes from. fake makes it up, without network or API key.");
 
   // City option. Defaults to None.
   let city_option = Arg::new("city")
@@ -28,6 +55,13 @@ pub fn cli() -> Command {
     .required(false)
     .help("Post code to get the weather for.");
 
+  // Place option. Defaults to None.
+  let place_option = Arg::new("place")
+    .long("place")
+    .value_parser(value_parser!(String))
+    .required(false)
+    .help("Place from the config file to get the weather for.");
+
   // Days argument. Defaults to 1.
   let days_arg = Arg::new("days")
     .value_parser(value_parser!(i32))
@@ -35,39 +69,326 @@ pub fn cli() -> Command {
     .required(false)
     .help("How many days ahead to forecast to? Minimum value of 1 and maximum value of 5.");
 
+  // Minimum severity option. Defaults to None.
+  let min_severity_option = Arg::new("min_severity")
+    .long("min-severity")
+    .value_parser(["minor", "moderate", "severe", "extreme"])
+    .required(false)
+    .help("Only show alerts of at least this severity.");
+
+  // Event option. Can be given multiple times, defaults to None.
+  let event_option = Arg::new("event")
+    .short('e')
+    .long("event")
+    .value_parser(value_parser!(String))
+    .action(ArgAction::Append)
+    .required(false)
+    .help("Only show alerts whose event contains this text.");
+
+  // Active only flag. Defaults to false.
+  let active_only_flag = Arg::new("active_only")
+    .long("active-only")
+    .action(ArgAction::SetTrue)
+    .help("Only show alerts that are currently in effect.");
+
+  // New flag. Defaults to false.
+  let new_flag = Arg::new("new")
+    .long("new")
+    .action(ArgAction::SetTrue)
+    .help("Only show alerts not seen on a previous run, exits with 2 if any of them are severe.");
+
+  // Notify flag. Defaults to false.
+  let notify_flag = Arg::new("notify")
+    .long("notify")
+    .action(ArgAction::SetTrue)
+    .help("Send the shown alerts to the notification sinks in the config file.");
+
+  // Geofence flag. Defaults to false.
+  let geofence_flag = Arg::new("geofence")
+    .long("geofence")
+    .action(ArgAction::SetTrue)
+    .help("Only show alerts whose area contains the location, matching area names when an alert has no geometry.");
+
+  // CAP option. Defaults to None.
+  let cap_option = Arg::new("cap")
+    .long("cap")
+    .value_parser(value_parser!(String))
+    .required(false)
+    .help(
+      "Read CAP 1.2 alerts from a URL, a file, or - for standard input, instead of the provider.",
+    );
+
+  // Format option. Defaults to text.
+  let format_option = Arg::new("format")
+    .long("format")
+    .value_parser(["text", "atom", "rss"])
+    .default_value("text")
+    .required(false)
+    .help("Output alerts as text, or as an Atom or RSS feed.");
+
+  // Interval option. Defaults to 10 minutes.
+  let interval_option = Arg::new("interval")
+    .short('i')
+    .long("interval")
+    .value_parser(humantime::parse_duration)
+    .default_value("10m")
+    .required(false)
+    .help("How often to refresh, for example 30s, 10m or 1h.");
+
+  // Days option, for commands whose positional argument is taken. Defaults to 1.
+  let days_option = Arg::new("days")
+    .short('d')
+    .long("days")
+    .value_parser(value_parser!(i32))
+    .default_value("1")
+    .required(false)
+    .help("How many days ahead to forecast to? Minimum value of 1 and maximum value of 5.");
+
+  // Watch view argument. Defaults to current.
+  let view_arg = Arg::new("view")
+    .value_parser(["current", "forecast", "alert"])
+    .default_value("current")
+    .required(false)
+    .help("What to watch.");
+
+  // Chart flag. Defaults to false.
+  let chart_flag = Arg::new("chart")
+    .long("chart")
+    .action(ArgAction::SetTrue)
+    .conflicts_with("template")
+    .help("Draw hourly charts of the forecast.");
+
+  // Braille flag. Defaults to false.
+  let braille_flag = Arg::new("braille")
+    .long("braille")
+    .action(ArgAction::SetTrue)
+    .requires("chart")
+    .help("Draw charts in high resolution using braille characters.");
+
+  // Bind option. Defaults to 127.0.0.1:8080.
+  let bind_option = Arg::new("bind")
+    .short('b')
+    .long("bind")
+    .value_parser(value_parser!(SocketAddr))
+    .default_value("127.0.0.1:8080")
+    .required(false)
+    .help("Address to listen on.");
+
+  // Public URL option. Defaults to the bound address.
+  let public_url_option = Arg::new("public_url")
+    .long("public-url")
+    .value_parser(value_parser!(String))
+    .required(false)
+    .help(
+      "URL the API is reached at, for links to it, when behind a proxy or bound to all addresses.",
+    );
+
+  // Broker option. Defaults to the broker in the config file.
+  let broker_option = Arg::new("broker")
+    .long("broker")
+    .value_parser(value_parser!(String))
+    .required(false)
+    .help("MQTT broker to publish to, as host:port.");
+
+  // Discovery flag. Defaults to false.
+  let discovery_flag = Arg::new("discovery")
+    .long("discovery")
+    .action(ArgAction::SetTrue)
+    .help("Announce sensors to Home Assistant.");
+
+  // Since option. Defaults to 7 days.
+  let since_option = Arg::new("since")
+    .long("since")
+    .value_parser(humantime::parse_duration)
+    .default_value("7d")
+    .required(false)
+    .help("How far back to look, for example 12h, 7d or 30d.");
+
+  // Output option. Defaults to standard output.
+  let output_option = Arg::new("output")
+    .short('o')
+    .long("output")
+    .value_parser(value_parser!(PathBuf))
+    .required(false)
+    .help("File to write to instead of standard output.");
+
+  // Template option. Defaults to None.
+  let template_option = Arg::new("template")
+    .short('t')
+    .long("template")
+    .value_parser(value_parser!(String))
+    .required(false)
+    .help("Print with a template such as '{temp}°{unit} {condition.glyph}', or a named template from the config file.")
+    .long_help(template_help());
+
+  // Protocol option. Defaults to plain text.
+  let protocol_option = Arg::new("protocol")
+    .long("protocol")
+    .value_parser(["plain", "waybar", "i3bar", "tmux"])
+    .default_value("plain")
+    .required(false)
+    .help("Print for a status bar: plain text, waybar or i3bar JSON, or tmux style escapes.");
+
+  // Language option. Defaults to the language of the LANG environment variable.
+  let lang_option = Arg::new("lang")
+    .long("lang")
+    .value_parser(value_parser!(String))
+    .required(false)
+    .global(true)
+    .help("Language of conditions and labels, for example de or pt_BR. Defaults to $LANG.");
+
   Command::new("weather")
     .about("Weather!")
     .subcommand_required(true)
     .arg_required_else_help(true)
     .styles(style())
+    .arg(lang_option)
+    .arg(verbose_flag)
+    .arg(log_format_option)
+    .arg(log_file_option)
+    .arg(provider_option)
     .subcommand(
       Command::new("alert")
         .about("Get the current alerts.")
         .arg(days_arg.clone())
-        .arg(verbose_option.clone())
         .arg(city_option.clone())
-        .arg(post_code_option.clone()),
+        .arg(post_code_option.clone())
+        .arg(place_option.clone())
+        .arg(min_severity_option)
+        .arg(event_option)
+        .arg(active_only_flag)
+        .arg(new_flag)
+        .arg(notify_flag)
+        .arg(format_option)
+        .arg(cap_option)
+        .arg(geofence_flag)
+        .arg(template_option.clone()),
     )
     .subcommand(
       Command::new("astronomy")
         .about("Get the current astronomy.")
-        .arg(verbose_option.clone())
         .arg(city_option.clone())
-        .arg(post_code_option.clone()),
+        .arg(post_code_option.clone())
+        .arg(place_option.clone())
+        .arg(template_option.clone()),
     )
     .subcommand(
       Command::new("current")
         .about("Get the current weather.")
-        .arg(verbose_option.clone())
         .arg(city_option.clone())
-        .arg(post_code_option.clone()),
+        .arg(post_code_option.clone())
+        .arg(place_option.clone())
+        .arg(template_option.clone()),
     )
     .subcommand(
       Command::new("forecast")
         .about("Get the forecasted weather.")
         .arg(days_arg.clone())
-        .arg(verbose_option.clone())
         .arg(city_option.clone())
-        .arg(post_code_option.clone()),
+        .arg(post_code_option.clone())
+        .arg(place_option.clone())
+        .arg(chart_flag)
+        .arg(braille_flag)
+        .arg(template_option.clone()),
+    )
+    .subcommand(
+      Command::new("bar")
+        .about("Print a single line for a status bar, with the forecast and alerts as a tooltip.")
+        .arg(days_option.clone().default_value("3"))
+        .arg(city_option.clone())
+        .arg(post_code_option.clone())
+        .arg(place_option.clone())
+        .arg(protocol_option)
+        .arg(template_option.clone()),
     )
+    .subcommand(
+      Command::new("watch")
+        .about("Watch the weather, refreshing it in place.")
+        .arg(view_arg)
+        .arg(interval_option.clone())
+        .arg(days_option.clone())
+        .arg(city_option.clone())
+        .arg(post_code_option.clone())
+        .arg(place_option.clone()),
+    )
+    .subcommand(
+      Command::new("tui")
+        .about("Show an interactive weather dashboard.")
+        .arg(interval_option.clone())
+        .arg(city_option.clone())
+        .arg(post_code_option.clone())
+        .arg(place_option.clone()),
+    )
+    .subcommand(
+      Command::new("serve")
+        .about("Serve the weather as a REST API.")
+        .arg(bind_option.clone())
+        .arg(public_url_option),
+    )
+    .subcommand(
+      Command::new("exporter")
+        .about("Export the current weather of configured places as Prometheus metrics.")
+        .arg(bind_option.clone().default_value("127.0.0.1:9184"))
+        .arg(interval_option.clone().default_value("5m"))
+        .arg(city_option.clone())
+        .arg(post_code_option.clone())
+        .arg(place_option.clone()),
+    )
+    .subcommand(
+      Command::new("mqtt")
+        .about("Publish the weather of configured places to an MQTT broker.")
+        .arg(broker_option)
+        .arg(discovery_flag)
+        .arg(interval_option.clone().default_value("5m"))
+        .arg(days_option.clone())
+        .arg(city_option.clone())
+        .arg(post_code_option.clone())
+        .arg(place_option.clone()),
+    )
+    .subcommand(
+      Command::new("log")
+        .about("Record the current weather in the local store.")
+        .arg(city_option.clone())
+        .arg(post_code_option.clone())
+        .arg(place_option.clone()),
+    )
+    .subcommand(
+      Command::new("stats")
+        .about("Summarise the weather recorded in the local store.")
+        .arg(place_option.clone().required(true))
+        .arg(since_option),
+    )
+    .subcommand(
+      Command::new("quota").about("Show the requests made with each API key against the quota."),
+    )
+    .subcommand(
+      Command::new("export")
+        .about("Export the weather to other applications.")
+        .subcommand_required(true)
+        .subcommand(
+          Command::new("ical")
+            .about("Export the forecast and alerts as an iCalendar file.")
+            .arg(days_option.default_value("3"))
+            .arg(output_option)
+            .arg(city_option.clone())
+            .arg(post_code_option.clone())
+            .arg(place_option.clone()),
+        ),
+    )
+}
+
+/// Long help of the template option, listing every filter.
+fn template_help() -> String {
+  let filters = FILTERS
+    .iter()
+    .map(|filter| format!("  {:<12}{}", filter.name, filter.help))
+    .collect::<Vec<_>>()
+    .join("\n");
+  format!(
+    "Print with a template instead of the usual output, or with a named template from the config file.\n\
+     Fields are written {{name}}, such as {{temp}}, {{unit}}, {{condition.glyph}} or {{wind.dir}}, \
+     and may be followed by filters, such as {{temp|c_to_f|round:1}}.\n\
+     Filters:\n{}",
+    filters
+  )
 }
@@ -0,0 +1,355 @@
+use std::{
+  collections::BTreeMap,
+  fmt::Write,
+  net::SocketAddr,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use chrono::Utc;
+use tokio::{net::TcpListener, signal, time::sleep};
+use weather_core::{
+  alert::Severity,
+  error::Error,
+  forecast::get_outlook,
+  location::Location,
+  redact::describe,
+  weather::{Units, Weather},
+};
+
+/// Severities reported by the alert metric, so every series is present even when zero.
+const SEVERITIES: [Severity; 5] = [
+  Severity::Unknown,
+  Severity::Minor,
+  Severity::Moderate,
+  Severity::Severe,
+  Severity::Extreme,
+];
+
+/// A metric taken from the current weather of each place.
+struct Gauge {
+  name: &'static str,
+  help: &'static str,
+  value: fn(&Weather) -> f64,
+}
+
+const GAUGES: [Gauge; 10] = [
+  Gauge {
+    name: "weather_temperature_celsius",
+    help: "Air temperature.",
+    value: |weather| weather.temperature,
+  },
+  Gauge {
+    name: "weather_feels_like_celsius",
+    help: "Feels like temperature.",
+    value: |weather| weather.feels_like,
+  },
+  Gauge {
+    name: "weather_dew_point_celsius",
+    help: "Dew point.",
+    value: |weather| weather.dew_point,
+  },
+  Gauge {
+    name: "weather_humidity_percent",
+    help: "Relative humidity.",
+    value: |weather| weather.humidity as f64,
+  },
+  Gauge {
+    name: "weather_pressure_millibars",
+    help: "Air pressure.",
+    value: |weather| weather.pressure,
+  },
+  Gauge {
+    name: "weather_wind_speed_kph",
+    help: "Wind speed.",
+    value: |weather| weather.wind_speed,
+  },
+  Gauge {
+    name: "weather_wind_gust_kph",
+    help: "Wind gust speed.",
+    value: |weather| weather.wind_gust_speed,
+  },
+  Gauge {
+    name: "weather_wind_direction_degrees",
+    help: "Direction the wind comes from.",
+    value: |weather| weather.wind_degree as f64,
+  },
+  Gauge {
+    name: "weather_uv_index",
+    help: "UV index.",
+    value: |weather| weather.uv,
+  },
+  Gauge {
+    name: "weather_precipitation_millimetres",
+    help: "Precipitation amount.",
+    value: |weather| weather.precipitation,
+  },
+];
+
+/// Latest conditions of a place.
+#[derive(Debug, Clone)]
+struct Sample {
+  weather: Weather,
+  alerts: BTreeMap<Severity, usize>,
+}
+
+/// Everything exported at `/metrics`, keyed by place name.
+#[derive(Debug, Default)]
+struct Metrics {
+  samples: BTreeMap<String, Sample>,
+  fetch_seconds: BTreeMap<String, f64>,
+  fetch_seconds_sum: BTreeMap<String, f64>,
+  fetches: BTreeMap<String, u64>,
+  errors: BTreeMap<String, u64>,
+  last_success: BTreeMap<String, i64>,
+}
+
+type Shared = Arc<Mutex<Metrics>>;
+
+/// Handle the exporter command.
+/// Fetches the current conditions of every place each `interval` and serves them at `/metrics`.
+pub async fn handle_exporter(
+  bind: SocketAddr,
+  places: Vec<(String, Location)>,
+  interval: Duration,
+) -> Result<(), Error> {
+  let metrics: Shared = Arc::default();
+
+  let listener = TcpListener::bind(bind).await.map_err(|err| Error::Error {
+    message: format!("failed to bind {}: {}", bind, err),
+  })?;
+  println!("Serving metrics on http://{}/metrics", bind);
+
+  tokio::spawn(collect(places, interval, metrics.clone()));
+
+  let router = Router::new()
+    .route("/metrics", get(serve_metrics))
+    .with_state(metrics);
+
+  axum::serve(listener, router)
+    .with_graceful_shutdown(async {
+      signal::ctrl_c().await.ok();
+    })
+    .await
+    .map_err(|err| Error::Error {
+      message: err.to_string(),
+    })
+}
+
+async fn serve_metrics(State(metrics): State<Shared>) -> impl IntoResponse {
+  let body = render(&metrics.lock().unwrap());
+  ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// Fetch every place forever, recording the results and the time taken.
+async fn collect(places: Vec<(String, Location)>, interval: Duration, metrics: Shared) {
+  loop {
+    for (name, location) in &places {
+      let started = Instant::now();
+      let result = fetch(location).await;
+      let elapsed = started.elapsed().as_secs_f64();
+
+      let mut metrics = metrics.lock().unwrap();
+      metrics.fetch_seconds.insert(name.clone(), elapsed);
+      *metrics.fetch_seconds_sum.entry(name.clone()).or_default() += elapsed;
+      *metrics.fetches.entry(name.clone()).or_default() += 1;
+
+      match result {
+        Ok(sample) => {
+          metrics.samples.insert(name.clone(), sample);
+          metrics
+            .last_success
+            .insert(name.clone(), Utc::now().timestamp());
+        }
+        Err(err) => {
+          *metrics.errors.entry(name.clone()).or_default() += 1;
+          eprintln!("Error: failed to fetch {}: {}", name, describe(&err));
+        }
+      }
+    }
+
+    sleep(interval).await;
+  }
+}
+
+/// The weather and alerts of a place come from a single request, so neither goes stale alone.
+async fn fetch(location: &Location) -> Result<Sample, Error> {
+  let outlook = get_outlook(location.clone(), Units::Metric, 1).await?;
+  let alerts = outlook.alerts.dedup();
+
+  let mut counts = BTreeMap::new();
+  for alert in alerts
+    .alerts
+    .iter()
+    .filter(|alert| alert.is_active(Utc::now()))
+  {
+    *counts.entry(alert.severity_level()).or_default() += 1;
+  }
+
+  Ok(Sample {
+    weather: outlook.weather,
+    alerts: counts,
+  })
+}
+
+/// Render the metrics in the Prometheus text exposition format.
+fn render(metrics: &Metrics) -> String {
+  let mut output = String::new();
+
+  for gauge in GAUGES {
+    header(&mut output, gauge.name, gauge.help, "gauge");
+    for (place, sample) in &metrics.samples {
+      writeln!(
+        output,
+        "{}{{place=\"{}\"}} {}",
+        gauge.name,
+        escape(place),
+        (gauge.value)(&sample.weather)
+      )
+      .unwrap();
+    }
+  }
+
+  header(
+    &mut output,
+    "weather_alerts_active",
+    "Active alerts by severity.",
+    "gauge",
+  );
+  for (place, sample) in &metrics.samples {
+    for severity in SEVERITIES {
+      writeln!(
+        output,
+        "weather_alerts_active{{place=\"{}\",severity=\"{}\"}} {}",
+        escape(place),
+        severity.to_string().to_lowercase(),
+        sample.alerts.get(&severity).copied().unwrap_or(0)
+      )
+      .unwrap();
+    }
+  }
+
+  header(
+    &mut output,
+    "weather_exporter_fetch_duration_seconds",
+    "Time taken by upstream fetches.",
+    "summary",
+  );
+  for (place, sum) in &metrics.fetch_seconds_sum {
+    let count = metrics.fetches.get(place).copied().unwrap_or(0);
+    let place = escape(place);
+    writeln!(
+      output,
+      "weather_exporter_fetch_duration_seconds_sum{{place=\"{}\"}} {}",
+      place, sum
+    )
+    .unwrap();
+    writeln!(
+      output,
+      "weather_exporter_fetch_duration_seconds_count{{place=\"{}\"}} {}",
+      place, count
+    )
+    .unwrap();
+  }
+
+  header(
+    &mut output,
+    "weather_exporter_last_fetch_duration_seconds",
+    "Time taken by the most recent upstream fetch.",
+    "gauge",
+  );
+  for (place, seconds) in &metrics.fetch_seconds {
+    writeln!(
+      output,
+      "weather_exporter_last_fetch_duration_seconds{{place=\"{}\"}} {}",
+      escape(place),
+      seconds
+    )
+    .unwrap();
+  }
+
+  header(
+    &mut output,
+    "weather_exporter_upstream_errors_total",
+    "Upstream fetches that failed.",
+    "counter",
+  );
+  for place in metrics.fetches.keys() {
+    writeln!(
+      output,
+      "weather_exporter_upstream_errors_total{{place=\"{}\"}} {}",
+      escape(place),
+      metrics.errors.get(place).copied().unwrap_or(0)
+    )
+    .unwrap();
+  }
+
+  header(
+    &mut output,
+    "weather_exporter_last_success_timestamp_seconds",
+    "Unix time of the last successful fetch.",
+    "gauge",
+  );
+  for (place, timestamp) in &metrics.last_success {
+    writeln!(
+      output,
+      "weather_exporter_last_success_timestamp_seconds{{place=\"{}\"}} {}",
+      escape(place),
+      timestamp
+    )
+    .unwrap();
+  }
+
+  output
+}
+
+fn header(output: &mut String, name: &str, help: &str, kind: &str) {
+  writeln!(output, "# HELP {} {}", name, help).unwrap();
+  writeln!(output, "# TYPE {} {}", name, kind).unwrap();
+}
+
+/// Escape a label value as required by the exposition format.
+fn escape(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_render() {
+    let mut metrics = Metrics::default();
+    metrics.samples.insert(
+      "office".to_string(),
+      Sample {
+        weather: Weather::sample(),
+        alerts: BTreeMap::from([(Severity::Severe, 2)]),
+      },
+    );
+    metrics.fetch_seconds.insert("office".to_string(), 0.25);
+    metrics.fetch_seconds_sum.insert("office".to_string(), 0.75);
+    metrics.fetches.insert("office".to_string(), 3);
+    metrics.errors.insert("office".to_string(), 1);
+
+    let output = render(&metrics);
+    assert!(output.contains("# TYPE weather_temperature_celsius gauge\n"));
+    assert!(output.contains("weather_temperature_celsius{place=\"office\"} 5.1\n"));
+    assert!(output.contains("weather_humidity_percent{place=\"office\"} 100\n"));
+    assert!(output.contains("weather_wind_direction_degrees{place=\"office\"} 206\n"));
+    assert!(output.contains("weather_alerts_active{place=\"office\",severity=\"severe\"} 2\n"));
+    assert!(output.contains("weather_alerts_active{place=\"office\",severity=\"minor\"} 0\n"));
+    assert!(output.contains("weather_exporter_fetch_duration_seconds_sum{place=\"office\"} 0.75\n"));
+    assert!(output.contains("weather_exporter_fetch_duration_seconds_count{place=\"office\"} 3\n"));
+    assert!(output.contains("weather_exporter_upstream_errors_total{place=\"office\"} 1\n"));
+  }
+
+  #[test]
+  fn test_escape() {
+    assert_eq!(escape("a \"b\"\\c\n"), "a \\\"b\\\"\\\\c\\n");
+  }
+}
@@ -1,98 +1,357 @@
+use std::{fs, path::PathBuf, process::ExitCode, time::Duration};
+
+use chrono::Utc;
+use serde_json::Value;
 use weather_core::{
-  alert::get_alerts,
+  alert::{get_alerts, Alerts, Filter, Severity},
   astronomy::get_current_astronomy,
+  cap::get_cap,
+  config::Config,
+  derive::Derived,
   error::Error,
-  ip::get_public_ip,
-  location::get_location_from_ip,
+  feed::{self, Format},
+  forecast::get_forecast,
+  ical::calendar,
+  keys::{Cooldowns, KeyRing},
+  location::Location,
+  notify::{notify, Sink},
+  quota::{Quota, UsageLog},
+  redact::describe,
+  state::AlertState,
+  store::{Report, Store},
+  template::{astronomy_context, context, day_context, weather_context, Template},
   weather::{get_current_weather, get_forecast_weather, Units},
 };
 
+use crate::chart::{forecast_charts, terminal_width, Mode};
 use crate::validate::validate_days;
 
-pub async fn handle_alerts(days: i32) -> Result<(), Error> {
-  println!("Weather alerts");
+/// Where the alert command reads alerts from.
+pub enum Source {
+  /// The weather provider, for a location and number of days.
+  Provider(Location, i32),
+  /// A CAP document at a URL, in a file, or `-` for standard input.
+  Cap(String),
+}
+
+impl Source {
+  fn name(&self) -> String {
+    match self {
+      Source::Provider(location, _) => location.to_string(),
+      Source::Cap(source) if source == "-" => "standard input".to_string(),
+      Source::Cap(source) => source.clone(),
+    }
+  }
 
-  let days_valid = validate_days(days);
-  if days_valid.is_err() {
-    return days_valid;
+  async fn alerts(self) -> Result<Alerts, Error> {
+    match self {
+      Source::Provider(location, days) => {
+        validate_days(days)?;
+        get_alerts(location, days).await
+      }
+      Source::Cap(source) => get_cap(&source).await,
+    }
   }
+}
 
-  match get_public_ip().await {
-    Ok(ip) => {
-      let location = get_location_from_ip(&ip).await.unwrap();
-      println!("{:?}", location.to_string());
+/// How the alert command prints alerts.
+pub enum Output {
+  Text,
+  Feed(Format),
+  /// One line per alert, rendered with a template.
+  Template(Template),
+}
 
-      let alerts = get_alerts(location.clone(), days).await.unwrap();
-      println!("{:?}", alerts.to_string());
+/// Handle the alert command.
+/// When `new_only` is set only alerts not seen on a previous run are printed,
+/// and the exit code is 2 if any of those are severe or worse.
+/// When `notify` is set the printed alerts are also sent to the configured sinks.
+/// When printing a feed or a template only the alerts are printed, so they can be redirected.
+pub async fn handle_alerts(
+  source: Source,
+  filter: Filter,
+  new_only: bool,
+  notify: bool,
+  output: Output,
+  config: &Config,
+) -> Result<ExitCode, Error> {
+  let place = source.name();
+  if let Output::Text = output {
+    println!("Weather alerts");
+    println!("{:?}", place);
+  }
 
-      Ok(())
+  let alerts = source.alerts().await?;
+  let alerts = alerts.dedup().filter(&filter, Utc::now());
+
+  if !new_only {
+    print_alerts(&alerts, &place, &output)?;
+    if notify {
+      notify_alerts(&alerts, config).await;
     }
-    Err(err) => Err(Error::Error {
-      message: err.to_string(),
-    }),
+    return Ok(ExitCode::SUCCESS);
   }
-}
 
-pub async fn handle_astronomy() -> Result<(), Error> {
-  println!("Current astronomy");
-  match get_public_ip().await {
-    Ok(ip) => {
-      let location = get_location_from_ip(&ip).await.unwrap();
-      println!("{:?}", location.to_string());
+  let path = AlertState::default_path();
+  let mut state = AlertState::load(&path)?;
+  state.prune(Utc::now());
 
-      let astronomy = get_current_astronomy(location.clone()).await.unwrap();
-      println!("{:?}", astronomy.to_string());
+  let unseen = state.unseen(&alerts);
+  state.mark(&alerts);
+  state.save(&path)?;
 
-      Ok(())
+  print_alerts(&unseen, &place, &output)?;
+  if notify {
+    notify_alerts(&unseen, config).await;
+  }
+
+  let severe = unseen
+    .alerts
+    .iter()
+    .any(|alert| alert.severity_level() >= Severity::Severe);
+  match severe {
+    true => Ok(ExitCode::from(2)),
+    false => Ok(ExitCode::SUCCESS),
+  }
+}
+
+fn print_alerts(alerts: &Alerts, place: &str, output: &Output) -> Result<(), Error> {
+  match output {
+    Output::Text => println!("{:?}", alerts.to_string()),
+    Output::Feed(format) => print!("{}", feed::render(*format, place, alerts, Utc::now(), None)),
+    Output::Template(template) => {
+      for alert in &alerts.alerts {
+        println!("{}", template.render(&with_place(context(alert), place))?);
+      }
     }
-    Err(err) => Err(Error::Error {
-      message: err.to_string(),
-    }),
+  }
+  Ok(())
+}
+
+/// Add the name of the place to a template context.
+fn with_place(mut context: Value, place: &str) -> Value {
+  context["place"] = Value::String(place.to_string());
+  context
+}
+
+/// Send alerts to every sink in the config file.
+/// Failing sinks are reported but do not fail the command.
+async fn notify_alerts(alerts: &Alerts, config: &Config) {
+  let sinks: Vec<Sink> = (&config.notify).into();
+  if sinks.is_empty() {
+    eprintln!(
+      "No notification sinks configured in {}",
+      Config::path().display()
+    );
+    return;
+  }
+
+  for err in notify(&sinks, alerts).await {
+    eprintln!("Error: {}", describe(&err));
   }
 }
 
-pub async fn handle_current() -> Result<(), Error> {
+/// Handle the astronomy command.
+/// When `template` is set only the rendered template is printed.
+pub async fn handle_astronomy(location: Location, template: Option<Template>) -> Result<(), Error> {
+  if let Some(template) = template {
+    let place = location.to_string();
+    let astronomy = get_current_astronomy(location).await?;
+    println!(
+      "{}",
+      template.render(&with_place(astronomy_context(&astronomy), &place))?
+    );
+    return Ok(());
+  }
+
+  println!("Current astronomy");
+  println!("{:?}", location.to_string());
+
+  let astronomy = get_current_astronomy(location).await?;
+  println!("{:?}", astronomy.to_string());
+
+  Ok(())
+}
+
+/// Handle the current command.
+/// When `verbose` is set values derived from the weather are printed too.
+/// When `template` is set only the rendered template is printed.
+pub async fn handle_current(
+  location: Location,
+  verbose: bool,
+  template: Option<Template>,
+) -> Result<(), Error> {
+  if let Some(template) = template {
+    let place = location.to_string();
+    let weather = get_current_weather(location, Units::Metric).await?;
+    println!(
+      "{}",
+      template.render(&with_place(weather_context(&weather), &place))?
+    );
+    return Ok(());
+  }
+
   println!("Current weather");
-  match get_public_ip().await {
-    Ok(ip) => {
-      let location = get_location_from_ip(&ip).await.unwrap();
-      println!("{:?}", location.to_string());
+  println!("{:?}", location.to_string());
 
-      let weather = get_current_weather(location.clone(), Units::Metric)
-        .await
-        .unwrap();
-      println!("{:?}", weather.to_string());
+  let weather = get_current_weather(location, Units::Metric).await?;
+  println!("{:?}", weather.to_string());
+  if verbose {
+    println!("{:?}", Derived::from(&weather).to_string());
+  }
 
-      Ok(())
+  Ok(())
+}
+
+/// Handle the forecast command.
+/// When `chart` is set hourly charts are drawn at the width of the terminal.
+/// When `verbose` is set values derived from the weather are printed too.
+/// When `template` is set only the template is printed, rendered once for each day.
+pub async fn handle_forecast(
+  location: Location,
+  days: i32,
+  chart: Option<Mode>,
+  verbose: bool,
+  template: Option<Template>,
+) -> Result<(), Error> {
+  if let Some(template) = template {
+    validate_days(days)?;
+    let place = location.to_string();
+    let forecast = get_forecast(location, Units::Metric, days).await?;
+    for day in &forecast.days {
+      println!(
+        "{}",
+        template.render(&with_place(day_context(day, Units::Metric), &place))?
+      );
     }
-    Err(err) => Err(Error::Error {
+    return Ok(());
+  }
+
+  println!("Weather forecast");
+
+  validate_days(days)?;
+
+  println!("{:?}", location.to_string());
+
+  if let Some(mode) = chart {
+    let forecast = get_forecast(location, Units::Metric, days).await?;
+    print!(
+      "{}",
+      forecast_charts(&forecast, terminal_width(), mode, ["°C", "km/h", "mb"])
+    );
+    return Ok(());
+  }
+
+  let weather = get_forecast_weather(location, Units::Metric, days).await?;
+  println!("{:?}", weather.to_string());
+  if verbose {
+    println!("{:?}", Derived::from(&weather).to_string());
+  }
+
+  Ok(())
+}
+
+/// Handle the log command.
+/// Fetches the current weather of every place, reporting places that fail without stopping.
+/// The weather is recorded by the store recorder, like that of every other command.
+pub async fn handle_log(places: Vec<(String, Location)>) -> Result<(), Error> {
+  for (name, location) in places {
+    match get_current_weather(location, Units::Metric).await {
+      Ok(weather) => println!("Logged {}: {}", name, weather.to_string()),
+      Err(err) => eprintln!("Error: failed to fetch {}: {}", name, describe(&err)),
+    }
+  }
+
+  Ok(())
+}
+
+/// Handle the stats command.
+/// Only reads the local store, so works offline.
+pub fn handle_stats(store: &Store, place: &str, since: Duration) -> Result<(), Error> {
+  let since = Utc::now()
+    - chrono::Duration::from_std(since).map_err(|err| Error::InvalidArgument {
+      arg: "since".to_string(),
       message: err.to_string(),
-    }),
+    })?;
+
+  let samples = store.samples(place, since)?;
+  match Report::new(place, &samples) {
+    Some(report) => println!("{}", report.to_string()),
+    None => println!("No samples of {} since {}", place, since.to_rfc3339()),
   }
+
+  Ok(())
 }
 
-pub async fn handle_forecast(days: i32) -> Result<(), Error> {
-  println!("Weather forecast");
+/// Handle the quota command.
+/// Shows the requests made with every key used so far, and with the configured keys even if unused.
+/// Keys that are no longer configured are shown by the fingerprint their usage is logged under.
+/// Configured keys are listed with whether they are cooling down.
+pub fn handle_quota(config: &Config) -> Result<(), Error> {
+  let path = UsageLog::default_path();
+  let log = UsageLog::load(&path)?;
+  let now = Utc::now();
 
-  let days_valid = validate_days(days);
-  if days_valid.is_err() {
-    return days_valid;
+  let mut usages = log.all(now);
+  let configured = config.keys.iter().map(|key| key.key.clone());
+  let current = std::env::var("WEATHER_KEY").ok();
+  for key in configured.chain(current) {
+    let usage = log.usage(&key, now);
+    match usages.iter_mut().find(|logged| logged.key == usage.key) {
+      Some(logged) => *logged = usage,
+      None => usages.push(usage),
+    }
   }
 
-  match get_public_ip().await {
-    Ok(ip) => {
-      let location = get_location_from_ip(&ip).await.unwrap();
-      println!("{:?}", location.to_string());
+  if !config.keys.is_empty() {
+    let keys = KeyRing::new(
+      &config.keys,
+      Duration::from_secs(config.quota.cooldown),
+      Cooldowns::default_path(),
+      |_| {},
+    );
+    for status in keys.status(now)? {
+      println!("{}", status);
+    }
+  }
 
-      let weather = get_forecast_weather(location.clone(), Units::Metric, days)
-        .await
-        .unwrap();
-      println!("{:?}", weather.to_string());
+  if usages.is_empty() {
+    println!("No requests recorded");
+    return Ok(());
+  }
 
-      Ok(())
+  let quota = Quota::new(&config.quota, path, |_| {});
+  for usage in usages {
+    println!("{}", quota.report(&usage));
+    if let Some(warning) = quota.warning(&usage) {
+      println!("Warning: {}", warning);
     }
-    Err(err) => Err(Error::Error {
+  }
+
+  Ok(())
+}
+
+/// Handle the export ical command.
+/// Writes the calendar to `output` if given, otherwise prints it.
+pub async fn handle_export_ical(
+  location: Location,
+  days: i32,
+  output: Option<&PathBuf>,
+) -> Result<(), Error> {
+  validate_days(days)?;
+
+  let forecast = get_forecast(location.clone(), Units::Metric, days).await?;
+  let alerts = get_alerts(location.clone(), days).await?.dedup();
+  let calendar = calendar(&location, &forecast, &alerts, Utc::now());
+
+  match output {
+    Some(path) => fs::write(path, calendar).map_err(|err| Error::Io {
+      path: path.display().to_string(),
       message: err.to_string(),
     }),
+    None => {
+      print!("{}", calendar);
+      Ok(())
+    }
   }
 }
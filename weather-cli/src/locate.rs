@@ -0,0 +1,87 @@
+use clap::ArgMatches;
+use serde::Deserialize;
+use weather_core::{
+  config::Config,
+  error::Error,
+  ip::get_public_ip,
+  location::{get_location_from_ip, get_location_from_place, search_location, Location},
+  store,
+};
+
+/// Ways a location can be asked for, by command line arguments or query parameters.
+#[derive(Debug, Default, Deserialize)]
+pub struct LocationQuery {
+  pub place: Option<String>,
+  pub city: Option<String>,
+  #[serde(alias = "postcode")]
+  pub post_code: Option<String>,
+  pub lat: Option<f64>,
+  pub lon: Option<f64>,
+}
+
+impl LocationQuery {
+  /// Resolve the location asked for.
+  /// In order of preference: coordinates, a configured place, a city, then a post code.
+  /// Returns `None` when no location was asked for.
+  pub async fn resolve(&self, config: &Config) -> Result<Option<Location>, Error> {
+    if let (Some(lat), Some(lon)) = (self.lat, self.lon) {
+      return Ok(Some(Location {
+        country: String::new(),
+        region: None,
+        city: None,
+        lat: Some(lat),
+        lon: Some(lon),
+      }));
+    }
+
+    if let Some(name) = &self.place {
+      return locate_place(name, config).await.map(Some);
+    }
+
+    match self.city.as_ref().or(self.post_code.as_ref()) {
+      Some(query) => search_location(query).await.map(Some),
+      None => Ok(None),
+    }
+  }
+}
+
+impl From<&ArgMatches> for LocationQuery {
+  fn from(args: &ArgMatches) -> Self {
+    let get = |arg: &str| args.try_get_one::<String>(arg).ok().flatten().cloned();
+    LocationQuery {
+      place: get("place"),
+      city: get("city"),
+      post_code: get("post_code"),
+      lat: None,
+      lon: None,
+    }
+  }
+}
+
+/// Resolve the location a command should use.
+/// Falls back to the public IP address when no location was given.
+pub async fn resolve_location(args: &ArgMatches, config: &Config) -> Result<Location, Error> {
+  match LocationQuery::from(args).resolve(config).await? {
+    Some(location) => Ok(location),
+    None => locate_ip().await,
+  }
+}
+
+/// Resolve a place from the config file by name.
+pub async fn locate_place(name: &str, config: &Config) -> Result<Location, Error> {
+  match config.place(name) {
+    Some(place) => {
+      let location = get_location_from_place(place).await?;
+      store::name_place(&location, &place.name);
+      Ok(location)
+    }
+    None => Err(Error::UnknownLocation {
+      location: name.to_string(),
+    }),
+  }
+}
+
+/// Resolve the location of the public IP address of this machine.
+pub async fn locate_ip() -> Result<Location, Error> {
+  get_location_from_ip(&get_public_ip().await?).await
+}
@@ -0,0 +1,109 @@
+use std::{fs::OpenOptions, path::PathBuf, sync::Mutex};
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{
+  filter::Targets, fmt, fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt,
+  Layer,
+};
+use weather_core::error::Error;
+
+/// How log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+  /// One line of text for each event, with the fields of its spans.
+  Text,
+  /// One JSON object for each event, for log collectors.
+  Json,
+}
+
+impl From<&str> for LogFormat {
+  fn from(format: &str) -> Self {
+    match format {
+      "json" => LogFormat::Json,
+      _ => LogFormat::Text,
+    }
+  }
+}
+
+/// Level of the events of this application, by the number of times `-v` was given.
+/// Warnings for the user, such as the quota running low, are always shown.
+/// Events of dependencies are only shown from `-vvv` on.
+fn levels(verbosity: u8) -> Targets {
+  let level = match verbosity {
+    0 => LevelFilter::ERROR,
+    1 => LevelFilter::INFO,
+    2 => LevelFilter::DEBUG,
+    _ => LevelFilter::TRACE,
+  };
+  let dependencies = match verbosity {
+    0..=2 => LevelFilter::WARN.min(level),
+    _ => LevelFilter::TRACE,
+  };
+  Targets::new()
+    .with_target("weather_core", level)
+    .with_target("weather_cli", level)
+    .with_target("weather_cli::app", LevelFilter::WARN.max(level))
+    .with_default(dependencies)
+}
+
+/// Start logging to stderr, or appending to `file` when given.
+/// Logging to a file is at least at the info level, so the file is worth keeping.
+pub fn init_logging(verbosity: u8, format: LogFormat, file: Option<&PathBuf>) -> Result<(), Error> {
+  let (writer, verbosity) = match file {
+    Some(path) => {
+      let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| Error::Io {
+          path: path.display().to_string(),
+          message: err.to_string(),
+        })?;
+      (BoxMakeWriter::new(Mutex::new(file)), verbosity.max(1))
+    }
+    None => (BoxMakeWriter::new(std::io::stderr), verbosity),
+  };
+
+  let layer = fmt::layer().with_writer(writer).with_ansi(file.is_none());
+  let layer = match format {
+    LogFormat::Text => layer.boxed(),
+    LogFormat::Json => layer.json().with_current_span(true).boxed(),
+  };
+
+  tracing_subscriber::registry()
+    .with(layer.with_filter(levels(verbosity)))
+    .try_init()
+    .map_err(|err| Error::Error {
+      message: err.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tracing::Level;
+
+  #[test]
+  fn test_levels() {
+    let quiet = levels(0);
+    assert!(quiet.would_enable("weather_core::http", &Level::ERROR));
+    assert!(!quiet.would_enable("weather_core::http", &Level::WARN));
+    assert!(!quiet.would_enable("hyper", &Level::WARN));
+    assert!(quiet.would_enable("weather_cli::app", &Level::WARN));
+    assert!(!quiet.would_enable("weather_cli::app", &Level::INFO));
+
+    let verbose = levels(2);
+    assert!(verbose.would_enable("weather_core::http", &Level::DEBUG));
+    assert!(!verbose.would_enable("weather_core::http", &Level::TRACE));
+    assert!(verbose.would_enable("hyper", &Level::WARN));
+    assert!(!verbose.would_enable("hyper", &Level::INFO));
+
+    assert!(levels(3).would_enable("hyper", &Level::TRACE));
+  }
+
+  #[test]
+  fn test_log_format() {
+    assert_eq!(LogFormat::from("json"), LogFormat::Json);
+    assert_eq!(LogFormat::from("text"), LogFormat::Text);
+  }
+}
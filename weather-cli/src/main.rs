@@ -1,13 +1,29 @@
 mod app;
+mod bar;
+mod chart;
 mod command;
+mod exporter;
 mod handler;
+mod locate;
+mod logging;
+mod mqtt;
+mod serve;
 mod style;
+mod tui;
 mod validate;
+mod watch;
+
+use std::process::ExitCode;
+
+use weather_core::redact::describe;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
   match app::run().await {
-    Ok(_) => (),
-    Err(err) => eprintln!("Error: {:?}", err),
+    Ok(code) => code,
+    Err(err) => {
+      eprintln!("Error: {}", describe(&err));
+      ExitCode::FAILURE
+    }
   }
 }
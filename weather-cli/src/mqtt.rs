@@ -0,0 +1,471 @@
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, EventLoop, MqttOptions, QoS};
+use serde_json::{json, Value};
+use tokio::{select, signal, time::sleep};
+use weather_core::{
+  alert::Alerts,
+  config::MqttConfig,
+  error::Error,
+  forecast::{get_outlook, Forecast},
+  location::Location,
+  redact::describe,
+  weather::{Units, Weather},
+};
+
+/// A message to publish to the broker.
+#[derive(Debug, Clone, PartialEq)]
+struct Message {
+  topic: String,
+  payload: String,
+  retain: bool,
+}
+
+/// A sensor announced to Home Assistant, read from the current conditions topic.
+struct Sensor {
+  key: &'static str,
+  name: &'static str,
+  unit: Option<&'static str>,
+  device_class: Option<&'static str>,
+}
+
+const SENSORS: [Sensor; 7] = [
+  Sensor {
+    key: "temperature",
+    name: "Temperature",
+    unit: Some("°C"),
+    device_class: Some("temperature"),
+  },
+  Sensor {
+    key: "feels_like",
+    name: "Feels like",
+    unit: Some("°C"),
+    device_class: Some("temperature"),
+  },
+  Sensor {
+    key: "humidity",
+    name: "Humidity",
+    unit: Some("%"),
+    device_class: Some("humidity"),
+  },
+  Sensor {
+    key: "pressure",
+    name: "Pressure",
+    unit: Some("mbar"),
+    device_class: Some("pressure"),
+  },
+  Sensor {
+    key: "wind_speed",
+    name: "Wind speed",
+    unit: Some("km/h"),
+    device_class: Some("wind_speed"),
+  },
+  Sensor {
+    key: "uv",
+    name: "UV index",
+    unit: None,
+    device_class: None,
+  },
+  Sensor {
+    key: "condition",
+    name: "Condition",
+    unit: None,
+    device_class: None,
+  },
+];
+
+/// Handle the mqtt command.
+/// Publishes the conditions, forecast and alerts of every place each `interval` until interrupted.
+pub async fn handle_mqtt(
+  places: Vec<(String, Location)>,
+  days: i32,
+  interval: Duration,
+  config: &MqttConfig,
+) -> Result<(), Error> {
+  let (client, eventloop) = connect(config)?;
+  let connection = tokio::spawn(drive(eventloop));
+  println!("Publishing to mqtt://{}", config.broker);
+
+  if config.discovery {
+    for (name, _) in &places {
+      publish(&client, discovery(name, config)).await?;
+    }
+  }
+
+  loop {
+    for (name, location) in &places {
+      match fetch(location, days).await {
+        Ok((weather, forecast, alerts)) => {
+          publish(
+            &client,
+            messages(name, &weather, &forecast, &alerts, config),
+          )
+          .await?
+        }
+        Err(err) => eprintln!("Error: failed to fetch {}: {}", name, describe(&err)),
+      }
+    }
+
+    select! {
+      _ = sleep(interval) => {}
+      _ = signal::ctrl_c() => break,
+    }
+  }
+
+  client.disconnect().await.ok();
+  connection.abort();
+  Ok(())
+}
+
+/// Create a client for the configured broker.
+/// Nothing is sent until the event loop is polled.
+fn connect(config: &MqttConfig) -> Result<(AsyncClient, EventLoop), Error> {
+  let (host, port) = broker_address(&config.broker)?;
+
+  let mut options = MqttOptions::new(config.client_id.clone(), host, port);
+  options.set_keep_alive(Duration::from_secs(30));
+  if let Some(username) = &config.username {
+    options.set_credentials(
+      username.clone(),
+      config.password.clone().unwrap_or_default(),
+    );
+  }
+
+  Ok(AsyncClient::new(options, 64))
+}
+
+/// Poll the event loop forever, which sends queued messages and reconnects when the broker drops.
+async fn drive(mut eventloop: EventLoop) {
+  loop {
+    if let Err(err) = eventloop.poll().await {
+      eprintln!("Error: mqtt connection failed: {}", err);
+      sleep(Duration::from_secs(5)).await;
+    }
+  }
+}
+
+async fn publish(client: &AsyncClient, messages: Vec<Message>) -> Result<(), Error> {
+  for message in messages {
+    client
+      .publish(
+        message.topic,
+        QoS::AtLeastOnce,
+        message.retain,
+        message.payload,
+      )
+      .await
+      .map_err(|err| Error::Error {
+        message: format!("failed to publish: {}", err),
+      })?;
+  }
+  Ok(())
+}
+
+async fn fetch(location: &Location, days: i32) -> Result<(Weather, Forecast, Alerts), Error> {
+  let outlook = get_outlook(location.clone(), Units::Metric, days).await?;
+  Ok((outlook.weather, outlook.forecast, outlook.alerts.dedup()))
+}
+
+/// Split a broker address such as `mqtt://localhost:1883` into host and port.
+fn broker_address(broker: &str) -> Result<(String, u16), Error> {
+  let address = broker.strip_prefix("mqtt://").unwrap_or(broker);
+  let invalid = || Error::InvalidArgument {
+    arg: "broker".to_string(),
+    message: format!("expected host:port, got {}", broker),
+  };
+
+  match address.rsplit_once(':') {
+    Some((host, port)) if !host.is_empty() => {
+      Ok((host.to_string(), port.parse().map_err(|_| invalid())?))
+    }
+    Some(_) => Err(invalid()),
+    None if !address.is_empty() => Ok((address.to_string(), 1883)),
+    None => Err(invalid()),
+  }
+}
+
+/// Messages holding the latest state of a place.
+fn messages(
+  place: &str,
+  weather: &Weather,
+  forecast: &Forecast,
+  alerts: &Alerts,
+  config: &MqttConfig,
+) -> Vec<Message> {
+  let summary: Vec<Value> = forecast
+    .days
+    .iter()
+    .map(|day| {
+      json!({
+        "date": day.date,
+        "condition": day.condition.text,
+        "high": day.max_temperature,
+        "low": day.min_temperature,
+        "chance_of_rain": day.chance_of_rain,
+        "chance_of_snow": day.chance_of_snow,
+      })
+    })
+    .collect();
+
+  vec![
+    Message {
+      topic: topic(&config.current_topic, place),
+      payload: serde_json::to_string(weather).unwrap(),
+      retain: config.retain,
+    },
+    Message {
+      topic: topic(&config.forecast_topic, place),
+      payload: Value::Array(summary).to_string(),
+      retain: config.retain,
+    },
+    Message {
+      topic: topic(&config.alerts_topic, place),
+      payload: json!({ "count": alerts.len(), "alerts": alerts.alerts }).to_string(),
+      retain: config.retain,
+    },
+  ]
+}
+
+/// Home Assistant discovery messages announcing the sensors of a place.
+/// These are always retained so Home Assistant finds them after a restart.
+fn discovery(place: &str, config: &MqttConfig) -> Vec<Message> {
+  let id = slug(place);
+  let device = json!({
+    "identifiers": [format!("weather_{}", id)],
+    "name": format!("Weather {}", place),
+  });
+
+  let mut messages: Vec<Message> = SENSORS
+    .iter()
+    .map(|sensor| {
+      let mut payload = json!({
+        "name": sensor.name,
+        "unique_id": format!("weather_{}_{}", id, sensor.key),
+        "state_topic": topic(&config.current_topic, place),
+        "value_template": format!("{{{{ value_json.{} }}}}", value_path(sensor.key)),
+        "device": device,
+      });
+      if let Some(unit) = sensor.unit {
+        payload["unit_of_measurement"] = json!(unit);
+      }
+      if let Some(class) = sensor.device_class {
+        payload["device_class"] = json!(class);
+      }
+
+      Message {
+        topic: format!(
+          "{}/sensor/weather_{}_{}/config",
+          config.discovery_prefix, id, sensor.key
+        ),
+        payload: payload.to_string(),
+        retain: true,
+      }
+    })
+    .collect();
+
+  messages.push(Message {
+    topic: format!(
+      "{}/sensor/weather_{}_alerts/config",
+      config.discovery_prefix, id
+    ),
+    payload: json!({
+      "name": "Alerts",
+      "unique_id": format!("weather_{}_alerts", id),
+      "state_topic": topic(&config.alerts_topic, place),
+      "value_template": "{{ value_json.count }}",
+      "device": device,
+    })
+    .to_string(),
+    retain: true,
+  });
+
+  messages
+}
+
+/// Where a sensor value sits in the serialized weather.
+fn value_path(key: &str) -> &str {
+  match key {
+    "condition" => "condition.text",
+    key => key,
+  }
+}
+
+fn topic(template: &str, place: &str) -> String {
+  template.replace("{place}", &slug(place))
+}
+
+/// Lowercase a place name into a single topic level.
+fn slug(place: &str) -> String {
+  place
+    .to_lowercase()
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+  };
+  use weather_core::alert::Alert;
+
+  fn alerts() -> Alerts {
+    Alerts {
+      alerts: vec![Alert {
+        headline: "Flood warning".to_string(),
+        event: "Flood Warning".to_string(),
+        effective: "2024-01-01T00:00:00+00:00".to_string(),
+        expires: "2024-01-02T00:00:00+00:00".to_string(),
+        ..Alert::sample()
+      }],
+    }
+  }
+
+  #[test]
+  fn test_broker_address() {
+    assert_eq!(
+      broker_address("mqtt://broker.lab:1884").unwrap(),
+      ("broker.lab".to_string(), 1884)
+    );
+    assert_eq!(
+      broker_address("localhost").unwrap(),
+      ("localhost".to_string(), 1883)
+    );
+    assert!(broker_address("localhost:port").is_err());
+    assert!(broker_address(":1883").is_err());
+  }
+
+  #[test]
+  fn test_messages() {
+    let config = MqttConfig::default();
+    let messages = messages(
+      "Head Office",
+      &Weather::sample(),
+      &Forecast { days: Vec::new() },
+      &alerts(),
+      &config,
+    );
+
+    let topics: Vec<&str> = messages.iter().map(|m| m.topic.as_str()).collect();
+    assert_eq!(
+      topics,
+      [
+        "weather/head_office/current",
+        "weather/head_office/forecast",
+        "weather/head_office/alerts"
+      ]
+    );
+    assert!(messages.iter().all(|m| m.retain));
+
+    let current: Value = serde_json::from_str(&messages[0].payload).unwrap();
+    assert_eq!(current["temperature"], 5.1);
+    assert_eq!(messages[1].payload, "[]");
+    let alerts: Value = serde_json::from_str(&messages[2].payload).unwrap();
+    assert_eq!(alerts["count"], 1);
+    assert_eq!(alerts["alerts"][0]["headline"], "Flood warning");
+  }
+
+  #[test]
+  fn test_discovery() {
+    let config = MqttConfig::default();
+    let messages = discovery("office", &config);
+    assert_eq!(messages.len(), SENSORS.len() + 1);
+    assert_eq!(
+      messages[0].topic,
+      "homeassistant/sensor/weather_office_temperature/config"
+    );
+
+    let payload: Value = serde_json::from_str(&messages[0].payload).unwrap();
+    assert_eq!(payload["state_topic"], "weather/office/current");
+    assert_eq!(payload["value_template"], "{{ value_json.temperature }}");
+    assert_eq!(payload["unit_of_measurement"], "°C");
+    assert_eq!(payload["unique_id"], "weather_office_temperature");
+
+    let condition = messages
+      .iter()
+      .find(|m| m.topic.contains("condition"))
+      .unwrap();
+    let payload: Value = serde_json::from_str(&condition.payload).unwrap();
+    assert_eq!(payload["value_template"], "{{ value_json.condition.text }}");
+    assert!(payload.get("unit_of_measurement").is_none());
+  }
+
+  /// Read one MQTT packet, returning its first byte and body.
+  async fn read_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+    let kind = stream.read_u8().await.ok()?;
+    let mut length = 0usize;
+    let mut shift = 0;
+    loop {
+      let byte = stream.read_u8().await.ok()?;
+      length |= ((byte & 0x7f) as usize) << shift;
+      shift += 7;
+      if byte & 0x80 == 0 {
+        break;
+      }
+    }
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await.ok()?;
+    Some((kind, body))
+  }
+
+  /// Just enough of a broker to accept one client and report what it publishes.
+  async fn broker(listener: TcpListener, published: mpsc::UnboundedSender<(String, bool)>) {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    while let Some((kind, body)) = read_packet(&mut stream).await {
+      match kind >> 4 {
+        1 => stream.write_all(&[0x20, 0x02, 0x00, 0x00]).await.unwrap(),
+        3 => {
+          let length = u16::from_be_bytes([body[0], body[1]]) as usize;
+          let topic = String::from_utf8(body[2..2 + length].to_vec()).unwrap();
+          if (kind >> 1) & 0x03 > 0 {
+            let id = &body[2 + length..4 + length];
+            stream.write_all(&[0x40, 0x02, id[0], id[1]]).await.unwrap();
+          }
+          published.send((topic, kind & 0x01 == 1)).unwrap();
+        }
+        12 => stream.write_all(&[0xd0, 0x00]).await.unwrap(),
+        14 => break,
+        _ => {}
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn test_publish() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let config = MqttConfig {
+      broker: format!("mqtt://{}", listener.local_addr().unwrap()),
+      discovery: true,
+      ..MqttConfig::default()
+    };
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    tokio::spawn(broker(listener, sender));
+
+    let (client, eventloop) = connect(&config).unwrap();
+    let connection = tokio::spawn(drive(eventloop));
+
+    let mut expected = discovery("office", &config);
+    expected.extend(messages(
+      "office",
+      &Weather::sample(),
+      &Forecast { days: Vec::new() },
+      &alerts(),
+      &config,
+    ));
+    publish(&client, expected.clone()).await.unwrap();
+
+    for message in expected {
+      let (topic, retain) = receiver.recv().await.unwrap();
+      assert_eq!(topic, message.topic);
+      assert!(retain);
+    }
+
+    client.disconnect().await.unwrap();
+    connection.abort();
+  }
+}
@@ -0,0 +1,370 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+  extract::{OriginalUri, Query, State},
+  http::{header, HeaderValue, StatusCode},
+  response::{IntoResponse, Response},
+  routing::get,
+  Json, Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{net::TcpListener, signal};
+use weather_core::{
+  alert::get_alerts,
+  astronomy::get_current_astronomy,
+  config::Config,
+  error::Error,
+  feed::{self, Format},
+  forecast::get_forecast,
+  location::Location,
+  redact::describe,
+  weather::{get_current_weather, Units},
+};
+
+use crate::{locate::LocationQuery, validate::validate_days};
+
+/// State shared by every request.
+/// Requests also share the HTTP client and response cache of weather-core.
+#[derive(Clone)]
+struct AppState {
+  config: Arc<Config>,
+  /// URL the API is reached at, without a trailing slash, for links to it.
+  public_url: Arc<str>,
+}
+
+/// Options accepted by the weather endpoints besides the location.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Options {
+  units: Units,
+  days: i32,
+  /// Feed format of alerts, JSON when not given.
+  format: Option<Format>,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Options {
+      units: Units::Metric,
+      days: 1,
+      format: None,
+    }
+  }
+}
+
+/// Error returned to API clients as JSON with a matching status code.
+struct ApiError(Error);
+
+impl From<Error> for ApiError {
+  fn from(err: Error) -> Self {
+    ApiError(err)
+  }
+}
+
+impl IntoResponse for ApiError {
+  fn into_response(self) -> Response {
+    let status = match &self.0 {
+      Error::NoLocation | Error::InvalidArgument { .. } => StatusCode::BAD_REQUEST,
+      Error::UnknownLocation { .. } => StatusCode::NOT_FOUND,
+      Error::Fetch { .. }
+      | Error::Response { .. }
+      | Error::BadIp { .. }
+      | Error::KeyRejected { .. } => StatusCode::BAD_GATEWAY,
+      Error::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+      Error::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+      Error::QuotaExceeded { .. } => StatusCode::SERVICE_UNAVAILABLE,
+      _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let mut response = (status, Json(json!({ "error": describe(&self.0) }))).into_response();
+    if let Error::RateLimited {
+      retry_after: Some(seconds),
+    } = &self.0
+    {
+      response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(*seconds));
+    }
+    response
+  }
+}
+
+type ApiResult = Result<Response, ApiError>;
+
+/// Handle the serve command.
+/// Serves the REST API on `bind` until interrupted.
+/// Links to the API, such as the self link of alert feeds, start with `public_url`, or with the
+/// bound address when not given.
+pub async fn handle_serve(
+  bind: SocketAddr,
+  public_url: Option<String>,
+  config: Config,
+) -> Result<(), Error> {
+  let listener = TcpListener::bind(bind).await.map_err(|err| Error::Error {
+    message: format!("failed to bind {}: {}", bind, err),
+  })?;
+  println!("Listening on http://{}", bind);
+
+  let public_url = public_url.unwrap_or_else(|| format!("http://{}", bind));
+  axum::serve(listener, router(config, &public_url))
+    .with_graceful_shutdown(async {
+      signal::ctrl_c().await.ok();
+    })
+    .await
+    .map_err(|err| Error::Error {
+      message: err.to_string(),
+    })
+}
+
+fn router(config: Config, public_url: &str) -> Router {
+  let state = AppState {
+    config: Arc::new(config),
+    public_url: public_url.trim_end_matches('/').into(),
+  };
+
+  Router::new()
+    .route("/healthz", get(healthz))
+    .route("/locate", get(locate))
+    .route("/current", get(current))
+    .route("/forecast", get(forecast))
+    .route("/astronomy", get(astronomy))
+    .route("/alerts", get(alerts))
+    .with_state(state)
+}
+
+/// Resolve the location of a request, which unlike the CLI must always be given.
+async fn location(query: &LocationQuery, state: &AppState) -> Result<Location, Error> {
+  query.resolve(&state.config).await?.ok_or(Error::NoLocation)
+}
+
+async fn healthz() -> Response {
+  Json(json!({ "status": "ok" })).into_response()
+}
+
+async fn locate(State(state): State<AppState>, Query(query): Query<LocationQuery>) -> ApiResult {
+  let location = location(&query, &state).await?;
+  Ok(Json(location).into_response())
+}
+
+async fn current(
+  State(state): State<AppState>,
+  Query(query): Query<LocationQuery>,
+  Query(options): Query<Options>,
+) -> ApiResult {
+  let location = location(&query, &state).await?;
+  let weather = get_current_weather(location, options.units).await?;
+  Ok(Json(weather).into_response())
+}
+
+async fn forecast(
+  State(state): State<AppState>,
+  Query(query): Query<LocationQuery>,
+  Query(options): Query<Options>,
+) -> ApiResult {
+  validate_days(options.days)?;
+  let location = location(&query, &state).await?;
+  let forecast = get_forecast(location, options.units, options.days).await?;
+  Ok(Json(forecast).into_response())
+}
+
+async fn astronomy(State(state): State<AppState>, Query(query): Query<LocationQuery>) -> ApiResult {
+  let location = location(&query, &state).await?;
+  let astronomy = get_current_astronomy(location).await?;
+  Ok(Json(astronomy).into_response())
+}
+
+async fn alerts(
+  State(state): State<AppState>,
+  Query(query): Query<LocationQuery>,
+  Query(options): Query<Options>,
+  OriginalUri(uri): OriginalUri,
+) -> ApiResult {
+  validate_days(options.days)?;
+  let location = location(&query, &state).await?;
+  let place = location.to_string();
+  let alerts = get_alerts(location, options.days).await?.dedup();
+  let link = format!("{}{}", state.public_url, uri);
+  match options.format {
+    Some(format) => Ok(
+      (
+        [(header::CONTENT_TYPE, format.content_type())],
+        feed::render(format, &place, &alerts, Utc::now(), Some(&link)),
+      )
+        .into_response(),
+    ),
+    None => Ok(Json(alerts).into_response()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::body::Body;
+  use http_body_util::BodyExt;
+  use serde_json::Value;
+  use std::path::Path;
+  use tower::ServiceExt;
+  use weather_core::{
+    config::Place,
+    replay::{self, Mode},
+  };
+
+  async fn request(uri: &str) -> (StatusCode, Value) {
+    let (status, body) = request_text(uri).await;
+    (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+  }
+
+  /// Request with provider responses replayed from the London fixtures of weather-core.
+  async fn replayed(uri: &str) -> (StatusCode, Value) {
+    replay::scope(london(), request(uri)).await
+  }
+
+  fn london() -> Mode {
+    Mode::Replay(Path::new(env!("CARGO_MANIFEST_DIR")).join("../weather-core/fixtures/london"))
+  }
+
+  async fn request_text(uri: &str) -> (StatusCode, String) {
+    let config = Config {
+      places: vec![Place {
+        name: "office".to_string(),
+        lat: Some(51.5171),
+        lon: Some(-0.1062),
+        query: None,
+      }],
+      ..Config::default()
+    };
+
+    let response = router(config, "https://weather.example.com/")
+      .oneshot(
+        axum::http::Request::builder()
+          .uri(uri)
+          .body(Body::empty())
+          .unwrap(),
+      )
+      .await
+      .unwrap();
+
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+  }
+
+  #[tokio::test]
+  async fn test_healthz() {
+    let (status, body) = request("/healthz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+  }
+
+  #[tokio::test]
+  async fn test_locate_coordinates() {
+    let (status, body) = request("/locate?lat=51.5&lon=-0.1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["lat"], 51.5);
+    assert_eq!(body["lon"], -0.1);
+  }
+
+  #[tokio::test]
+  async fn test_locate_place() {
+    let (status, body) = request("/locate?place=office").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["city"], "office");
+    assert_eq!(body["lat"], 51.5171);
+  }
+
+  #[tokio::test]
+  async fn test_current() {
+    let (status, body) = replayed("/current?place=office").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["temperature"], 5.1);
+    assert_eq!(body["condition"]["text"], "Fog");
+  }
+
+  #[tokio::test]
+  async fn test_forecast() {
+    let (status, body) = replayed("/forecast?place=office").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["days"].as_array().unwrap().len(), 1);
+    assert_eq!(body["days"][0]["date"], "2024-12-28");
+  }
+
+  #[tokio::test]
+  async fn test_alerts() {
+    let (status, body) = replayed("/alerts?place=office&days=3").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["alerts"][0]["headline"], "Severe Weather Alert");
+  }
+
+  #[tokio::test]
+  async fn test_alerts_feed_link() {
+    let (status, body) = replay::scope(
+      london(),
+      request_text("/alerts?place=office&days=3&format=atom"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains(
+      "<link rel=\"self\" href=\"https://weather.example.com/alerts?place=office&amp;days=3&amp;format=atom\"/>"
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_unknown_place() {
+    let (status, body) = request("/current?place=home").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body["error"].as_str().unwrap().contains("home"));
+  }
+
+  #[tokio::test]
+  async fn test_missing_location() {
+    let (status, _) = request("/current").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+  }
+
+  #[tokio::test]
+  async fn test_invalid_format() {
+    let (status, _) = request("/alerts?place=office&format=html").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+  }
+
+  #[test]
+  fn test_upstream_errors() {
+    let status = |err: Error| ApiError(err).into_response().status();
+    assert_eq!(
+      status(Error::Timeout {
+        message: "timed out".to_string()
+      }),
+      StatusCode::GATEWAY_TIMEOUT
+    );
+    assert_eq!(
+      status(Error::KeyRejected {
+        status: 403,
+        message: "API key has been disabled.".to_string()
+      }),
+      StatusCode::BAD_GATEWAY
+    );
+    assert_eq!(
+      status(Error::QuotaExceeded {
+        period: "daily".to_string(),
+        limit: 100
+      }),
+      StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    let response = ApiError(Error::RateLimited {
+      retry_after: Some(30),
+    })
+    .into_response();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    let response = ApiError(Error::RateLimited { retry_after: None }).into_response();
+    assert!(response.headers().get(header::RETRY_AFTER).is_none());
+  }
+
+  #[tokio::test]
+  async fn test_invalid_days() {
+    let (status, _) = request("/forecast?place=office&days=9").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+  }
+}
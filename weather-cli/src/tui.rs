@@ -0,0 +1,754 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use ratatui::{
+  crossterm::event::{self, Event, KeyCode, KeyEventKind},
+  layout::{Constraint, Direction, Layout, Rect},
+  style::{Color, Modifier, Style},
+  symbols,
+  text::{Line, Span},
+  widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, List, ListItem, Paragraph},
+  DefaultTerminal, Frame,
+};
+use tokio::task::spawn_blocking;
+use weather_core::{
+  alert::{Alerts, Severity},
+  config::Config,
+  error::Error,
+  forecast::{get_outlook, Forecast, ForecastDay},
+  locale,
+  location::Location,
+  redact::describe,
+  weather::{Units, Weather},
+};
+
+use crate::locate::locate_place;
+
+/// How long to wait for a key press before checking if a refresh is due.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Number of days shown in the forecast strip.
+const FORECAST_DAYS: i32 = 3;
+
+/// Everything fetched for one place.
+struct Snapshot {
+  location: Location,
+  weather: Weather,
+  forecast: Forecast,
+  alerts: Alerts,
+}
+
+/// What the event loop should do after a key press.
+#[derive(Debug, PartialEq)]
+enum Action {
+  None,
+  Refresh,
+  Quit,
+}
+
+/// State of the dashboard.
+/// The first place is the location the command was started with, followed by the other configured places.
+struct Dashboard {
+  names: Vec<String>,
+  locations: Vec<Option<Location>>,
+  place: usize,
+  day: usize,
+  units: Units,
+  snapshot: Option<Snapshot>,
+  error: Option<String>,
+  loading: bool,
+  updated: Option<DateTime<Local>>,
+}
+
+/// Handle the tui command.
+/// Shows a full screen dashboard until the user quits, refreshing every `interval`.
+/// `place` is the name of the configured place the location was resolved from, if any.
+pub async fn handle_tui(
+  location: Location,
+  place: Option<&str>,
+  config: &Config,
+  interval: Duration,
+) -> Result<(), Error> {
+  let mut dashboard = Dashboard::new(location, place, config);
+  let mut terminal = ratatui::init();
+  let result = run(&mut terminal, &mut dashboard, config, interval).await;
+  ratatui::restore();
+  result
+}
+
+async fn run(
+  terminal: &mut DefaultTerminal,
+  dashboard: &mut Dashboard,
+  config: &Config,
+  interval: Duration,
+) -> Result<(), Error> {
+  let mut refreshed: Option<Instant> = None;
+  let mut refresh = true;
+
+  loop {
+    if refresh || refreshed.is_some_and(|at| at.elapsed() >= interval) {
+      dashboard.loading = true;
+      terminal
+        .draw(|frame| dashboard.draw(frame))
+        .map_err(terminal_error)?;
+      dashboard.refresh(config).await;
+      refreshed = Some(Instant::now());
+      refresh = false;
+    }
+
+    terminal
+      .draw(|frame| dashboard.draw(frame))
+      .map_err(terminal_error)?;
+
+    let Some(event) = next_event().await? else {
+      continue;
+    };
+    if let Event::Key(key) = event {
+      if key.kind != KeyEventKind::Press {
+        continue;
+      }
+      match dashboard.handle_key(key.code) {
+        Action::Quit => return Ok(()),
+        Action::Refresh => refresh = true,
+        Action::None => (),
+      }
+    }
+  }
+}
+
+/// Wait up to `POLL_INTERVAL` for a terminal event, on the blocking pool so the runtime keeps running.
+async fn next_event() -> Result<Option<Event>, Error> {
+  spawn_blocking(|| match event::poll(POLL_INTERVAL)? {
+    true => event::read().map(Some),
+    false => Ok(None),
+  })
+  .await
+  .map_err(|err| Error::Error {
+    message: err.to_string(),
+  })?
+  .map_err(terminal_error)
+}
+
+fn terminal_error(err: std::io::Error) -> Error {
+  Error::Error {
+    message: err.to_string(),
+  }
+}
+
+impl Dashboard {
+  fn new(location: Location, place: Option<&str>, config: &Config) -> Self {
+    let start = place.and_then(|name| config.place(name));
+    let mut names = vec![start.map_or_else(|| location.to_string(), |place| place.name.clone())];
+    let mut locations = vec![Some(location)];
+    for place in &config.places {
+      if start.is_some_and(|start| start.name == place.name) {
+        continue;
+      }
+      names.push(place.name.clone());
+      locations.push(None);
+    }
+
+    Dashboard {
+      names,
+      locations,
+      place: 0,
+      day: 0,
+      units: Units::Metric,
+      snapshot: None,
+      error: None,
+      loading: false,
+      updated: None,
+    }
+  }
+
+  fn handle_key(&mut self, key: KeyCode) -> Action {
+    match key {
+      KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+      KeyCode::Char('r') => Action::Refresh,
+      KeyCode::Char('u') => {
+        self.units = match self.units {
+          Units::Metric => Units::Imperial,
+          Units::Imperial => Units::Metric,
+        };
+        Action::Refresh
+      }
+      KeyCode::Down | KeyCode::Tab | KeyCode::Char('j') => {
+        self.place = (self.place + 1) % self.names.len();
+        self.day = 0;
+        Action::Refresh
+      }
+      KeyCode::Up | KeyCode::BackTab | KeyCode::Char('k') => {
+        self.place = (self.place + self.names.len() - 1) % self.names.len();
+        self.day = 0;
+        Action::Refresh
+      }
+      KeyCode::Right | KeyCode::Char('l') => {
+        self.day = (self.day + 1).min(FORECAST_DAYS as usize - 1);
+        Action::None
+      }
+      KeyCode::Left | KeyCode::Char('h') => {
+        self.day = self.day.saturating_sub(1);
+        Action::None
+      }
+      _ => Action::None,
+    }
+  }
+
+  /// Fetch everything for the selected place.
+  /// Errors are kept for display and the previous data stays on screen.
+  async fn refresh(&mut self, config: &Config) {
+    self.loading = false;
+    match self.fetch(config).await {
+      Ok(snapshot) => {
+        self.snapshot = Some(snapshot);
+        self.error = None;
+        self.updated = Some(Local::now());
+      }
+      Err(err) => self.error = Some(describe(&err)),
+    }
+  }
+
+  async fn fetch(&mut self, config: &Config) -> Result<Snapshot, Error> {
+    let location = match &self.locations[self.place] {
+      Some(location) => location.clone(),
+      None => {
+        let location = locate_place(&self.names[self.place], config).await?;
+        self.locations[self.place] = Some(location.clone());
+        location
+      }
+    };
+
+    let outlook = get_outlook(location.clone(), self.units, FORECAST_DAYS).await?;
+    Ok(Snapshot {
+      location,
+      weather: outlook.weather,
+      forecast: outlook.forecast,
+      alerts: outlook.alerts.dedup(),
+    })
+  }
+
+  fn selected_day(&self) -> Option<&ForecastDay> {
+    let snapshot = self.snapshot.as_ref()?;
+    snapshot
+      .forecast
+      .days
+      .get(self.day)
+      .or(snapshot.forecast.days.last())
+  }
+
+  fn draw(&self, frame: &mut Frame) {
+    let rows = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([
+        Constraint::Length(1),
+        Constraint::Length(10),
+        Constraint::Min(8),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(1),
+      ])
+      .split(frame.area());
+
+    self.draw_title(frame, rows[0]);
+
+    let top = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+      .split(rows[1]);
+    self.draw_current(frame, top[0]);
+    self.draw_astronomy(frame, top[1]);
+    self.draw_hourly(frame, rows[2]);
+    self.draw_forecast(frame, rows[3]);
+    self.draw_alerts(frame, rows[4]);
+
+    let help = "q quit  ←/→ day  ↑/↓ place  u units  r refresh";
+    frame.render_widget(
+      Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+      rows[5],
+    );
+  }
+
+  fn draw_title(&self, frame: &mut Frame, area: Rect) {
+    let mut spans = vec![
+      Span::styled("Weather ", Style::default().add_modifier(Modifier::BOLD)),
+      Span::raw(format!(
+        "{} ({}/{})  {}",
+        self.names[self.place],
+        self.place + 1,
+        self.names.len(),
+        labels(self.units).temperature
+      )),
+    ];
+
+    if self.loading {
+      spans.push(Span::raw("  Loading…"));
+    } else if let Some(updated) = self.updated {
+      spans.push(Span::raw(format!(
+        "  Last updated {}",
+        updated.format("%H:%M:%S")
+      )));
+    }
+    if let Some(error) = &self.error {
+      spans.push(Span::styled(
+        format!("  {}", error),
+        Style::default().fg(Color::Red),
+      ));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+  }
+
+  fn draw_current(&self, frame: &mut Frame, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title("Current");
+    let Some(snapshot) = &self.snapshot else {
+      frame.render_widget(block, area);
+      return;
+    };
+
+    let weather = &snapshot.weather;
+    let labels = labels(self.units);
+    let locale = locale::current();
+    let lines = vec![
+      Line::from(Span::styled(
+        format!(
+          "{} {}",
+          weather.condition.kind().glyph(weather.is_day),
+          weather.condition.text
+        ),
+        Style::default().add_modifier(Modifier::BOLD),
+      )),
+      Line::from(format!(
+        "{}: {}{} ({} {}{})",
+        locale.label("Temperature"),
+        locale.number(weather.temperature),
+        labels.temperature,
+        locale.label("Feels Like"),
+        locale.number(weather.feels_like),
+        labels.temperature
+      )),
+      Line::from(format!(
+        "{}: {} {} {} ({} {} {})",
+        locale.label("Wind"),
+        locale.number(weather.wind_speed),
+        labels.speed,
+        weather.wind_dir,
+        locale.label("Gust"),
+        locale.number(weather.wind_gust_speed),
+        labels.speed
+      )),
+      Line::from(format!(
+        "{}: {}%",
+        locale.label("Humidity"),
+        weather.humidity
+      )),
+      Line::from(format!(
+        "{}: {} {}",
+        locale.label("Pressure"),
+        locale.number(weather.pressure),
+        labels.pressure
+      )),
+      Line::from(format!(
+        "{}: {} {}",
+        locale.label("Precipitation"),
+        locale.number(weather.precipitation),
+        labels.precipitation
+      )),
+      Line::from(format!(
+        "{}: {}",
+        locale.label("UV"),
+        locale.number(weather.uv)
+      )),
+      Line::from(format!(
+        "{}: {} {}",
+        locale.label("Visibility"),
+        locale.number(weather.visibility),
+        labels.distance
+      )),
+    ];
+
+    frame.render_widget(
+      Paragraph::new(lines).block(block.title(snapshot.location.to_string())),
+      area,
+    );
+  }
+
+  fn draw_astronomy(&self, frame: &mut Frame, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title("Astronomy");
+    let Some(day) = self.selected_day() else {
+      frame.render_widget(block, area);
+      return;
+    };
+
+    let astronomy = &day.astronomy;
+    let locale = locale::current();
+    let lines = vec![
+      Line::from(format!(
+        "{}: {}",
+        locale.label("Sunrise"),
+        locale.time(&astronomy.sunrise)
+      )),
+      Line::from(format!(
+        "{}: {}",
+        locale.label("Sunset"),
+        locale.time(&astronomy.sunset)
+      )),
+      Line::from(format!(
+        "{}: {}",
+        locale.label("Moonrise"),
+        locale.time(&astronomy.moonrise)
+      )),
+      Line::from(format!(
+        "{}: {}",
+        locale.label("Moonset"),
+        locale.time(&astronomy.moonset)
+      )),
+      Line::from(format!(
+        "{}: {}",
+        locale.label("Moon Phase"),
+        astronomy.moon_phase
+      )),
+      Line::from(format!(
+        "{}: {}%",
+        locale.label("Moon Illumination"),
+        astronomy.moon_illumination
+      )),
+    ];
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+  }
+
+  fn draw_hourly(&self, frame: &mut Frame, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title("Hourly");
+    let Some(day) = self.selected_day() else {
+      frame.render_widget(block, area);
+      return;
+    };
+    let locale = locale::current();
+
+    let temperature: Vec<(f64, f64)> = day
+      .hours
+      .iter()
+      .enumerate()
+      .map(|(hour, data)| (hour as f64, data.temperature))
+      .collect();
+    let feels_like: Vec<(f64, f64)> = day
+      .hours
+      .iter()
+      .enumerate()
+      .map(|(hour, data)| (hour as f64, data.feels_like))
+      .collect();
+
+    let values = temperature.iter().chain(feels_like.iter()).map(|(_, y)| *y);
+    let min = values.clone().fold(f64::INFINITY, f64::min).floor() - 1.0;
+    let max = values.fold(f64::NEG_INFINITY, f64::max).ceil() + 1.0;
+    let (min, max) = match min.is_finite() && max.is_finite() {
+      true => (min, max),
+      false => (0.0, 1.0),
+    };
+
+    let datasets = vec![
+      Dataset::default()
+        .name(locale.label("Temperature"))
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Yellow))
+        .data(&temperature),
+      Dataset::default()
+        .name(locale.label("Feels Like"))
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Cyan))
+        .data(&feels_like),
+    ];
+
+    let chart = Chart::new(datasets)
+      .block(block.title(format!("Hourly {}", day.date)))
+      .x_axis(
+        Axis::default()
+          .bounds([0.0, 23.0])
+          .labels(["00:00", "06:00", "12:00", "18:00", "23:00"]),
+      )
+      .y_axis(Axis::default().bounds([min, max]).labels([
+        format!("{}", min),
+        format!("{}", (min + max) / 2.0),
+        format!("{}", max),
+      ]));
+
+    frame.render_widget(chart, area);
+  }
+
+  fn draw_forecast(&self, frame: &mut Frame, area: Rect) {
+    let Some(snapshot) = &self.snapshot else {
+      frame.render_widget(
+        Block::default().borders(Borders::ALL).title("Forecast"),
+        area,
+      );
+      return;
+    };
+
+    let days = &snapshot.forecast.days;
+    let columns = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints(vec![
+        Constraint::Ratio(1, days.len().max(1) as u32);
+        days.len()
+      ])
+      .split(area);
+
+    let selected = self.day.min(days.len().saturating_sub(1));
+    let temperature = labels(self.units).temperature;
+    for (index, (day, column)) in days.iter().zip(columns.iter()).enumerate() {
+      let style = match index == selected {
+        true => Style::default().fg(Color::Yellow),
+        false => Style::default(),
+      };
+
+      let lines = vec![
+        Line::from(format!(
+          "{} {}",
+          day.condition.kind().glyph(true),
+          day.condition.text
+        )),
+        Line::from(format!(
+          "High {}{} Low {}{}",
+          day.max_temperature, temperature, day.min_temperature, temperature
+        )),
+        Line::from(format!("Rain {}%", day.chance_of_rain)),
+      ];
+
+      let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(style)
+        .title(weekday(&day.date));
+      frame.render_widget(Paragraph::new(lines).block(block), *column);
+    }
+  }
+
+  fn draw_alerts(&self, frame: &mut Frame, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title("Alerts");
+    let items: Vec<ListItem> = match &self.snapshot {
+      Some(snapshot) => {
+        let now = Utc::now();
+        let active: Vec<ListItem> = snapshot
+          .alerts
+          .alerts
+          .iter()
+          .filter(|alert| alert.is_active(now))
+          .map(|alert| {
+            let colour = match alert.severity_level() {
+              Severity::Extreme | Severity::Severe => Color::Red,
+              Severity::Moderate => Color::Yellow,
+              _ => Color::Reset,
+            };
+            ListItem::new(Line::from(vec![
+              Span::styled(
+                format!("{}: ", alert.severity_level().to_string()),
+                Style::default().fg(colour),
+              ),
+              Span::raw(format!("{} (until {})", alert.headline, alert.expires)),
+            ]))
+          })
+          .collect();
+
+        match active.is_empty() {
+          true => vec![ListItem::new("No active alerts")],
+          false => active,
+        }
+      }
+      None => vec![],
+    };
+
+    frame.render_widget(List::new(items).block(block), area);
+  }
+}
+
+/// Unit suffixes for displayed values.
+struct Labels {
+  temperature: &'static str,
+  speed: &'static str,
+  pressure: &'static str,
+  precipitation: &'static str,
+  distance: &'static str,
+}
+
+fn labels(units: Units) -> Labels {
+  match units {
+    Units::Metric => Labels {
+      temperature: "°C",
+      speed: "km/h",
+      pressure: "mb",
+      precipitation: "mm",
+      distance: "km",
+    },
+    Units::Imperial => Labels {
+      temperature: "°F",
+      speed: "mph",
+      pressure: "in",
+      precipitation: "in",
+      distance: "miles",
+    },
+  }
+}
+
+/// Short weekday name for a `YYYY-MM-DD` date, or the date itself if it can not be parsed.
+fn weekday(date: &str) -> String {
+  match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+    Ok(parsed) => format!("{} {}", parsed.format("%a"), date),
+    Err(_) => date.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ratatui::{backend::TestBackend, Terminal};
+  use weather_core::{
+    alert::Alert, astronomy::Astronomy, config::Place, forecast::Hour, weather::Condition,
+  };
+
+  fn location() -> Location {
+    Location {
+      country: "United Kingdom".to_string(),
+      region: None,
+      city: Some("London".to_string()),
+      lat: Some(51.5171),
+      lon: Some(-0.1062),
+    }
+  }
+
+  fn config() -> Config {
+    Config {
+      places: vec![Place {
+        name: "office".to_string(),
+        lat: Some(52.2),
+        lon: Some(0.12),
+        query: None,
+      }],
+      ..Config::default()
+    }
+  }
+
+  fn condition(text: &str) -> Condition {
+    Condition::new(text.to_string(), String::new(), 1000)
+  }
+
+  fn snapshot() -> Snapshot {
+    let weather = Weather::sample();
+
+    let hour = |time: usize| Hour {
+      time: format!("2024-12-28 {:02}:00", time),
+      is_day: true,
+      temperature: 4.0 + time as f64 / 4.0,
+      feels_like: 3.0 + time as f64 / 4.0,
+      condition: condition("Fog"),
+      wind_speed: 3.6,
+      wind_degree: 206,
+      wind_dir: "SSW".to_string(),
+      wind_gust_speed: 4.1,
+      pressure: 1030.0,
+      precipitation: 0.0,
+      humidity: 100,
+      cloud: 100,
+      chance_of_rain: 10,
+      chance_of_snow: 0,
+      uv: 0.0,
+    };
+
+    let day = |date: &str| ForecastDay {
+      date: date.to_string(),
+      max_temperature: 7.2,
+      min_temperature: 4.1,
+      avg_temperature: 5.6,
+      max_wind_speed: 10.1,
+      total_precipitation: 0.2,
+      avg_humidity: 91.0,
+      chance_of_rain: 40,
+      chance_of_snow: 0,
+      condition: condition("Overcast"),
+      uv: 0.1,
+      astronomy: Astronomy::new(
+        "08:06 AM".to_string(),
+        "03:58 PM".to_string(),
+        "06:11 AM".to_string(),
+        "01:21 PM".to_string(),
+        "Waning Crescent".to_string(),
+        9,
+        0,
+        0,
+      ),
+      hours: (0..24).map(hour).collect(),
+    };
+
+    let alert = Alert {
+      headline: "Heavy rain warning".to_string(),
+      effective: String::new(),
+      expires: String::new(),
+      ..Alert::sample()
+    };
+
+    Snapshot {
+      location: location(),
+      weather,
+      forecast: Forecast {
+        days: vec![day("2024-12-28"), day("2024-12-29"), day("2024-12-30")],
+      },
+      alerts: Alerts::new(vec![alert]),
+    }
+  }
+
+  #[test]
+  fn test_handle_key() {
+    let mut dashboard = Dashboard::new(location(), None, &config());
+    assert_eq!(dashboard.names, vec!["London, United Kingdom", "office"]);
+
+    assert_eq!(dashboard.handle_key(KeyCode::Right), Action::None);
+    assert_eq!(dashboard.handle_key(KeyCode::Right), Action::None);
+    assert_eq!(dashboard.handle_key(KeyCode::Right), Action::None);
+    assert_eq!(dashboard.day, 2);
+    assert_eq!(dashboard.handle_key(KeyCode::Left), Action::None);
+    assert_eq!(dashboard.day, 1);
+
+    assert_eq!(dashboard.handle_key(KeyCode::Down), Action::Refresh);
+    assert_eq!(dashboard.place, 1);
+    assert_eq!(dashboard.day, 0);
+    assert_eq!(dashboard.handle_key(KeyCode::Down), Action::Refresh);
+    assert_eq!(dashboard.place, 0);
+    assert_eq!(dashboard.handle_key(KeyCode::Up), Action::Refresh);
+    assert_eq!(dashboard.place, 1);
+
+    assert_eq!(dashboard.handle_key(KeyCode::Char('u')), Action::Refresh);
+    assert_eq!(dashboard.units, Units::Imperial);
+    assert_eq!(dashboard.handle_key(KeyCode::Char('q')), Action::Quit);
+  }
+
+  #[test]
+  fn test_new_from_place() {
+    let mut dashboard = Dashboard::new(location(), Some("Office"), &config());
+    assert_eq!(dashboard.names, vec!["office"]);
+    assert!(dashboard.locations[0].is_some());
+
+    assert_eq!(dashboard.handle_key(KeyCode::Down), Action::Refresh);
+    assert_eq!(dashboard.place, 0);
+  }
+
+  #[test]
+  fn test_draw() {
+    let mut dashboard = Dashboard::new(location(), None, &config());
+    dashboard.snapshot = Some(snapshot());
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+    terminal.draw(|frame| dashboard.draw(frame)).unwrap();
+
+    let screen: String = terminal
+      .backend()
+      .buffer()
+      .content()
+      .iter()
+      .map(|cell| cell.symbol())
+      .collect();
+    assert!(screen.contains("Temperature: 5.1°C"));
+    assert!(screen.contains("Sunrise: 08:06 AM"));
+    assert!(screen.contains("Sat 2024-12-28"));
+    assert!(screen.contains("Severe: Heavy rain warning"));
+  }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use weather_core::error::Error;
 
 pub fn validate_days(days: i32) -> Result<(), Error> {
@@ -10,6 +12,16 @@ pub fn validate_days(days: i32) -> Result<(), Error> {
   }
 }
 
+pub fn validate_interval(interval: Duration) -> Result<(), Error> {
+  match interval >= Duration::from_secs(10) {
+    true => Ok(()),
+    false => Err(Error::InvalidArgument {
+      arg: humantime::format_duration(interval).to_string(),
+      message: "Interval should be at least 10 seconds.".to_string(),
+    }),
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -41,4 +53,13 @@ mod tests {
       panic!("Expected InvalidArgument error");
     }
   }
+
+  #[test]
+  fn test_validate_interval() {
+    assert!(validate_interval(Duration::from_secs(10)).is_ok());
+    assert!(validate_interval(Duration::from_secs(600)).is_ok());
+
+    let result = validate_interval(Duration::from_secs(5));
+    assert!(matches!(result, Err(Error::InvalidArgument { .. })));
+  }
 }
@@ -0,0 +1,260 @@
+use std::{fmt::Write, time::Duration};
+
+use chrono::{DateTime, Local, Utc};
+use tokio::{signal, time::sleep};
+use weather_core::{
+  alert::get_alerts,
+  error::Error,
+  http, locale,
+  location::Location,
+  redact::describe,
+  weather::{get_current_weather, get_forecast_weather, Units, Weather},
+};
+
+/// Delay before the first retry after a failed refresh.
+const RETRY_DELAY: Duration = Duration::from_secs(15);
+
+/// Changes smaller than this are shown as steady.
+const TREND_THRESHOLD: f64 = 0.05;
+
+/// What is shown by the watch command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+  Current,
+  Forecast,
+  Alert,
+}
+
+/// Values compared between refreshes to show change indicators.
+#[derive(Debug, Clone, Copy, Default)]
+struct Sample {
+  temperature: f64,
+  feels_like: f64,
+  pressure: f64,
+  humidity: f64,
+  wind_speed: f64,
+  alerts: usize,
+}
+
+impl From<&Weather> for Sample {
+  fn from(weather: &Weather) -> Self {
+    Sample {
+      temperature: weather.temperature,
+      feels_like: weather.feels_like,
+      pressure: weather.pressure,
+      humidity: weather.humidity as f64,
+      wind_speed: weather.wind_speed,
+      alerts: 0,
+    }
+  }
+}
+
+impl From<&str> for View {
+  fn from(view: &str) -> Self {
+    match view {
+      "forecast" => View::Forecast,
+      "alert" => View::Alert,
+      _ => View::Current,
+    }
+  }
+}
+
+/// Handle the watch command.
+/// Refreshes the view every `interval` until interrupted, redrawing the terminal in place.
+/// Failed refreshes keep the last good output on screen and are retried with a growing delay.
+pub async fn handle_watch(
+  location: Location,
+  view: View,
+  interval: Duration,
+  days: i32,
+) -> Result<(), Error> {
+  let mut previous: Option<Sample> = None;
+  let mut screen = String::new();
+  let mut failures = 0;
+
+  loop {
+    let (result, fetched) = http::with_fetched(refresh(&location, view, days, previous)).await;
+    let delay = match result {
+      Ok((sample, body)) => {
+        failures = 0;
+        previous = Some(sample);
+        screen = format!(
+          "{}\n{} (every {})\n",
+          body,
+          updated(fetched.unwrap_or_else(Utc::now), Utc::now()),
+          humantime::format_duration(interval)
+        );
+        draw(&screen);
+        interval
+      }
+      Err(err) => {
+        failures += 1;
+        let delay = backoff(failures, interval);
+        draw(&format!(
+          "{}\nUpdate failed at {}: {}\nRetrying in {}\n",
+          screen,
+          Local::now().format("%H:%M:%S"),
+          describe(&err),
+          humantime::format_duration(delay)
+        ));
+        delay
+      }
+    };
+
+    tokio::select! {
+      _ = sleep(delay) => (),
+      _ = signal::ctrl_c() => return Ok(()),
+    }
+  }
+}
+
+/// Fetch the data for the view and render it, comparing against the previous sample.
+async fn refresh(
+  location: &Location,
+  view: View,
+  days: i32,
+  previous: Option<Sample>,
+) -> Result<(Sample, String), Error> {
+  let mut body = String::new();
+  match view {
+    View::Current | View::Forecast => {
+      let weather = match view {
+        View::Forecast => get_forecast_weather(location.clone(), Units::Metric, days).await?,
+        _ => get_current_weather(location.clone(), Units::Metric).await?,
+      };
+      let sample = Sample::from(&weather);
+
+      let locale = locale::current();
+      writeln!(body, "{}\n", location.to_string()).unwrap();
+      for (label, value, before) in [
+        (
+          "Temperature",
+          sample.temperature,
+          previous.map(|p| p.temperature),
+        ),
+        (
+          "Feels Like",
+          sample.feels_like,
+          previous.map(|p| p.feels_like),
+        ),
+        ("Pressure", sample.pressure, previous.map(|p| p.pressure)),
+        ("Humidity", sample.humidity, previous.map(|p| p.humidity)),
+        (
+          "Wind Speed",
+          sample.wind_speed,
+          previous.map(|p| p.wind_speed),
+        ),
+      ] {
+        writeln!(
+          body,
+          "{}: {} {}",
+          locale.label(label),
+          locale.number(value),
+          trend(before, value)
+        )
+        .unwrap();
+      }
+      writeln!(
+        body,
+        "{}: {} {}",
+        locale.label("Condition"),
+        weather.condition.kind().glyph(weather.is_day),
+        weather.condition.text
+      )
+      .unwrap();
+
+      Ok((sample, body))
+    }
+    View::Alert => {
+      let alerts = get_alerts(location.clone(), days).await?.dedup();
+      let sample = Sample {
+        alerts: alerts.len(),
+        ..Sample::default()
+      };
+
+      writeln!(body, "{}\n", location.to_string()).unwrap();
+      writeln!(
+        body,
+        "Alerts: {} {}",
+        sample.alerts,
+        trend(previous.map(|p| p.alerts as f64), sample.alerts as f64)
+      )
+      .unwrap();
+      writeln!(body, "{}", alerts.to_string()).unwrap();
+
+      Ok((sample, body))
+    }
+  }
+}
+
+/// When the data shown was fetched, which is earlier than the refresh when it came from the cache.
+fn updated(fetched: DateTime<Utc>, now: DateTime<Utc>) -> String {
+  let age = (now - fetched).num_seconds().max(0) as u64;
+  let time = fetched.with_timezone(&Local).format("%H:%M:%S");
+  match age {
+    0 => format!("Last updated {}", time),
+    age => format!(
+      "Last updated {}, {} ago",
+      time,
+      humantime::format_duration(Duration::from_secs(age))
+    ),
+  }
+}
+
+/// Clear the terminal and draw the screen from the top left corner.
+fn draw(screen: &str) {
+  print!("\x1b[2J\x1b[H{}", screen);
+}
+
+/// Change indicator between the previous and current value.
+fn trend(previous: Option<f64>, current: f64) -> String {
+  match previous {
+    Some(previous) if current - previous >= TREND_THRESHOLD => {
+      format!("↑ +{:.1}", current - previous)
+    }
+    Some(previous) if previous - current >= TREND_THRESHOLD => {
+      format!("↓ -{:.1}", previous - current)
+    }
+    Some(_) => "→".to_string(),
+    None => String::new(),
+  }
+}
+
+/// Delay before retrying after `failures` consecutive failed refreshes.
+/// Doubles with each failure, never waiting longer than the refresh interval.
+fn backoff(failures: u32, interval: Duration) -> Duration {
+  let delay = RETRY_DELAY.saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)));
+  delay.min(interval.max(RETRY_DELAY))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_trend() {
+    assert_eq!(trend(None, 5.0), "");
+    assert_eq!(trend(Some(5.0), 5.5), "↑ +0.5");
+    assert_eq!(trend(Some(5.0), 4.0), "↓ -1.0");
+    assert_eq!(trend(Some(5.0), 5.01), "→");
+  }
+
+  #[test]
+  fn test_updated() {
+    let now = Utc::now();
+    assert!(updated(now, now).starts_with("Last updated "));
+    assert!(!updated(now, now).contains("ago"));
+    assert!(updated(now - chrono::Duration::seconds(90), now).ends_with(", 1m 30s ago"));
+  }
+
+  #[test]
+  fn test_backoff() {
+    let interval = Duration::from_secs(600);
+    assert_eq!(backoff(1, interval), Duration::from_secs(15));
+    assert_eq!(backoff(2, interval), Duration::from_secs(30));
+    assert_eq!(backoff(4, interval), Duration::from_secs(120));
+    assert_eq!(backoff(10, interval), interval);
+    assert_eq!(backoff(100, interval), interval);
+    assert_eq!(backoff(3, Duration::from_secs(5)), RETRY_DELAY);
+  }
+}
@@ -1,9 +1,18 @@
-use serde::Deserialize;
+use std::{collections::HashSet, str::FromStr};
 
-use crate::{error::Error, location::Location, Query};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  area::{Area, Point},
+  error::Error,
+  fingerprint, http, keys, locale,
+  location::Location,
+  Query,
+};
 
 #[derive(Debug, Deserialize)]
-struct Response {
+pub(crate) struct Response {
   alerts: AlertsResponse,
 }
 
@@ -29,32 +38,106 @@ struct AlertResponse {
   instruction: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Alerts {
-  alerts: Vec<Alert>,
+  pub alerts: Vec<Alert>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Alert {
-  headline: String,
-  message_type: String,
-  description: String,
-  severity: String,
-  urgency: String,
-  areas: String,
-  category: String,
-  certainty: String,
-  event: String,
-  note: String,
-  effective: String,
-  expires: String,
-  instruction: String,
+  pub headline: String,
+  pub message_type: String,
+  pub description: String,
+  pub severity: String,
+  pub urgency: String,
+  pub areas: String,
+  pub category: String,
+  pub certainty: String,
+  pub event: String,
+  pub note: String,
+  pub effective: String,
+  pub expires: String,
+  pub instruction: String,
+  /// Language of the text fields, such as `en-GB`, empty when unknown.
+  pub language: String,
+  /// Areas the alert applies to, when the source gives more than their names.
+  pub geometry: Vec<Area>,
+}
+
+/// Alert severity levels, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  Unknown,
+  Minor,
+  Moderate,
+  Severe,
+  Extreme,
+}
+
+/// How soon action should be taken, ordered from least to most urgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Urgency {
+  Unknown,
+  Past,
+  Future,
+  Expected,
+  Immediate,
+}
+
+/// How certain the alerted event is, ordered from least to most certain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Certainty {
+  Unknown,
+  Unlikely,
+  Possible,
+  Likely,
+  Observed,
+}
+
+/// Criteria used to narrow down a set of alerts.
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+  pub min_severity: Option<Severity>,
+  pub events: Vec<String>,
+  pub active_only: bool,
+  /// Only keep alerts covering this location, see [`Alert::covers`].
+  pub location: Option<Location>,
 }
 
 impl Alerts {
   pub fn new(alerts: Vec<Alert>) -> Self {
     Alerts { alerts }
   }
+
+  pub fn len(&self) -> usize {
+    self.alerts.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.alerts.is_empty()
+  }
+
+  /// Keep only the alerts matching the given filter at time `now`.
+  pub fn filter(self, filter: &Filter, now: DateTime<Utc>) -> Self {
+    let alerts = self
+      .alerts
+      .into_iter()
+      .filter(|alert| filter.matches(alert, now))
+      .collect();
+    Alerts::new(alerts)
+  }
+
+  /// Remove duplicate alerts, keeping the first occurrence.
+  /// Two alerts are duplicates when they share the same id, see [`Alert::id`].
+  pub fn dedup(self) -> Self {
+    let mut seen = HashSet::new();
+    let alerts = self
+      .alerts
+      .into_iter()
+      .filter(|alert| seen.insert(alert.id()))
+      .collect();
+    Alerts::new(alerts)
+  }
 }
 
 impl From<Response> for Alerts {
@@ -64,6 +147,7 @@ impl From<Response> for Alerts {
   }
 }
 
+#[allow(clippy::to_string_trait_impl)]
 impl ToString for Alerts {
   fn to_string(&self) -> String {
     let mut result = String::new();
@@ -75,6 +159,7 @@ impl ToString for Alerts {
 }
 
 impl Alert {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     headline: String,
     message_type: String,
@@ -104,8 +189,110 @@ impl Alert {
       effective,
       expires,
       instruction,
+      language: String::new(),
+      geometry: Vec::new(),
     }
   }
+
+  /// Heavy rain warning for London, shared by the tests of both crates.
+  #[cfg(any(test, feature = "test-support"))]
+  pub fn sample() -> Self {
+    Alert::new(
+      "Severe Weather Alert".to_string(),
+      "Alert".to_string(),
+      "Heavy rain expected in the area.".to_string(),
+      "Severe".to_string(),
+      "Immediate".to_string(),
+      "London".to_string(),
+      "Met".to_string(),
+      "Likely".to_string(),
+      "Heavy Rain".to_string(),
+      String::new(),
+      "2024-12-28T10:00:00Z".to_string(),
+      "2024-12-28T18:00:00Z".to_string(),
+      "Stay indoors and avoid travel.".to_string(),
+    )
+  }
+}
+
+impl Alert {
+  /// Stable identifier for the alert.
+  /// Derived from the headline, event and effective window, so the same alert
+  /// reported for several overlapping areas shares one id.
+  pub fn id(&self) -> String {
+    fingerprint(&[
+      self.headline.trim(),
+      self.event.trim(),
+      self.effective.trim(),
+      self.expires.trim(),
+    ])
+  }
+
+  pub fn severity_level(&self) -> Severity {
+    self.severity.parse().unwrap_or(Severity::Unknown)
+  }
+
+  pub fn urgency_level(&self) -> Urgency {
+    self.urgency.parse().unwrap_or(Urgency::Unknown)
+  }
+
+  pub fn certainty_level(&self) -> Certainty {
+    self.certainty.parse().unwrap_or(Certainty::Unknown)
+  }
+
+  pub fn effective_at(&self) -> Option<DateTime<Utc>> {
+    parse_time(&self.effective)
+  }
+
+  pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+    parse_time(&self.expires)
+  }
+
+  /// Whether the alert covers a location.
+  /// When the alert has geometry and the location has coordinates, the point must lie
+  /// within one of its areas. Otherwise the city or a part of the region must be named
+  /// in the areas of the alert. Alerts naming no areas at all are assumed to apply, as are
+  /// alerts for locations without a region, such as coordinates or places given by them,
+  /// whose city is at most a nickname. The provider has already scoped those to the point.
+  pub fn covers(&self, location: &Location) -> bool {
+    let point = Point::from_location(location);
+    let geometry: Vec<&Area> = self
+      .geometry
+      .iter()
+      .filter(|area| area.has_geometry())
+      .collect();
+    if let (Some(point), false) = (point, geometry.is_empty()) {
+      return geometry.iter().any(|area| area.contains(&point));
+    }
+
+    let areas = self.areas.to_lowercase();
+    let Some(region) = location
+      .region
+      .as_deref()
+      .filter(|region| !region.trim().is_empty())
+    else {
+      return true;
+    };
+    if areas.trim().is_empty() {
+      return true;
+    }
+
+    location
+      .city
+      .iter()
+      .map(String::as_str)
+      .chain(region.split(','))
+      .map(|name| name.trim().to_lowercase())
+      .any(|name| name.len() >= 3 && areas.contains(&name))
+  }
+
+  /// Whether the alert is in effect at time `now`.
+  /// Missing or malformed times are treated as open ended.
+  pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+    let started = self.effective_at().is_none_or(|effective| effective <= now);
+    let ended = self.expires_at().is_some_and(|expires| expires <= now);
+    started && !ended
+  }
 }
 
 impl From<AlertResponse> for Alert {
@@ -128,27 +315,166 @@ impl From<AlertResponse> for Alert {
   }
 }
 
+#[allow(clippy::to_string_trait_impl)]
 impl ToString for Alert {
   fn to_string(&self) -> String {
-    format!(
-      "Headline: {}\nType: {}\nDescription: {}\nSeverity: {}\nUrgency: {}\nAreas: {}\nCategory: {}\nCertainty: {}\nEvent: {}\nNote: {}\nEffective: {}\nExpires: {}\nInstruction: {}\n",
-      self.headline,
-      self.message_type,
-      self.description,
-      self.severity,
-      self.urgency,
-      self.areas,
-      self.category,
-      self.certainty,
-      self.event,
-      self.note,
-      self.effective,
-      self.expires,
-      self.instruction
-    )
+    let locale = locale::current();
+    let mut result = String::new();
+    for (label, value) in [
+      ("Headline", &self.headline),
+      ("Type", &self.message_type),
+      ("Description", &self.description),
+      ("Severity", &self.severity),
+      ("Urgency", &self.urgency),
+      ("Areas", &self.areas),
+      ("Category", &self.category),
+      ("Certainty", &self.certainty),
+      ("Event", &self.event),
+      ("Note", &self.note),
+      ("Effective", &self.effective),
+      ("Expires", &self.expires),
+      ("Instruction", &self.instruction),
+    ] {
+      result.push_str(&format!("{}: {}\n", locale.label(label), value));
+    }
+    result
+  }
+}
+
+impl FromStr for Severity {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_lowercase().as_str() {
+      "unknown" | "" => Ok(Severity::Unknown),
+      "minor" => Ok(Severity::Minor),
+      "moderate" => Ok(Severity::Moderate),
+      "severe" => Ok(Severity::Severe),
+      "extreme" => Ok(Severity::Extreme),
+      _ => Err(Error::InvalidArgument {
+        arg: s.to_string(),
+        message: "Severity should be one of minor, moderate, severe or extreme.".to_string(),
+      }),
+    }
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Severity {
+  fn to_string(&self) -> String {
+    match self {
+      Severity::Unknown => "Unknown",
+      Severity::Minor => "Minor",
+      Severity::Moderate => "Moderate",
+      Severity::Severe => "Severe",
+      Severity::Extreme => "Extreme",
+    }
+    .to_string()
+  }
+}
+
+impl FromStr for Urgency {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_lowercase().as_str() {
+      "unknown" | "" => Ok(Urgency::Unknown),
+      "past" => Ok(Urgency::Past),
+      "future" => Ok(Urgency::Future),
+      "expected" => Ok(Urgency::Expected),
+      "immediate" => Ok(Urgency::Immediate),
+      _ => Err(Error::InvalidArgument {
+        arg: s.to_string(),
+        message: "Urgency should be one of past, future, expected or immediate.".to_string(),
+      }),
+    }
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Urgency {
+  fn to_string(&self) -> String {
+    match self {
+      Urgency::Unknown => "Unknown",
+      Urgency::Past => "Past",
+      Urgency::Future => "Future",
+      Urgency::Expected => "Expected",
+      Urgency::Immediate => "Immediate",
+    }
+    .to_string()
+  }
+}
+
+impl FromStr for Certainty {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_lowercase().as_str() {
+      "unknown" | "" => Ok(Certainty::Unknown),
+      "unlikely" => Ok(Certainty::Unlikely),
+      "possible" => Ok(Certainty::Possible),
+      // CAP 1.0 used "Very Likely", which CAP 1.1 and later treat as "Likely".
+      "likely" | "very likely" => Ok(Certainty::Likely),
+      "observed" => Ok(Certainty::Observed),
+      _ => Err(Error::InvalidArgument {
+        arg: s.to_string(),
+        message: "Certainty should be one of unlikely, possible, likely or observed.".to_string(),
+      }),
+    }
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Certainty {
+  fn to_string(&self) -> String {
+    match self {
+      Certainty::Unknown => "Unknown",
+      Certainty::Unlikely => "Unlikely",
+      Certainty::Possible => "Possible",
+      Certainty::Likely => "Likely",
+      Certainty::Observed => "Observed",
+    }
+    .to_string()
+  }
+}
+
+impl Filter {
+  /// Check if an alert satisfies every criteria of the filter at time `now`.
+  /// Events are matched case insensitively against the alert event.
+  pub fn matches(&self, alert: &Alert, now: DateTime<Utc>) -> bool {
+    if let Some(min) = self.min_severity {
+      if alert.severity_level() < min {
+        return false;
+      }
+    }
+
+    if !self.events.is_empty() {
+      let event = alert.event.to_lowercase();
+      if !self
+        .events
+        .iter()
+        .any(|wanted| event.contains(&wanted.to_lowercase()))
+      {
+        return false;
+      }
+    }
+
+    if let Some(location) = &self.location {
+      if !alert.covers(location) {
+        return false;
+      }
+    }
+
+    !self.active_only || alert.is_active(now)
   }
 }
 
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+  DateTime::parse_from_rfc3339(time.trim())
+    .ok()
+    .map(|time| time.with_timezone(&Utc))
+}
+
 /// Get weather alerts for a location.
 /// Returns a Result with the Alerts struct or an Error from this crate.
 pub async fn get_alerts(location: Location, days: i32) -> Result<Alerts, Error> {
@@ -157,70 +483,36 @@ pub async fn get_alerts(location: Location, days: i32) -> Result<Alerts, Error>
     Err(err) => return Err(err),
   };
 
-  let address = std::env::var("WEATHER_API").unwrap();
-  let key = std::env::var("WEATHER_KEY").unwrap();
+  let address = http::address();
+  let key = keys::env_key();
 
   let url = format!(
-    "{}/forecast.json?key={}&q={}&days={}&alerts=yes",
-    address, key, location_query, days
+    "{}/forecast.json?key={}&q={}&days={}&alerts=yes{}",
+    address,
+    key,
+    location_query,
+    days,
+    locale::current().query()
   );
 
-  match reqwest::get(&url).await {
-    Ok(response) => {
-      let alerts: Response = response.json::<Response>().await.unwrap();
-      Ok(Alerts::from(alerts))
-    }
-    Err(err) => Err(Error::Fetch {
-      message: err.to_string(),
-    }),
-  }
+  let alerts: Response = http::get_json(&url).await?;
+  Ok(Alerts::from(alerts))
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use mockito::mock;
+  use crate::replay::{self, fixtures};
   use tokio;
 
   fn setup() {
     dotenv::dotenv().ok();
-    std::env::set_var("WEATHER_API", &mockito::server_url());
     std::env::set_var("WEATHER_KEY", "test_key");
   }
 
   #[tokio::test]
   async fn test_get_alerts_success() {
     setup();
-    let _m = mock(
-      "GET",
-      "/forecast.json?key=test_key&q=51.5171,-0.1062&days=3&alerts=yes",
-    )
-    .with_status(200)
-    .with_header("content-type", "application/json")
-    .with_body(
-      r#"{
-        "alerts": {
-          "alert": [
-            {
-              "headline": "Severe Weather Alert",
-              "msgtype": "Alert",
-              "severity": "Severe",
-              "urgency": "Immediate",
-              "areas": "London",
-              "category": "Met",
-              "certainty": "Likely",
-              "event": "Heavy Rain",
-              "note": "Stay indoors",
-              "effective": "2024-12-28T10:00:00Z",
-              "expires": "2024-12-28T18:00:00Z",
-              "desc": "Heavy rain expected in the area.",
-              "instruction": "Stay indoors and avoid travel."
-            }
-          ]
-        }
-      }"#,
-    )
-    .create();
 
     let location = Location {
       country: "United Kingdom".to_string(),
@@ -230,7 +522,7 @@ mod tests {
       lon: Some(-0.1062),
     };
 
-    let result = get_alerts(location, 3).await;
+    let result = replay::scope(fixtures("london"), get_alerts(location, 3)).await;
     assert!(result.is_ok());
 
     let alerts = result.unwrap();
@@ -255,20 +547,6 @@ mod tests {
   #[tokio::test]
   async fn test_get_alerts_empty_alerts() {
     setup();
-    let _m = mock(
-      "GET",
-      "/forecast.json?key=test_key&q=51.5171,-0.1062&days=3&alerts=yes",
-    )
-    .with_status(200)
-    .with_header("content-type", "application/json")
-    .with_body(
-      r#"{
-        "alerts": {
-          "alert": []
-        }
-      }"#,
-    )
-    .create();
 
     let location = Location {
       country: "United Kingdom".to_string(),
@@ -278,13 +556,209 @@ mod tests {
       lon: Some(-0.1062),
     };
 
-    let result = get_alerts(location, 3).await;
+    let result = replay::scope(fixtures("london-calm"), get_alerts(location, 3)).await;
     assert!(result.is_ok());
 
     let alerts = result.unwrap();
     assert_eq!(alerts.alerts.len(), 0);
   }
 
+  fn alert(headline: &str, severity: &str, event: &str, effective: &str, expires: &str) -> Alert {
+    Alert {
+      headline: headline.to_string(),
+      severity: severity.to_string(),
+      event: event.to_string(),
+      effective: effective.to_string(),
+      expires: expires.to_string(),
+      ..Alert::sample()
+    }
+  }
+
+  fn now() -> DateTime<Utc> {
+    parse_time("2024-12-28T12:00:00Z").unwrap()
+  }
+
+  #[test]
+  fn test_severity_order() {
+    assert!(Severity::Extreme > Severity::Severe);
+    assert!(Severity::Severe > Severity::Moderate);
+    assert!(Severity::Minor > Severity::Unknown);
+    assert_eq!("SEVERE".parse::<Severity>().unwrap(), Severity::Severe);
+    assert!("bad".parse::<Severity>().is_err());
+  }
+
+  #[test]
+  fn test_dedup() {
+    let mut other_area = alert(
+      "Rain",
+      "Severe",
+      "Heavy Rain",
+      "2024-12-28T10:00:00Z",
+      "2024-12-28T18:00:00Z",
+    );
+    other_area.areas = "Kent".to_string();
+
+    let alerts = Alerts::new(vec![
+      alert(
+        "Rain",
+        "Severe",
+        "Heavy Rain",
+        "2024-12-28T10:00:00Z",
+        "2024-12-28T18:00:00Z",
+      ),
+      other_area,
+      alert(
+        "Rain",
+        "Severe",
+        "Heavy Rain",
+        "2024-12-29T10:00:00Z",
+        "2024-12-29T18:00:00Z",
+      ),
+    ]);
+
+    let alerts = alerts.dedup();
+    assert_eq!(alerts.len(), 2);
+    assert_eq!(alerts.alerts[0].areas, "London");
+  }
+
+  #[test]
+  fn test_filter() {
+    let alerts = Alerts::new(vec![
+      alert(
+        "Rain",
+        "Severe",
+        "Heavy Rain",
+        "2024-12-28T10:00:00Z",
+        "2024-12-28T18:00:00Z",
+      ),
+      alert(
+        "Wind",
+        "Moderate",
+        "Wind",
+        "2024-12-28T10:00:00Z",
+        "2024-12-28T18:00:00Z",
+      ),
+      alert(
+        "Snow",
+        "Extreme",
+        "Snow",
+        "2024-12-29T10:00:00Z",
+        "2024-12-29T18:00:00Z",
+      ),
+    ]);
+
+    let filter = Filter {
+      min_severity: Some(Severity::Severe),
+      ..Filter::default()
+    };
+    assert_eq!(alerts.clone().filter(&filter, now()).len(), 2);
+
+    let filter = Filter {
+      events: vec!["rain".to_string()],
+      ..Filter::default()
+    };
+    let filtered = alerts.clone().filter(&filter, now());
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered.alerts[0].headline, "Rain");
+
+    let filter = Filter {
+      active_only: true,
+      ..Filter::default()
+    };
+    assert_eq!(alerts.filter(&filter, now()).len(), 2);
+  }
+
+  fn london() -> Location {
+    Location {
+      country: "United Kingdom".to_string(),
+      region: Some("City of London, Greater London".to_string()),
+      city: Some("London".to_string()),
+      lat: Some(51.5171),
+      lon: Some(-0.1062),
+    }
+  }
+
+  #[test]
+  fn test_covers_geometry() {
+    let mut alert = alert("Fog", "Moderate", "Fog", "", "");
+    alert.areas = "Somewhere else".to_string();
+    alert.geometry = vec![Area {
+      description: "Central London".to_string(),
+      polygons: vec![crate::area::parse_polygon(
+        "51.49,-0.15 51.53,-0.15 51.53,-0.05 51.49,-0.05 51.49,-0.15",
+      )
+      .unwrap()],
+      ..Area::default()
+    }];
+    assert!(alert.covers(&london()));
+
+    let mut outside = london();
+    outside.lon = Some(-0.3);
+    assert!(!alert.covers(&outside));
+
+    // Without coordinates the area names are matched instead.
+    outside.lat = None;
+    assert!(!alert.covers(&outside));
+    alert.areas = "Greater London".to_string();
+    assert!(alert.covers(&outside));
+  }
+
+  #[test]
+  fn test_covers_text() {
+    let mut alert = alert("Fog", "Moderate", "Fog", "", "");
+    assert!(alert.covers(&london()));
+
+    alert.areas = "Kent; Greater London".to_string();
+    let mut croydon = london();
+    croydon.city = Some("Croydon".to_string());
+    assert!(alert.covers(&croydon));
+
+    alert.areas = "Kent; Sussex".to_string();
+    assert!(!alert.covers(&croydon));
+
+    alert.areas = String::new();
+    assert!(alert.covers(&croydon));
+  }
+
+  #[test]
+  fn test_covers_coordinates() {
+    let mut alert = alert("Fog", "Moderate", "Fog", "", "");
+    alert.areas = "Kent; Sussex".to_string();
+    let office = Location {
+      country: String::new(),
+      region: None,
+      city: Some("office".to_string()),
+      lat: Some(51.5171),
+      lon: Some(-0.1062),
+    };
+    assert!(alert.covers(&office));
+    assert!(alert.covers(&Location {
+      city: None,
+      ..office
+    }));
+  }
+
+  #[test]
+  fn test_filter_location() {
+    let mut kent = alert("Fog", "Moderate", "Fog", "", "");
+    kent.areas = "Kent".to_string();
+    let alerts = Alerts::new(vec![alert("Rain", "Severe", "Rain", "", ""), kent]);
+
+    let filter = Filter {
+      location: Some(london()),
+      ..Filter::default()
+    };
+    let filtered = alerts.filter(&filter, now());
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered.alerts[0].headline, "Rain");
+  }
+
+  #[test]
+  fn test_is_active_open_ended() {
+    let alert = alert("Rain", "Severe", "Heavy Rain", "", "not a time");
+    assert!(alert.is_active(now()));
+  }
+
   #[tokio::test]
   async fn test_get_alerts_no_location() {
     setup();
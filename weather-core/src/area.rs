@@ -0,0 +1,252 @@
+use serde::Serialize;
+
+use crate::location::Location;
+
+/// Mean radius of the Earth in kilometres.
+const EARTH_RADIUS: f64 = 6371.0;
+
+/// A position in decimal degrees, as used by CAP and the WeatherAPI.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Point {
+  pub lat: f64,
+  pub lon: f64,
+}
+
+/// A circle around a point, with its radius in kilometres.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Circle {
+  pub center: Point,
+  pub radius: f64,
+}
+
+/// A code identifying a region in some scheme, such as a FIPS or EMMA_ID code.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Geocode {
+  pub name: String,
+  pub value: String,
+}
+
+/// An area an alert applies to.
+/// An area may be described by any mix of polygons, circles and geocodes.
+/// An area with neither polygons nor circles is only known by its name and codes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Area {
+  pub description: String,
+  pub polygons: Vec<Vec<Point>>,
+  pub circles: Vec<Circle>,
+  pub geocodes: Vec<Geocode>,
+}
+
+impl Point {
+  /// Parse a CAP point, `latitude,longitude`.
+  pub fn parse(point: &str) -> Option<Self> {
+    let (lat, lon) = point.trim().split_once(',')?;
+    let point = Point {
+      lat: lat.trim().parse().ok()?,
+      lon: lon.trim().parse().ok()?,
+    };
+    match (-90.0..=90.0).contains(&point.lat) && (-180.0..=180.0).contains(&point.lon) {
+      true => Some(point),
+      false => None,
+    }
+  }
+
+  /// Great circle distance to another point in kilometres.
+  pub fn distance(&self, other: &Point) -> f64 {
+    let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (other.lon - self.lon).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+  }
+
+  /// Point of a location, if it has coordinates.
+  pub fn from_location(location: &Location) -> Option<Self> {
+    Some(Point {
+      lat: location.lat?,
+      lon: location.lon?,
+    })
+  }
+}
+
+impl Circle {
+  pub fn contains(&self, point: &Point) -> bool {
+    self.center.distance(point) <= self.radius
+  }
+
+  /// Parse a CAP circle, a point and a radius separated by a space.
+  pub fn parse(circle: &str) -> Option<Self> {
+    let (center, radius) = circle.trim().split_once(char::is_whitespace)?;
+    Some(Circle {
+      center: Point::parse(center)?,
+      radius: radius.trim().parse().ok()?,
+    })
+  }
+}
+
+impl Area {
+  /// Whether the area has any geometry to test points against.
+  pub fn has_geometry(&self) -> bool {
+    !self.polygons.is_empty() || !self.circles.is_empty()
+  }
+
+  /// Whether a point lies within any polygon or circle of the area.
+  pub fn contains(&self, point: &Point) -> bool {
+    self
+      .polygons
+      .iter()
+      .any(|polygon| polygon_contains(polygon, point))
+      || self.circles.iter().any(|circle| circle.contains(point))
+  }
+}
+
+/// Whether a point lies within a polygon, by counting the edges a ray from the point crosses.
+/// Coordinates are treated as planar, which is accurate enough for alert areas
+/// that do not cross the antimeridian.
+pub fn polygon_contains(polygon: &[Point], point: &Point) -> bool {
+  let mut inside = false;
+  let mut previous = match polygon.last() {
+    Some(last) => last,
+    None => return false,
+  };
+  for current in polygon {
+    if (current.lat > point.lat) != (previous.lat > point.lat) {
+      let lon = current.lon
+        + (point.lat - current.lat) / (previous.lat - current.lat) * (previous.lon - current.lon);
+      if point.lon < lon {
+        inside = !inside;
+      }
+    }
+    previous = current;
+  }
+  inside
+}
+
+/// Parse a CAP polygon, points separated by spaces where the first and last are the same.
+/// Polygons with fewer than three distinct points are rejected.
+pub fn parse_polygon(polygon: &str) -> Option<Vec<Point>> {
+  let mut points = polygon
+    .split_whitespace()
+    .map(Point::parse)
+    .collect::<Option<Vec<_>>>()?;
+  if points.len() > 1 && points.first() == points.last() {
+    points.pop();
+  }
+  match points.len() >= 3 {
+    true => Some(points),
+    false => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_point() {
+    assert_eq!(
+      Point::parse(" 51.5,-0.1 "),
+      Some(Point {
+        lat: 51.5,
+        lon: -0.1
+      })
+    );
+    assert_eq!(Point::parse("91,0"), None);
+    assert_eq!(Point::parse("51.5"), None);
+  }
+
+  #[test]
+  fn test_parse_circle() {
+    let circle = Circle::parse("32.9525,-115.5527 2.5").unwrap();
+    assert_eq!(circle.center.lat, 32.9525);
+    assert_eq!(circle.radius, 2.5);
+    assert_eq!(Circle::parse("32.9525,-115.5527"), None);
+  }
+
+  fn square() -> Vec<Point> {
+    parse_polygon("51.49,-0.15 51.53,-0.15 51.53,-0.05 51.49,-0.05 51.49,-0.15").unwrap()
+  }
+
+  #[test]
+  fn test_polygon_contains() {
+    let square = square();
+    assert!(polygon_contains(
+      &square,
+      &Point {
+        lat: 51.5171,
+        lon: -0.1062
+      }
+    ));
+    assert!(!polygon_contains(
+      &square,
+      &Point {
+        lat: 51.5171,
+        lon: -0.2
+      }
+    ));
+    assert!(!polygon_contains(&[], &Point { lat: 0.0, lon: 0.0 }));
+
+    // A concave polygon, shaped like a U opening to the north.
+    let u = parse_polygon("0,0 0,3 3,3 3,2 1,2 1,1 3,1 3,0 0,0").unwrap();
+    assert!(polygon_contains(&u, &Point { lat: 2.0, lon: 0.5 }));
+    assert!(!polygon_contains(&u, &Point { lat: 2.0, lon: 1.5 }));
+  }
+
+  #[test]
+  fn test_circle_contains() {
+    let circle = Circle::parse("51.5074,-0.1278 5").unwrap();
+    // Roughly 1.6 km from the centre.
+    assert!(circle.contains(&Point {
+      lat: 51.5171,
+      lon: -0.1062
+    }));
+    // Roughly 12 km from the centre.
+    assert!(!circle.contains(&Point {
+      lat: 51.6,
+      lon: -0.2
+    }));
+  }
+
+  #[test]
+  fn test_distance() {
+    let london = Point {
+      lat: 51.5074,
+      lon: -0.1278,
+    };
+    let paris = Point {
+      lat: 48.8566,
+      lon: 2.3522,
+    };
+    assert!((london.distance(&paris) - 343.5).abs() < 1.0);
+  }
+
+  #[test]
+  fn test_area_contains() {
+    let area = Area {
+      description: "Central London".to_string(),
+      polygons: vec![square()],
+      ..Area::default()
+    };
+    assert!(area.has_geometry());
+    assert!(area.contains(&Point {
+      lat: 51.5,
+      lon: -0.1
+    }));
+    assert!(!Area::default().has_geometry());
+    assert!(!Area::default().contains(&Point {
+      lat: 51.5,
+      lon: -0.1
+    }));
+  }
+
+  #[test]
+  fn test_parse_polygon() {
+    let polygon = parse_polygon("38.47,-120.14 38.34,-119.95 38.52,-119.74 38.47,-120.14").unwrap();
+    assert_eq!(polygon.len(), 3);
+    assert_eq!(
+      parse_polygon("38.47,-120.14 38.34,-119.95 38.47,-120.14"),
+      None
+    );
+    assert_eq!(parse_polygon("38.47,-120.14 nonsense"), None);
+  }
+}
@@ -1,7 +1,7 @@
 use chrono::Local;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{error::Error, location::Location, Query};
+use crate::{error::Error, http, keys, locale, location::Location, Query};
 
 #[derive(Deserialize, Debug)]
 struct Response {
@@ -14,18 +14,20 @@ struct AstronomyResponse {
 }
 
 #[derive(Deserialize, Debug)]
-struct AstroResponse {
+pub(crate) struct AstroResponse {
   sunrise: String,
   sunset: String,
   moonrise: String,
   moonset: String,
   moon_phase: String,
   moon_illumination: i32,
+  #[serde(default)]
   is_moon_up: i32,
+  #[serde(default)]
   is_sun_up: i32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Astronomy {
   pub sunrise: String,
   pub sunset: String,
@@ -38,6 +40,7 @@ pub struct Astronomy {
 }
 
 impl Astronomy {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     sunrise: String,
     sunset: String,
@@ -63,32 +66,43 @@ impl Astronomy {
 
 impl From<Response> for Astronomy {
   fn from(response: Response) -> Self {
+    Astronomy::from(response.astronomy.astro)
+  }
+}
+
+impl From<AstroResponse> for Astronomy {
+  fn from(astro: AstroResponse) -> Self {
     Astronomy::new(
-      response.astronomy.astro.sunrise,
-      response.astronomy.astro.sunset,
-      response.astronomy.astro.moonrise,
-      response.astronomy.astro.moonset,
-      response.astronomy.astro.moon_phase,
-      response.astronomy.astro.moon_illumination,
-      response.astronomy.astro.is_moon_up,
-      response.astronomy.astro.is_sun_up,
+      astro.sunrise,
+      astro.sunset,
+      astro.moonrise,
+      astro.moonset,
+      astro.moon_phase,
+      astro.moon_illumination,
+      astro.is_moon_up,
+      astro.is_sun_up,
     )
   }
 }
 
+#[allow(clippy::to_string_trait_impl)]
 impl ToString for Astronomy {
   fn to_string(&self) -> String {
-    format!(
-      "Sunrise: {}\nSunset: {}\nMoonrise: {}\nMoonset: {}\nMoon Phase: {}\nMoon Illumination: {}%\nIs Moon Up: {}\nIs Sun Up: {}",
-      self.sunrise,
-      self.sunset,
-      self.moonrise,
-      self.moonset,
-      self.moon_phase,
-      self.moon_illumination,
-      self.is_moon_up,
-      self.is_sun_up
-    )
+    let locale = locale::current();
+    [
+      ("Sunrise", locale.time(&self.sunrise)),
+      ("Sunset", locale.time(&self.sunset)),
+      ("Moonrise", locale.time(&self.moonrise)),
+      ("Moonset", locale.time(&self.moonset)),
+      ("Moon Phase", self.moon_phase.clone()),
+      ("Moon Illumination", format!("{}%", self.moon_illumination)),
+      ("Is Moon Up", locale.boolean(self.is_moon_up).to_string()),
+      ("Is Sun Up", locale.boolean(self.is_sun_up).to_string()),
+    ]
+    .iter()
+    .map(|(label, value)| format!("{}: {}", locale.label(label), value))
+    .collect::<Vec<_>>()
+    .join("\n")
   }
 }
 
@@ -103,77 +117,36 @@ pub async fn get_current_astronomy(location: Location) -> Result<Astronomy, Erro
 
   let dt = Local::now().format("%Y-%m-%d").to_string();
 
-  let address = std::env::var("WEATHER_API").unwrap();
-  let key = std::env::var("WEATHER_KEY").unwrap();
+  let address = http::address();
+  let key = keys::env_key();
 
   let url = format!(
-    "{}/astronomy.json?key={}&q={}&dt={}",
-    address, key, location_query, dt
+    "{}/astronomy.json?key={}&q={}&dt={}{}",
+    address,
+    key,
+    location_query,
+    dt,
+    locale::current().query()
   );
 
-  match reqwest::get(&url).await {
-    Ok(response) => {
-      let weather: Response = response.json().await.unwrap();
-      Ok(Astronomy::from(weather))
-    }
-    Err(err) => Err(Error::Fetch {
-      message: err.to_string(),
-    }),
-  }
+  let weather: Response = http::get_json(&url).await?;
+  Ok(Astronomy::from(weather))
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use mockito::mock;
+  use crate::replay::{self, fixtures};
   use tokio;
 
   fn setup() {
     dotenv::dotenv().ok();
-    std::env::set_var("WEATHER_API", &mockito::server_url());
     std::env::set_var("WEATHER_KEY", "test_key");
   }
 
   #[tokio::test]
   async fn test_get_current_astronomy_success() {
     setup();
-    let _m = mock(
-      "GET",
-      format!(
-        "/astronomy.json?key=test_key&q=51.5171,-0.1062&dt={}",
-        Local::now().format("%Y-%m-%d")
-      )
-      .as_str(),
-    )
-    .with_status(200)
-    .with_header("content-type", "application/json")
-    .with_body(
-      r#"{
-        "location": {
-          "name": "London",
-          "region": "City of London, Greater London",
-          "country": "United Kingdom",
-          "lat": 51.5171,
-          "lon": -0.1062,
-          "tz_id": "Europe/London",
-          "localtime": "2024-12-28 11:39"
-        },
-        "astronomy": {
-          "astro": {
-            "sunrise": "08:06 AM",
-            "sunset": "03:58 PM",
-            "moonrise": "06:11 AM",
-            "moonset": "01:21 PM",
-            "moon_phase": "Waning Crescent",
-            "moon_illumination": 9,
-            "is_moon_up": 0,
-            "is_sun_up": 0
-          }
-        }
-      }"#,
-    )
-    .create();
-
     let location = Location {
       country: "United Kingdom".to_string(),
       region: Some("City of London, Greater London".to_string()),
@@ -182,7 +155,7 @@ mod tests {
       lon: Some(-0.1062),
     };
 
-    let result = get_current_astronomy(location).await;
+    let result = replay::scope(fixtures("london"), get_current_astronomy(location)).await;
     assert!(result.is_ok());
 
     let astronomy = result.unwrap();
@@ -190,8 +163,8 @@ mod tests {
     assert_eq!(astronomy.sunset, "03:58 PM");
     assert_eq!(astronomy.moon_phase, "Waning Crescent");
     assert_eq!(astronomy.moon_illumination, 9);
-    assert_eq!(astronomy.is_moon_up, false);
-    assert_eq!(astronomy.is_sun_up, false);
+    assert!(!astronomy.is_moon_up);
+    assert!(!astronomy.is_sun_up);
   }
 
   #[tokio::test]
@@ -0,0 +1,130 @@
+use std::{
+  fs,
+  path::PathBuf,
+  time::{Duration, SystemTime},
+};
+
+use crate::{config::CacheConfig, fingerprint};
+
+/// On disk cache of provider responses, keyed by request URL.
+/// Entries older than the time to live are ignored and overwritten on the next fetch.
+#[derive(Debug, Clone)]
+pub struct Cache {
+  dir: PathBuf,
+  ttl: Duration,
+}
+
+impl Cache {
+  pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+    Cache { dir, ttl }
+  }
+
+  /// Default cache directory, `weather` in the platform cache directory.
+  pub fn default_dir() -> PathBuf {
+    dirs::cache_dir()
+      .unwrap_or_else(std::env::temp_dir)
+      .join("weather")
+  }
+
+  pub fn ttl(&self) -> Duration {
+    self.ttl
+  }
+
+  /// The cache with a time to live of at most `ttl`, so responses are never older than that.
+  pub fn limit_ttl(self, ttl: Duration) -> Self {
+    Cache {
+      ttl: self.ttl.min(ttl),
+      ..self
+    }
+  }
+
+  /// Get a fresh cached response body for a URL, if there is one.
+  pub fn get(&self, url: &str) -> Option<String> {
+    self.get_stored(url).map(|(body, _)| body)
+  }
+
+  /// Get a fresh cached response body for a URL with the time it was stored, if there is one.
+  pub fn get_stored(&self, url: &str) -> Option<(String, SystemTime)> {
+    let path = self.path(url);
+    let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok()?;
+    let age = SystemTime::now()
+      .duration_since(modified)
+      .unwrap_or_default();
+    if age >= self.ttl {
+      return None;
+    }
+    fs::read_to_string(path).ok().map(|body| (body, modified))
+  }
+
+  /// Store a response body for a URL.
+  /// Failing to write the cache is not fatal, the response is simply not cached.
+  pub fn put(&self, url: &str, body: &str) {
+    if fs::create_dir_all(&self.dir).is_ok() {
+      fs::write(self.path(url), body).ok();
+    }
+  }
+
+  fn path(&self, url: &str) -> PathBuf {
+    self.dir.join(format!("{}.json", fingerprint(&[url])))
+  }
+}
+
+impl From<&CacheConfig> for Option<Cache> {
+  fn from(config: &CacheConfig) -> Self {
+    if !config.enabled || config.ttl == 0 {
+      return None;
+    }
+
+    let dir = config.dir.clone().unwrap_or_else(Cache::default_dir);
+    Some(Cache::new(dir, Duration::from_secs(config.ttl)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_put_and_get() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().to_path_buf(), Duration::from_secs(60));
+
+    assert_eq!(cache.get("http://localhost/current.json"), None);
+    cache.put("http://localhost/current.json", "{}");
+    assert_eq!(
+      cache.get("http://localhost/current.json"),
+      Some("{}".to_string())
+    );
+    assert_eq!(cache.get("http://localhost/forecast.json"), None);
+  }
+
+  #[test]
+  fn test_expired() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().to_path_buf(), Duration::ZERO);
+
+    cache.put("http://localhost/current.json", "{}");
+    assert_eq!(cache.get("http://localhost/current.json"), None);
+  }
+
+  #[test]
+  fn test_from_config() {
+    let config = CacheConfig {
+      enabled: false,
+      ..CacheConfig::default()
+    };
+    assert!(Option::<Cache>::from(&config).is_none());
+
+    let config = CacheConfig::default();
+    let cache = Option::<Cache>::from(&config).unwrap();
+    assert_eq!(cache.ttl(), Duration::from_secs(300));
+    assert_eq!(
+      cache.clone().limit_ttl(Duration::from_secs(60)).ttl(),
+      Duration::from_secs(60)
+    );
+    assert_eq!(
+      cache.limit_ttl(Duration::from_secs(600)).ttl(),
+      Duration::from_secs(300)
+    );
+  }
+}
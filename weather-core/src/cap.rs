@@ -0,0 +1,343 @@
+use roxmltree::{Document, Node};
+use tokio::{fs, io::AsyncReadExt};
+
+use crate::{
+  alert::{Alert, Alerts, Certainty, Severity, Urgency},
+  area::{parse_polygon, Area, Circle, Geocode},
+  error::Error,
+  http,
+  locale::{self, Locale},
+};
+
+/// Language of an info block that does not give one, as defined by CAP 1.2.
+const DEFAULT_LANGUAGE: &str = "en-US";
+
+/// Read Common Alerting Protocol messages from a URL, a file, or standard input when `source` is `-`.
+pub async fn get_cap(source: &str) -> Result<Alerts, Error> {
+  let xml = if source.starts_with("http://") || source.starts_with("https://") {
+    http::get(source).await?
+  } else if source == "-" {
+    let mut xml = String::new();
+    tokio::io::stdin()
+      .read_to_string(&mut xml)
+      .await
+      .map_err(|err| Error::Io {
+        path: "stdin".to_string(),
+        message: err.to_string(),
+      })?;
+    xml
+  } else {
+    fs::read_to_string(source).await.map_err(|err| Error::Io {
+      path: source.to_string(),
+      message: err.to_string(),
+    })?
+  };
+
+  parse(&xml)
+}
+
+/// Parse CAP 1.2 XML into alerts, one per message, in the language of the current locale.
+/// Messages may be standalone or embedded in another document, such as an Atom feed.
+/// Severity, urgency and certainty are normalised, with unrecognised values as unknown.
+pub fn parse(xml: &str) -> Result<Alerts, Error> {
+  parse_in(xml, &locale::current())
+}
+
+fn parse_in(xml: &str, locale: &Locale) -> Result<Alerts, Error> {
+  let document = Document::parse(xml).map_err(|err| Error::Response {
+    message: format!("invalid CAP document: {}", err),
+  })?;
+
+  let messages: Vec<Node> = document
+    .descendants()
+    .filter(|node| node.tag_name().name() == "alert")
+    .collect();
+  if messages.is_empty() {
+    return Err(Error::Response {
+      message: "no CAP alert in document".to_string(),
+    });
+  }
+
+  let alerts = messages
+    .into_iter()
+    .filter_map(|message| Some(alert(message, info(message, locale)?)))
+    .collect();
+  Ok(Alerts::new(alerts))
+}
+
+/// The info block of a message in the language and region of the locale, then in its language,
+/// falling back to the first block.
+fn info<'a, 'input>(message: Node<'a, 'input>, locale: &Locale) -> Option<Node<'a, 'input>> {
+  let language = |info: Node| {
+    let language = text(info, "language").unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    let mut parts = language.split('-');
+    (
+      parts.next().unwrap_or_default().to_lowercase(),
+      parts.next().map(str::to_uppercase),
+    )
+  };
+  let matches = |region: bool| {
+    children(message, "info").find(|info| {
+      let (language, info_region) = language(*info);
+      language == locale.language && (!region || info_region == locale.region)
+    })
+  };
+  matches(true)
+    .or_else(|| matches(false))
+    .or_else(|| child(message, "info"))
+}
+
+fn alert(message: Node, info: Node) -> Alert {
+  let areas: Vec<Area> = children(info, "area").map(area).collect();
+
+  let mut alert = Alert::new(
+    text(info, "headline")
+      .or_else(|| text(info, "event"))
+      .unwrap_or_default(),
+    text(message, "msgType").unwrap_or_default(),
+    text(info, "description").unwrap_or_default(),
+    text(info, "severity")
+      .and_then(|severity| severity.parse::<Severity>().ok())
+      .unwrap_or(Severity::Unknown)
+      .to_string(),
+    text(info, "urgency")
+      .and_then(|urgency| urgency.parse::<Urgency>().ok())
+      .unwrap_or(Urgency::Unknown)
+      .to_string(),
+    areas
+      .iter()
+      .map(|area| area.description.as_str())
+      .filter(|description| !description.is_empty())
+      .collect::<Vec<_>>()
+      .join("; "),
+    children(info, "category")
+      .filter_map(|category| category.text())
+      .map(str::trim)
+      .collect::<Vec<_>>()
+      .join(", "),
+    text(info, "certainty")
+      .and_then(|certainty| certainty.parse::<Certainty>().ok())
+      .unwrap_or(Certainty::Unknown)
+      .to_string(),
+    text(info, "event").unwrap_or_default(),
+    text(message, "note").unwrap_or_default(),
+    text(info, "effective")
+      .or_else(|| text(message, "sent"))
+      .unwrap_or_default(),
+    text(info, "expires").unwrap_or_default(),
+    text(info, "instruction").unwrap_or_default(),
+  );
+  alert.language = text(info, "language").unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+  alert.geometry = areas;
+  alert
+}
+
+/// Malformed polygons and circles are dropped rather than failing the whole message.
+fn area(node: Node) -> Area {
+  Area {
+    description: text(node, "areaDesc").unwrap_or_default(),
+    polygons: children(node, "polygon")
+      .filter_map(|polygon| polygon.text().and_then(parse_polygon))
+      .collect(),
+    circles: children(node, "circle")
+      .filter_map(|circle| circle.text().and_then(Circle::parse))
+      .collect(),
+    geocodes: children(node, "geocode")
+      .filter_map(|geocode| {
+        Some(Geocode {
+          name: text(geocode, "valueName")?,
+          value: text(geocode, "value")?,
+        })
+      })
+      .collect(),
+  }
+}
+
+/// Child elements with a local name, in any namespace.
+fn children<'a, 'input>(
+  node: Node<'a, 'input>,
+  name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+  node
+    .children()
+    .filter(move |child| child.tag_name().name() == name)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &'static str) -> Option<Node<'a, 'input>> {
+  children(node, name).next()
+}
+
+/// Trimmed text of the first child element with a local name, if not empty.
+fn text(node: Node, name: &'static str) -> Option<String> {
+  let text = child(node, name)?.text()?.trim();
+  match text.is_empty() {
+    true => None,
+    false => Some(text.to_string()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Write;
+
+  const CAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<alert xmlns="urn:oasis:names:tc:emergency:cap:1.2">
+  <identifier>43b080713727</identifier>
+  <sender>hsas@dhs.gov</sender>
+  <sent>2024-12-28T06:00:00-00:00</sent>
+  <status>Actual</status>
+  <msgType>Alert</msgType>
+  <scope>Public</scope>
+  <note>Test message</note>
+  <info>
+    <language>en-GB</language>
+    <category>Met</category>
+    <category>Safety</category>
+    <event>Flood Warning</event>
+    <urgency>immediate</urgency>
+    <severity>SEVERE</severity>
+    <certainty>Very Likely</certainty>
+    <effective>2024-12-28T07:00:00-00:00</effective>
+    <expires>2024-12-29T07:00:00-00:00</expires>
+    <headline>Flood warning for the Thames</headline>
+    <description>River levels are rising.</description>
+    <instruction>Move to higher ground.</instruction>
+    <area>
+      <areaDesc>Central London</areaDesc>
+      <polygon>51.49,-0.15 51.53,-0.15 51.53,-0.05 51.49,-0.05 51.49,-0.15</polygon>
+      <circle>51.51,-0.1 5</circle>
+      <geocode>
+        <valueName>EMMA_ID</valueName>
+        <value>UK001</value>
+      </geocode>
+    </area>
+    <area>
+      <areaDesc>Kent</areaDesc>
+    </area>
+  </info>
+  <info>
+    <language>cy</language>
+    <category>Met</category>
+    <event>Rhybudd Llifogydd</event>
+    <urgency>Immediate</urgency>
+    <severity>Severe</severity>
+    <certainty>Likely</certainty>
+    <expires>2024-12-29T07:00:00-00:00</expires>
+    <area>
+      <areaDesc>Canol Llundain</areaDesc>
+    </area>
+  </info>
+</alert>"#;
+
+  fn locale(name: &str) -> Locale {
+    name.parse().unwrap()
+  }
+
+  #[test]
+  fn test_parse() {
+    let alerts = parse_in(CAP, &Locale::default()).unwrap();
+    assert_eq!(alerts.len(), 1);
+
+    let alert = &alerts.alerts[0];
+    assert_eq!(alert.headline, "Flood warning for the Thames");
+    assert_eq!(alert.message_type, "Alert");
+    assert_eq!(alert.language, "en-GB");
+    assert_eq!(alert.category, "Met, Safety");
+    assert_eq!(alert.severity, "Severe");
+    assert_eq!(alert.urgency, "Immediate");
+    assert_eq!(alert.certainty, "Likely");
+    assert_eq!(alert.note, "Test message");
+    assert_eq!(alert.areas, "Central London; Kent");
+    assert_eq!(alert.instruction, "Move to higher ground.");
+    assert_eq!(
+      alert.effective_at().unwrap().to_rfc3339(),
+      "2024-12-28T07:00:00+00:00"
+    );
+
+    assert_eq!(alert.geometry.len(), 2);
+    let area = &alert.geometry[0];
+    assert_eq!(area.polygons.len(), 1);
+    assert_eq!(area.polygons[0].len(), 4);
+    assert_eq!(area.circles[0].radius, 5.0);
+    assert_eq!(area.geocodes[0].name, "EMMA_ID");
+    assert_eq!(area.geocodes[0].value, "UK001");
+    assert!(alert.geometry[1].polygons.is_empty());
+  }
+
+  #[test]
+  fn test_parse_info_defaults() {
+    let alert = &parse_in(CAP, &locale("cy_GB")).unwrap().alerts[0];
+    assert_eq!(alert.language, "cy");
+    assert_eq!(alert.headline, "Rhybudd Llifogydd");
+    assert_eq!(alert.effective, "2024-12-28T06:00:00-00:00");
+    assert_eq!(alert.description, "");
+  }
+
+  #[test]
+  fn test_parse_unknown_levels() {
+    let xml = CAP
+      .replace("SEVERE", "Catastrophic")
+      .replace("immediate", "Soon")
+      .replace("<language>en-GB</language>", "");
+    let alert = &parse_in(&xml, &Locale::default()).unwrap().alerts[0];
+    assert_eq!(alert.severity, "Unknown");
+    assert_eq!(alert.urgency, "Unknown");
+    assert_eq!(alert.language, "en-US");
+  }
+
+  #[test]
+  fn test_parse_language() {
+    let event = |name: &str| {
+      parse_in(CAP, &locale(name)).unwrap().alerts[0]
+        .event
+        .clone()
+    };
+    assert_eq!(event("en_GB"), "Flood Warning");
+    assert_eq!(event("cy"), "Rhybudd Llifogydd");
+    assert_eq!(event("de_DE"), "Flood Warning");
+
+    let xml = CAP.replace("<language>cy</language>", "<language>en-US</language>");
+    let alerts = parse_in(&xml, &locale("en_US")).unwrap();
+    assert_eq!(alerts.alerts[0].event, "Rhybudd Llifogydd");
+  }
+
+  #[test]
+  fn test_parse_without_info() {
+    let xml =
+      r#"<alert xmlns="urn:oasis:names:tc:emergency:cap:1.2"><msgType>Cancel</msgType></alert>"#;
+    assert!(parse(xml).unwrap().is_empty());
+  }
+
+  #[test]
+  fn test_parse_invalid() {
+    assert!(parse("<alert>").is_err());
+    assert!(parse("<feed></feed>").is_err());
+  }
+
+  #[tokio::test]
+  async fn test_get_cap_file() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(CAP.as_bytes()).unwrap();
+
+    let alerts = get_cap(file.path().to_str().unwrap()).await.unwrap();
+    assert_eq!(alerts.len(), 1);
+
+    assert!(matches!(
+      get_cap("/nonexistent/alert.xml").await,
+      Err(Error::Io { .. })
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_get_cap_url() {
+    let _m = mockito::mock("GET", "/cap/alert.xml")
+      .with_status(200)
+      .with_body(CAP)
+      .create();
+
+    let url = format!("{}/cap/alert.xml", mockito::server_url());
+    let alerts = get_cap(&url).await.unwrap();
+    assert_eq!(alerts.alerts[0].event, "Flood Warning");
+  }
+}
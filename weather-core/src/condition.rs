@@ -0,0 +1,437 @@
+use serde::Serialize;
+
+/// Provider neutral kind of weather condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionKind {
+  Clear,
+  PartlyCloudy,
+  Cloudy,
+  Overcast,
+  Mist,
+  Fog,
+  FreezingFog,
+  Drizzle,
+  FreezingDrizzle,
+  Rain,
+  HeavyRain,
+  FreezingRain,
+  Showers,
+  Sleet,
+  Snow,
+  HeavySnow,
+  BlowingSnow,
+  IcePellets,
+  Thunder,
+  ThunderRain,
+  ThunderSnow,
+  Unknown,
+}
+
+/// A WeatherAPI condition code.
+/// `icon` is the number of the provider's icon image, shared by the day and night sets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Code {
+  pub code: i32,
+  pub day: &'static str,
+  pub night: &'static str,
+  pub icon: i32,
+  pub kind: ConditionKind,
+}
+
+const fn code(
+  code: i32,
+  day: &'static str,
+  night: &'static str,
+  icon: i32,
+  kind: ConditionKind,
+) -> Code {
+  Code {
+    code,
+    day,
+    night,
+    icon,
+    kind,
+  }
+}
+
+/// Every condition code documented by WeatherAPI, ordered by code.
+pub const CODES: [Code; 48] = {
+  use ConditionKind::*;
+  [
+    code(1000, "Sunny", "Clear", 113, Clear),
+    code(1003, "Partly cloudy", "Partly cloudy", 116, PartlyCloudy),
+    code(1006, "Cloudy", "Cloudy", 119, Cloudy),
+    code(1009, "Overcast", "Overcast", 122, Overcast),
+    code(1030, "Mist", "Mist", 143, Mist),
+    code(
+      1063,
+      "Patchy rain possible",
+      "Patchy rain possible",
+      176,
+      Showers,
+    ),
+    code(
+      1066,
+      "Patchy snow possible",
+      "Patchy snow possible",
+      179,
+      Snow,
+    ),
+    code(
+      1069,
+      "Patchy sleet possible",
+      "Patchy sleet possible",
+      182,
+      Sleet,
+    ),
+    code(
+      1072,
+      "Patchy freezing drizzle possible",
+      "Patchy freezing drizzle possible",
+      185,
+      FreezingDrizzle,
+    ),
+    code(
+      1087,
+      "Thundery outbreaks possible",
+      "Thundery outbreaks possible",
+      200,
+      Thunder,
+    ),
+    code(1114, "Blowing snow", "Blowing snow", 227, BlowingSnow),
+    code(1117, "Blizzard", "Blizzard", 230, BlowingSnow),
+    code(1135, "Fog", "Fog", 248, Fog),
+    code(1147, "Freezing fog", "Freezing fog", 260, FreezingFog),
+    code(
+      1150,
+      "Patchy light drizzle",
+      "Patchy light drizzle",
+      263,
+      Drizzle,
+    ),
+    code(1153, "Light drizzle", "Light drizzle", 266, Drizzle),
+    code(
+      1168,
+      "Freezing drizzle",
+      "Freezing drizzle",
+      281,
+      FreezingDrizzle,
+    ),
+    code(
+      1171,
+      "Heavy freezing drizzle",
+      "Heavy freezing drizzle",
+      284,
+      FreezingDrizzle,
+    ),
+    code(1180, "Patchy light rain", "Patchy light rain", 293, Showers),
+    code(1183, "Light rain", "Light rain", 296, Rain),
+    code(
+      1186,
+      "Moderate rain at times",
+      "Moderate rain at times",
+      299,
+      Rain,
+    ),
+    code(1189, "Moderate rain", "Moderate rain", 302, Rain),
+    code(
+      1192,
+      "Heavy rain at times",
+      "Heavy rain at times",
+      305,
+      HeavyRain,
+    ),
+    code(1195, "Heavy rain", "Heavy rain", 308, HeavyRain),
+    code(
+      1198,
+      "Light freezing rain",
+      "Light freezing rain",
+      311,
+      FreezingRain,
+    ),
+    code(
+      1201,
+      "Moderate or heavy freezing rain",
+      "Moderate or heavy freezing rain",
+      314,
+      FreezingRain,
+    ),
+    code(1204, "Light sleet", "Light sleet", 317, Sleet),
+    code(
+      1207,
+      "Moderate or heavy sleet",
+      "Moderate or heavy sleet",
+      320,
+      Sleet,
+    ),
+    code(1210, "Patchy light snow", "Patchy light snow", 323, Snow),
+    code(1213, "Light snow", "Light snow", 326, Snow),
+    code(
+      1216,
+      "Patchy moderate snow",
+      "Patchy moderate snow",
+      329,
+      Snow,
+    ),
+    code(1219, "Moderate snow", "Moderate snow", 332, Snow),
+    code(
+      1222,
+      "Patchy heavy snow",
+      "Patchy heavy snow",
+      335,
+      HeavySnow,
+    ),
+    code(1225, "Heavy snow", "Heavy snow", 338, HeavySnow),
+    code(1237, "Ice pellets", "Ice pellets", 350, IcePellets),
+    code(1240, "Light rain shower", "Light rain shower", 353, Showers),
+    code(
+      1243,
+      "Moderate or heavy rain shower",
+      "Moderate or heavy rain shower",
+      356,
+      Showers,
+    ),
+    code(
+      1246,
+      "Torrential rain shower",
+      "Torrential rain shower",
+      359,
+      HeavyRain,
+    ),
+    code(
+      1249,
+      "Light sleet showers",
+      "Light sleet showers",
+      362,
+      Sleet,
+    ),
+    code(
+      1252,
+      "Moderate or heavy sleet showers",
+      "Moderate or heavy sleet showers",
+      365,
+      Sleet,
+    ),
+    code(1255, "Light snow showers", "Light snow showers", 368, Snow),
+    code(
+      1258,
+      "Moderate or heavy snow showers",
+      "Moderate or heavy snow showers",
+      371,
+      HeavySnow,
+    ),
+    code(
+      1261,
+      "Light showers of ice pellets",
+      "Light showers of ice pellets",
+      374,
+      IcePellets,
+    ),
+    code(
+      1264,
+      "Moderate or heavy showers of ice pellets",
+      "Moderate or heavy showers of ice pellets",
+      377,
+      IcePellets,
+    ),
+    code(
+      1273,
+      "Patchy light rain with thunder",
+      "Patchy light rain with thunder",
+      386,
+      ThunderRain,
+    ),
+    code(
+      1276,
+      "Moderate or heavy rain with thunder",
+      "Moderate or heavy rain with thunder",
+      389,
+      ThunderRain,
+    ),
+    code(
+      1279,
+      "Patchy light snow with thunder",
+      "Patchy light snow with thunder",
+      392,
+      ThunderSnow,
+    ),
+    code(
+      1282,
+      "Moderate or heavy snow with thunder",
+      "Moderate or heavy snow with thunder",
+      395,
+      ThunderSnow,
+    ),
+  ]
+};
+
+/// Look up a WeatherAPI condition code.
+pub fn lookup(code: i32) -> Option<&'static Code> {
+  CODES
+    .binary_search_by_key(&code, |entry| entry.code)
+    .ok()
+    .map(|index| &CODES[index])
+}
+
+impl Code {
+  pub fn text(&self, is_day: bool) -> &'static str {
+    match is_day {
+      true => self.day,
+      false => self.night,
+    }
+  }
+}
+
+impl ConditionKind {
+  /// Kind of a WeatherAPI condition code, unknown for codes not in the catalogue.
+  pub fn from_code(code: i32) -> Self {
+    lookup(code).map_or(ConditionKind::Unknown, |entry| entry.kind)
+  }
+
+  /// Name of the kind, as used in icon names.
+  pub fn name(&self) -> &'static str {
+    match self {
+      ConditionKind::Clear => "clear",
+      ConditionKind::PartlyCloudy => "partly-cloudy",
+      ConditionKind::Cloudy => "cloudy",
+      ConditionKind::Overcast => "overcast",
+      ConditionKind::Mist => "mist",
+      ConditionKind::Fog => "fog",
+      ConditionKind::FreezingFog => "freezing-fog",
+      ConditionKind::Drizzle => "drizzle",
+      ConditionKind::FreezingDrizzle => "freezing-drizzle",
+      ConditionKind::Rain => "rain",
+      ConditionKind::HeavyRain => "heavy-rain",
+      ConditionKind::FreezingRain => "freezing-rain",
+      ConditionKind::Showers => "showers",
+      ConditionKind::Sleet => "sleet",
+      ConditionKind::Snow => "snow",
+      ConditionKind::HeavySnow => "heavy-snow",
+      ConditionKind::BlowingSnow => "blowing-snow",
+      ConditionKind::IcePellets => "ice-pellets",
+      ConditionKind::Thunder => "thunder",
+      ConditionKind::ThunderRain => "thunder-rain",
+      ConditionKind::ThunderSnow => "thunder-snow",
+      ConditionKind::Unknown => "unknown",
+    }
+  }
+
+  /// Whether the kind looks different by day and by night, where the sun or moon shows.
+  pub fn has_night_variant(&self) -> bool {
+    matches!(
+      self,
+      ConditionKind::Clear
+        | ConditionKind::PartlyCloudy
+        | ConditionKind::Showers
+        | ConditionKind::Thunder
+    )
+  }
+
+  /// Name of the local icon for the kind, such as `clear-night` or `fog`.
+  pub fn icon(&self, is_day: bool) -> String {
+    match (self.has_night_variant(), is_day) {
+      (true, true) => format!("{}-day", self.name()),
+      (true, false) => format!("{}-night", self.name()),
+      (false, _) => self.name().to_string(),
+    }
+  }
+
+  /// A single column symbol for terminals without emoji support.
+  pub fn glyph(&self, is_day: bool) -> &'static str {
+    match self {
+      ConditionKind::Clear if is_day => "☀",
+      ConditionKind::Clear => "☾",
+      ConditionKind::PartlyCloudy => "◐",
+      ConditionKind::Cloudy | ConditionKind::Overcast => "☁",
+      ConditionKind::Mist => "≈",
+      ConditionKind::Fog | ConditionKind::FreezingFog => "≡",
+      ConditionKind::Drizzle | ConditionKind::FreezingDrizzle => "‚",
+      ConditionKind::Rain
+      | ConditionKind::HeavyRain
+      | ConditionKind::FreezingRain
+      | ConditionKind::Showers => "☂",
+      ConditionKind::Sleet => "⁑",
+      ConditionKind::Snow | ConditionKind::HeavySnow | ConditionKind::BlowingSnow => "❄",
+      ConditionKind::IcePellets => "∴",
+      ConditionKind::Thunder | ConditionKind::ThunderRain | ConditionKind::ThunderSnow => "ϟ",
+      ConditionKind::Unknown => "?",
+    }
+  }
+
+  pub fn emoji(&self, is_day: bool) -> &'static str {
+    match self {
+      ConditionKind::Clear if is_day => "☀️",
+      ConditionKind::Clear => "🌙",
+      ConditionKind::PartlyCloudy if is_day => "⛅",
+      ConditionKind::PartlyCloudy | ConditionKind::Cloudy | ConditionKind::Overcast => "☁️",
+      ConditionKind::Mist | ConditionKind::Fog | ConditionKind::FreezingFog => "🌫️",
+      ConditionKind::Drizzle | ConditionKind::Showers if is_day => "🌦️",
+      ConditionKind::Drizzle
+      | ConditionKind::Showers
+      | ConditionKind::FreezingDrizzle
+      | ConditionKind::Rain
+      | ConditionKind::HeavyRain
+      | ConditionKind::FreezingRain => "🌧️",
+      ConditionKind::Sleet | ConditionKind::Snow => "🌨️",
+      ConditionKind::HeavySnow => "❄️",
+      ConditionKind::BlowingSnow => "🌬️",
+      ConditionKind::IcePellets => "🧊",
+      ConditionKind::Thunder => "🌩️",
+      ConditionKind::ThunderRain | ConditionKind::ThunderSnow => "⛈️",
+      ConditionKind::Unknown => "❓",
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_codes_sorted_and_unique() {
+    for pair in CODES.windows(2) {
+      assert!(pair[0].code < pair[1].code);
+      assert!(pair[0].icon < pair[1].icon);
+    }
+  }
+
+  #[test]
+  fn test_lookup() {
+    let fog = lookup(1135).unwrap();
+    assert_eq!(fog.day, "Fog");
+    assert_eq!(fog.icon, 248);
+    assert_eq!(fog.kind, ConditionKind::Fog);
+
+    assert_eq!(lookup(1000).unwrap().text(false), "Clear");
+    assert_eq!(lookup(1000).unwrap().text(true), "Sunny");
+    assert!(lookup(1001).is_none());
+  }
+
+  #[test]
+  fn test_kind() {
+    assert_eq!(ConditionKind::from_code(1276), ConditionKind::ThunderRain);
+    assert_eq!(ConditionKind::from_code(42), ConditionKind::Unknown);
+  }
+
+  #[test]
+  fn test_icon() {
+    assert_eq!(ConditionKind::Clear.icon(false), "clear-night");
+    assert_eq!(ConditionKind::PartlyCloudy.icon(true), "partly-cloudy-day");
+    assert_eq!(ConditionKind::Fog.icon(false), "fog");
+  }
+
+  #[test]
+  fn test_glyph_and_emoji() {
+    assert_eq!(ConditionKind::Clear.glyph(true), "☀");
+    assert_eq!(ConditionKind::Clear.glyph(false), "☾");
+    assert_eq!(ConditionKind::Clear.emoji(false), "🌙");
+    assert_eq!(ConditionKind::Showers.emoji(true), "🌦️");
+    assert_eq!(ConditionKind::Showers.emoji(false), "🌧️");
+
+    for entry in CODES {
+      assert_ne!(entry.kind, ConditionKind::Unknown);
+      assert_eq!(entry.kind.glyph(true).chars().count(), 1);
+    }
+  }
+}
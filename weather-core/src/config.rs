@@ -1 +1,442 @@
-pub struct Config {}
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::error::Error;
+
+/// User configuration, read from a TOML file.
+/// Every section is optional and falls back to its default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+  pub cache: CacheConfig,
+  pub http: HttpConfig,
+  pub quota: QuotaConfig,
+  pub notify: NotifyConfig,
+  pub mqtt: MqttConfig,
+  pub store: StoreConfig,
+  pub places: Vec<Place>,
+  /// API keys to spread requests over, in place of `WEATHER_KEY`.
+  pub keys: Vec<ApiKey>,
+  /// Named output templates, used by passing the name to `--template`.
+  pub templates: HashMap<String, String>,
+}
+
+/// Where logged weather samples are kept.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StoreConfig {
+  /// SQLite database file, defaults to `weather.db` in the state directory.
+  pub path: Option<PathBuf>,
+  /// Record the current weather fetched by every command, not only by `log`.
+  pub record: bool,
+}
+
+/// How conditions are published over MQTT.
+/// Topics may contain `{place}`, replaced by the name of the place.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+  /// Broker address as `host:port` or `mqtt://host:port`.
+  pub broker: String,
+  pub client_id: String,
+  pub username: Option<String>,
+  pub password: Option<String>,
+  pub current_topic: String,
+  pub forecast_topic: String,
+  pub alerts_topic: String,
+  /// Publish retained messages, so new subscribers get the latest state at once.
+  pub retain: bool,
+  /// Publish Home Assistant discovery payloads.
+  pub discovery: bool,
+  pub discovery_prefix: String,
+}
+
+/// A named place, given either by coordinates or by a query the provider can resolve,
+/// such as a city or post code.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Place {
+  pub name: String,
+  pub lat: Option<f64>,
+  pub lon: Option<f64>,
+  pub query: Option<String>,
+}
+
+/// A provider API key, one of several that requests are spread over.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ApiKey {
+  pub key: String,
+  /// Share of requests made with the key, relative to the other keys.
+  /// Keys with a weight of zero are only used when all others fail.
+  pub weight: u32,
+  /// Name shown in place of the key, such as the team or tier it belongs to.
+  pub name: Option<String>,
+}
+
+impl Default for ApiKey {
+  fn default() -> Self {
+    ApiKey {
+      key: String::new(),
+      weight: 1,
+      name: None,
+    }
+  }
+}
+
+/// How provider responses are cached between requests and runs.
+/// Only commands that run often or for long, `watch`, `tui`, `bar` and `serve`, use the cache,
+/// so one-off commands always show fresh weather.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+  pub enabled: bool,
+  /// Time to live of a cached response, in seconds.
+  pub ttl: u64,
+  /// Directory to store cached responses in, defaults to the platform cache directory.
+  pub dir: Option<PathBuf>,
+}
+
+/// How requests to providers are timed out and retried.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+  /// Time allowed to connect, in seconds.
+  pub connect_timeout: u64,
+  /// Time allowed for a whole request, in seconds.
+  pub timeout: u64,
+  /// Retries after connection errors, server errors and rate limits.
+  pub retries: u32,
+  /// Delay before the first retry in milliseconds, doubled for each further retry.
+  pub backoff: u64,
+  /// Longest delay between retries in seconds, including delays asked for by `Retry-After`.
+  pub max_delay: u64,
+  /// Header to send the API key in, in place of the `key` URL parameter, for providers that accept one.
+  pub key_header: Option<String>,
+  /// Hide IP addresses in errors and logs, as well as API keys.
+  pub redact_ip: bool,
+}
+
+/// Limits on provider requests, so a shared key is not used up.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct QuotaConfig {
+  /// Requests allowed per key each UTC day, unlimited if not set.
+  pub daily: Option<u64>,
+  /// Requests allowed per key each UTC month, unlimited if not set.
+  pub monthly: Option<u64>,
+  /// Fraction of a quota after which a warning is given.
+  pub warn_at: f64,
+  /// Sustained requests per second, 0 for no rate limit.
+  pub rate: f64,
+  /// Requests that may be made at once before the rate limit applies.
+  pub burst: u32,
+  /// Time a key rejected by the provider, or over its quota, is left unused, in seconds.
+  pub cooldown: u64,
+}
+
+/// Where alert notifications are sent.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+  /// Show freedesktop notifications over D-Bus.
+  pub desktop: bool,
+  /// URLs receiving each alert as a JSON document.
+  pub webhooks: Vec<String>,
+  /// Slack incoming webhook URLs.
+  pub slack: Vec<String>,
+  /// Discord webhook URLs.
+  pub discord: Vec<String>,
+  /// Shell commands receiving each alert as JSON on stdin.
+  pub commands: Vec<String>,
+}
+
+impl Default for HttpConfig {
+  fn default() -> Self {
+    HttpConfig {
+      connect_timeout: 10,
+      timeout: 30,
+      retries: 2,
+      backoff: 500,
+      max_delay: 30,
+      key_header: None,
+      redact_ip: false,
+    }
+  }
+}
+
+impl Default for QuotaConfig {
+  fn default() -> Self {
+    QuotaConfig {
+      daily: None,
+      monthly: None,
+      warn_at: 0.8,
+      rate: 1.0,
+      burst: 5,
+      cooldown: 3600,
+    }
+  }
+}
+
+impl Default for CacheConfig {
+  fn default() -> Self {
+    CacheConfig {
+      enabled: true,
+      ttl: 300,
+      dir: None,
+    }
+  }
+}
+
+impl Default for MqttConfig {
+  fn default() -> Self {
+    MqttConfig {
+      broker: "localhost:1883".to_string(),
+      client_id: "weather".to_string(),
+      username: None,
+      password: None,
+      current_topic: "weather/{place}/current".to_string(),
+      forecast_topic: "weather/{place}/forecast".to_string(),
+      alerts_topic: "weather/{place}/alerts".to_string(),
+      retain: true,
+      discovery: false,
+      discovery_prefix: "homeassistant".to_string(),
+    }
+  }
+}
+
+impl Config {
+  /// Find a place by name, ignoring case.
+  pub fn place(&self, name: &str) -> Option<&Place> {
+    self
+      .places
+      .iter()
+      .find(|place| place.name.eq_ignore_ascii_case(name))
+  }
+
+  /// Template with a name, or the text itself when there is no template of that name.
+  pub fn template<'a>(&'a self, name: &'a str) -> &'a str {
+    self.templates.get(name).map_or(name, String::as_str)
+  }
+
+  /// Location of the configuration file.
+  /// Uses `WEATHER_CONFIG` if set, otherwise `weather/config.toml` in the platform config directory.
+  pub fn path() -> PathBuf {
+    match std::env::var("WEATHER_CONFIG") {
+      Ok(path) if !path.is_empty() => PathBuf::from(path),
+      _ => dirs::config_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("weather")
+        .join("config.toml"),
+    }
+  }
+
+  /// Load the configuration from the default path.
+  /// A missing file results in the default configuration.
+  pub fn load() -> Result<Self, Error> {
+    let path = Config::path();
+    if !path.exists() {
+      return Ok(Config::default());
+    }
+    Config::from_file(&path)
+  }
+
+  pub fn from_file(path: &Path) -> Result<Self, Error> {
+    let content = fs::read_to_string(path).map_err(|err| Error::Config {
+      path: path.display().to_string(),
+      message: err.to_string(),
+    })?;
+    Config::parse(&content).map_err(|err| match err {
+      Error::Config { message, .. } => Error::Config {
+        path: path.display().to_string(),
+        message,
+      },
+      err => err,
+    })
+  }
+
+  pub fn parse(content: &str) -> Result<Self, Error> {
+    toml::from_str(content).map_err(|err| Error::Config {
+      path: String::new(),
+      message: err.to_string(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_empty() {
+    let config = Config::parse("").unwrap();
+    assert!(!config.notify.desktop);
+    assert!(config.notify.webhooks.is_empty());
+    assert!(config.cache.enabled);
+    assert_eq!(config.cache.ttl, 300);
+  }
+
+  #[test]
+  fn test_parse_cache() {
+    let config = Config::parse(
+      "[cache]
+ttl = 60
+dir = \"/tmp/weather\"",
+    )
+    .unwrap();
+    assert!(config.cache.enabled);
+    assert_eq!(config.cache.ttl, 60);
+    assert_eq!(config.cache.dir, Some(PathBuf::from("/tmp/weather")));
+  }
+
+  #[test]
+  fn test_parse_notify() {
+    let config = Config::parse(
+      r#"
+      [notify]
+      desktop = true
+      webhooks = ["http://localhost/hook"]
+      slack = ["https://hooks.slack.com/services/x"]
+      commands = ["cat"]
+      "#,
+    )
+    .unwrap();
+
+    assert!(config.notify.desktop);
+    assert_eq!(config.notify.webhooks, vec!["http://localhost/hook"]);
+    assert_eq!(config.notify.slack.len(), 1);
+    assert!(config.notify.discord.is_empty());
+    assert_eq!(config.notify.commands, vec!["cat"]);
+  }
+
+  #[test]
+  fn test_parse_places() {
+    let config = Config::parse(
+      r#"
+      [[places]]
+      name = "office"
+      lat = 51.5171
+      lon = -0.1062
+
+      [[places]]
+      name = "Lab"
+      query = "SW1A 1AA"
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.places.len(), 2);
+    assert_eq!(config.place("office").unwrap().lat, Some(51.5171));
+    assert_eq!(
+      config.place("lab").unwrap().query,
+      Some("SW1A 1AA".to_string())
+    );
+    assert!(config.place("home").is_none());
+  }
+
+  #[test]
+  fn test_parse_templates() {
+    let config = Config::parse(
+      r#"
+      [templates]
+      prompt = "{temp|round}°{unit} {condition.glyph}"
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+      config.template("prompt"),
+      "{temp|round}°{unit} {condition.glyph}"
+    );
+    assert_eq!(config.template("{temp}"), "{temp}");
+  }
+
+  #[test]
+  fn test_parse_http() {
+    let config = Config::parse(
+      r#"
+      [http]
+      timeout = 5
+      retries = 0
+      key_header = "key"
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.http.connect_timeout, 10);
+    assert_eq!(config.http.timeout, 5);
+    assert_eq!(config.http.retries, 0);
+    assert_eq!(config.http.backoff, 500);
+    assert_eq!(config.http.key_header, Some("key".to_string()));
+    assert!(!config.http.redact_ip);
+  }
+
+  #[test]
+  fn test_parse_quota() {
+    let config = Config::parse(
+      r#"
+      [quota]
+      monthly = 1000000
+      warn_at = 0.9
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.quota.monthly, Some(1000000));
+    assert_eq!(config.quota.daily, None);
+    assert_eq!(config.quota.warn_at, 0.9);
+    assert_eq!(config.quota.burst, 5);
+  }
+
+  #[test]
+  fn test_parse_keys() {
+    let config = Config::parse(
+      r#"
+      [[keys]]
+      key = "team-key"
+      weight = 3
+      name = "team"
+
+      [[keys]]
+      key = "tier-key"
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.keys.len(), 2);
+    assert_eq!(config.keys[0].weight, 3);
+    assert_eq!(config.keys[0].name, Some("team".to_string()));
+    assert_eq!(config.keys[1].weight, 1);
+    assert_eq!(config.quota.cooldown, 3600);
+  }
+
+  #[test]
+  fn test_parse_mqtt() {
+    let config = Config::parse(
+      r#"
+      [mqtt]
+      broker = "mqtt://broker.lab:1883"
+      current_topic = "lab/{place}/weather"
+      discovery = true
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.mqtt.broker, "mqtt://broker.lab:1883");
+    assert_eq!(config.mqtt.current_topic, "lab/{place}/weather");
+    assert_eq!(config.mqtt.alerts_topic, "weather/{place}/alerts");
+    assert!(config.mqtt.retain);
+    assert!(config.mqtt.discovery);
+  }
+
+  #[test]
+  fn test_parse_invalid() {
+    let result = Config::parse("[notify]\ndesktop = \"yes\"");
+    assert!(matches!(result, Err(Error::Config { .. })));
+  }
+}
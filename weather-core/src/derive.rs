@@ -0,0 +1,385 @@
+use serde::Serialize;
+
+use crate::{
+  locale,
+  weather::{Units, Weather},
+};
+
+/// Upper bounds of each Beaufort force in km/h, force 12 is anything above the last.
+const BEAUFORT_LIMITS: [f64; 12] = [
+  1.0, 6.0, 12.0, 20.0, 29.0, 39.0, 50.0, 62.0, 75.0, 89.0, 103.0, 118.0,
+];
+
+const BEAUFORT_DESCRIPTIONS: [&str; 13] = [
+  "Calm",
+  "Light air",
+  "Light breeze",
+  "Gentle breeze",
+  "Moderate breeze",
+  "Fresh breeze",
+  "Strong breeze",
+  "Near gale",
+  "Gale",
+  "Strong gale",
+  "Storm",
+  "Violent storm",
+  "Hurricane force",
+];
+
+/// Height the air cools by one degree Celsius of dew point spread, in metres.
+const CLOUD_BASE_LAPSE: f64 = 125.0;
+
+/// Wind force on the Beaufort scale.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Beaufort {
+  pub force: u8,
+  pub description: &'static str,
+}
+
+/// WHO UV index categories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum UvCategory {
+  Low,
+  Moderate,
+  High,
+  VeryHigh,
+  Extreme,
+}
+
+/// How the humidity feels, classified by dew point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Comfort {
+  Dry,
+  Comfortable,
+  Humid,
+  Muggy,
+  Oppressive,
+  Miserable,
+}
+
+/// Values computed from the current weather.
+/// Temperatures are in the units of the weather they were derived from,
+/// as is the cloud base, in metres or feet. Absolute humidity is always in g/m³.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Derived {
+  pub beaufort: Beaufort,
+  pub uv: UvCategory,
+  pub humidex: f64,
+  pub wet_bulb: f64,
+  pub apparent_temperature: f64,
+  pub absolute_humidity: f64,
+  pub cloud_base: f64,
+  pub comfort: Comfort,
+  pub units: Units,
+}
+
+impl Beaufort {
+  pub fn from_kph(speed: f64) -> Self {
+    let force = BEAUFORT_LIMITS
+      .iter()
+      .position(|limit| speed < *limit)
+      .unwrap_or(BEAUFORT_LIMITS.len());
+    Beaufort {
+      force: force as u8,
+      description: BEAUFORT_DESCRIPTIONS[force],
+    }
+  }
+}
+
+impl UvCategory {
+  pub fn from_index(uv: f64) -> Self {
+    match uv.round() {
+      uv if uv < 3.0 => UvCategory::Low,
+      uv if uv < 6.0 => UvCategory::Moderate,
+      uv if uv < 8.0 => UvCategory::High,
+      uv if uv < 11.0 => UvCategory::VeryHigh,
+      _ => UvCategory::Extreme,
+    }
+  }
+
+  /// Sun protection advice for the category, in the current locale.
+  pub fn advice(&self) -> &'static str {
+    locale::current().label(match self {
+      UvCategory::Low => "No protection needed.",
+      UvCategory::Moderate => "Seek shade around midday, wear a hat and use sunscreen.",
+      UvCategory::High => {
+        "Reduce time in the sun between 11am and 3pm, cover up and use sunscreen."
+      }
+      UvCategory::VeryHigh => {
+        "Avoid the sun between 11am and 3pm, shade, clothing and sunscreen are essential."
+      }
+      UvCategory::Extreme => {
+        "Avoid being outside during midday hours, unprotected skin burns in minutes."
+      }
+    })
+  }
+}
+
+impl Comfort {
+  pub fn from_dew_point(dew_point: f64) -> Self {
+    match dew_point {
+      d if d < 10.0 => Comfort::Dry,
+      d if d < 16.0 => Comfort::Comfortable,
+      d if d < 18.0 => Comfort::Humid,
+      d if d < 21.0 => Comfort::Muggy,
+      d if d < 24.0 => Comfort::Oppressive,
+      _ => Comfort::Miserable,
+    }
+  }
+}
+
+/// Saturation vapour pressure over water in hPa, by the Magnus formula.
+fn vapour_pressure(temperature: f64) -> f64 {
+  6.112 * (17.67 * temperature / (temperature + 243.5)).exp()
+}
+
+/// Humidex in °C from the temperature and dew point in °C.
+pub fn humidex(temperature: f64, dew_point: f64) -> f64 {
+  let e = 6.11 * (5417.7530 * (1.0 / 273.16 - 1.0 / (273.15 + dew_point))).exp();
+  temperature + 5.0 / 9.0 * (e - 10.0)
+}
+
+/// Wet-bulb temperature in °C from the temperature in °C and relative humidity in percent.
+/// Uses the Stull (2011) approximation, accurate to within 1 °C at everyday conditions.
+pub fn wet_bulb(temperature: f64, humidity: f64) -> f64 {
+  temperature * (0.151977 * (humidity + 8.313659).sqrt()).atan() + (temperature + humidity).atan()
+    - (humidity - 1.676331).atan()
+    + 0.00391838 * humidity.powf(1.5) * (0.023101 * humidity).atan()
+    - 4.686035
+}
+
+/// Steadman's apparent temperature in °C for shade, from the temperature in °C,
+/// relative humidity in percent and wind speed in km/h.
+pub fn apparent_temperature(temperature: f64, humidity: f64, wind_speed: f64) -> f64 {
+  let e = humidity / 100.0 * 6.105 * (17.27 * temperature / (237.7 + temperature)).exp();
+  temperature + 0.33 * e - 0.70 * (wind_speed / 3.6) - 4.00
+}
+
+/// Absolute humidity in g/m³ from the temperature in °C and relative humidity in percent.
+pub fn absolute_humidity(temperature: f64, humidity: f64) -> f64 {
+  vapour_pressure(temperature) * humidity * 2.1674 / (273.15 + temperature)
+}
+
+/// Estimated height of the cloud base above the ground in metres,
+/// from the temperature and dew point in °C.
+pub fn cloud_base(temperature: f64, dew_point: f64) -> f64 {
+  (temperature - dew_point).max(0.0) * CLOUD_BASE_LAPSE
+}
+
+pub(crate) fn to_celsius(fahrenheit: f64) -> f64 {
+  (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+pub(crate) fn to_fahrenheit(celsius: f64) -> f64 {
+  celsius * 9.0 / 5.0 + 32.0
+}
+
+impl From<&Weather> for Derived {
+  fn from(weather: &Weather) -> Self {
+    let (temperature, dew_point, wind_speed) = match weather.units {
+      Units::Metric => (weather.temperature, weather.dew_point, weather.wind_speed),
+      Units::Imperial => (
+        to_celsius(weather.temperature),
+        to_celsius(weather.dew_point),
+        weather.wind_speed * 1.609344,
+      ),
+    };
+    let humidity = weather.humidity as f64;
+
+    let temperature_out = |celsius: f64| match weather.units {
+      Units::Metric => celsius,
+      Units::Imperial => to_fahrenheit(celsius),
+    };
+    let cloud_base = cloud_base(temperature, dew_point);
+
+    Derived {
+      beaufort: Beaufort::from_kph(wind_speed),
+      uv: UvCategory::from_index(weather.uv),
+      humidex: temperature_out(humidex(temperature, dew_point)),
+      wet_bulb: temperature_out(wet_bulb(temperature, humidity)),
+      apparent_temperature: temperature_out(apparent_temperature(
+        temperature,
+        humidity,
+        wind_speed,
+      )),
+      absolute_humidity: absolute_humidity(temperature, humidity),
+      cloud_base: match weather.units {
+        Units::Metric => cloud_base,
+        Units::Imperial => cloud_base * 3.28084,
+      },
+      comfort: Comfort::from_dew_point(dew_point),
+      units: weather.units,
+    }
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for UvCategory {
+  fn to_string(&self) -> String {
+    locale::current()
+      .label(match self {
+        UvCategory::Low => "UV|Low",
+        UvCategory::Moderate => "UV|Moderate",
+        UvCategory::High => "UV|High",
+        UvCategory::VeryHigh => "UV|Very high",
+        UvCategory::Extreme => "UV|Extreme",
+      })
+      .to_string()
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Comfort {
+  fn to_string(&self) -> String {
+    locale::current()
+      .label(match self {
+        Comfort::Dry => "Dry",
+        Comfort::Comfortable => "Comfortable",
+        Comfort::Humid => "Humid",
+        Comfort::Muggy => "Muggy",
+        Comfort::Oppressive => "Oppressive",
+        Comfort::Miserable => "Miserable",
+      })
+      .to_string()
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Derived {
+  fn to_string(&self) -> String {
+    let height = match self.units {
+      Units::Metric => "m",
+      Units::Imperial => "ft",
+    };
+    let locale = locale::current();
+    [
+      (
+        "Beaufort",
+        format!(
+          "{} ({})",
+          self.beaufort.force,
+          locale.label(self.beaufort.description)
+        ),
+      ),
+      (
+        "UV Category",
+        format!("{} ({})", self.uv.to_string(), self.uv.advice()),
+      ),
+      ("Humidex", locale.fixed(self.humidex, 1)),
+      ("Wet Bulb", locale.fixed(self.wet_bulb, 1)),
+      (
+        "Apparent Temperature",
+        locale.fixed(self.apparent_temperature, 1),
+      ),
+      (
+        "Absolute Humidity",
+        format!("{} g/m³", locale.fixed(self.absolute_humidity, 1)),
+      ),
+      (
+        "Cloud Base",
+        format!("{} {}", locale.fixed(self.cloud_base, 0), height),
+      ),
+      ("Comfort", self.comfort.to_string()),
+    ]
+    .iter()
+    .map(|(label, value)| format!("{}: {}", locale.label(label), value))
+    .collect::<Vec<_>>()
+    .join("\n ")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::weather::Condition;
+
+  fn weather(temperature: f64, dew_point: f64, humidity: i32, wind_speed: f64) -> Weather {
+    Weather::new(
+      true,
+      temperature,
+      temperature,
+      temperature,
+      Condition::new("Sunny".to_string(), String::new(), 1000),
+      wind_speed,
+      180,
+      "S".to_string(),
+      wind_speed,
+      temperature,
+      1013.0,
+      0.0,
+      humidity,
+      0,
+      6.0,
+      10.0,
+      dew_point,
+    )
+  }
+
+  fn close(a: f64, b: f64, tolerance: f64) -> bool {
+    (a - b).abs() <= tolerance
+  }
+
+  #[test]
+  fn test_beaufort() {
+    assert_eq!(Beaufort::from_kph(0.0).force, 0);
+    assert_eq!(Beaufort::from_kph(15.0).force, 3);
+    assert_eq!(Beaufort::from_kph(15.0).description, "Gentle breeze");
+    assert_eq!(Beaufort::from_kph(80.0).force, 9);
+    assert_eq!(Beaufort::from_kph(150.0).force, 12);
+    assert_eq!(Beaufort::from_kph(150.0).description, "Hurricane force");
+  }
+
+  #[test]
+  fn test_uv_category() {
+    assert_eq!(UvCategory::from_index(2.4), UvCategory::Low);
+    assert_eq!(UvCategory::from_index(2.6), UvCategory::Moderate);
+    assert_eq!(UvCategory::from_index(7.0), UvCategory::High);
+    assert_eq!(UvCategory::from_index(10.0), UvCategory::VeryHigh);
+    assert_eq!(UvCategory::from_index(11.0), UvCategory::Extreme);
+  }
+
+  #[test]
+  fn test_formulas() {
+    // Reference values from Environment Canada and Stull (2011).
+    assert!(close(humidex(30.0, 15.0), 34.0, 0.5));
+    assert!(close(wet_bulb(20.0, 50.0), 13.7, 0.1));
+    assert!(close(absolute_humidity(20.0, 50.0), 8.6, 0.1));
+    assert!(close(apparent_temperature(25.0, 50.0, 0.0), 26.2, 0.2));
+    assert_eq!(cloud_base(20.0, 12.0), 1000.0);
+    assert_eq!(cloud_base(10.0, 11.0), 0.0);
+  }
+
+  #[test]
+  fn test_comfort() {
+    assert_eq!(Comfort::from_dew_point(5.0), Comfort::Dry);
+    assert_eq!(Comfort::from_dew_point(12.0), Comfort::Comfortable);
+    assert_eq!(Comfort::from_dew_point(19.0), Comfort::Muggy);
+    assert_eq!(Comfort::from_dew_point(26.0), Comfort::Miserable);
+  }
+
+  #[test]
+  fn test_derived_units() {
+    let metric = Derived::from(&weather(20.0, 12.0, 60, 16.0934));
+    let imperial = Derived::from(&Weather {
+      units: Units::Imperial,
+      ..weather(68.0, 53.6, 60, 10.0)
+    });
+
+    assert_eq!(metric.beaufort, imperial.beaufort);
+    assert_eq!(metric.uv, UvCategory::High);
+    assert_eq!(metric.comfort, imperial.comfort);
+    assert!(close(
+      metric.absolute_humidity,
+      imperial.absolute_humidity,
+      1e-9
+    ));
+    assert!(close(
+      to_fahrenheit(metric.wet_bulb),
+      imperial.wet_bulb,
+      1e-9
+    ));
+    assert!(close(to_fahrenheit(metric.humidex), imperial.humidex, 1e-9));
+    assert!(close(metric.cloud_base, 1000.0, 1e-9));
+    assert!(close(imperial.cloud_base, 3280.84, 1e-6));
+  }
+}
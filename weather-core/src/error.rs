@@ -1,11 +1,58 @@
 #[derive(Debug, PartialEq)]
 /// Various Error types that can occur when using the weather-core crate.
 pub enum Error {
-  Error { message: String },
-  Fetch { message: String },
-  Response { message: String },
-  BadIp { ip: String, message: String },
-  InvalidArgument { arg: String, message: String },
-  UnknownLocation { location: String },
+  Error {
+    message: String,
+  },
+  Fetch {
+    message: String,
+  },
+  Response {
+    message: String,
+  },
+  BadIp {
+    ip: String,
+    message: String,
+  },
+  InvalidArgument {
+    arg: String,
+    message: String,
+  },
+  UnknownLocation {
+    location: String,
+  },
   NoLocation,
+  Io {
+    path: String,
+    message: String,
+  },
+  Config {
+    path: String,
+    message: String,
+  },
+  Notify {
+    sink: String,
+    message: String,
+  },
+  Store {
+    path: String,
+    message: String,
+  },
+  Timeout {
+    message: String,
+  },
+  /// The provider refused the request for sending too many, `retry_after` is in seconds.
+  RateLimited {
+    retry_after: Option<u64>,
+  },
+  /// The provider refused the API key, as unknown, disabled or over its quota.
+  KeyRejected {
+    status: u16,
+    message: String,
+  },
+  /// The configured quota of a key is used up, `period` is daily or monthly.
+  QuotaExceeded {
+    period: String,
+    limit: u64,
+  },
 }
@@ -0,0 +1,784 @@
+use std::f64::consts::PI;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+use serde_json::{json, Value};
+
+use crate::{condition, error::Error, feed::escape, fingerprint};
+
+/// Hours between weather fronts.
+const FRONT_PERIOD: f64 = 90.0;
+/// Fronts stronger than this come with an alert.
+const ALERT_STRENGTH: f64 = 0.8;
+/// Length of a lunar month in days, and a new moon to count from.
+const LUNAR_MONTH: f64 = 29.530588;
+const NEW_MOON: i64 = 947182440;
+
+/// Public IP of the machine, an address reserved for documentation.
+pub const IP: &str = "192.0.2.1";
+/// Town that every IP address is located in.
+pub const TOWN: &str = "Fakeville";
+
+/// A place the fake provider knows, for any query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+  pub name: String,
+  pub region: String,
+  pub country: String,
+  pub lat: f64,
+  pub lon: f64,
+}
+
+/// Weather at a place and time.
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+  temperature: f64,
+  feels_like: f64,
+  dew_point: f64,
+  humidity: i32,
+  cloud: i32,
+  precipitation: f64,
+  wind_speed: f64,
+  wind_degree: i32,
+  gust: f64,
+  pressure: f64,
+  visibility: f64,
+  uv: f64,
+  is_day: bool,
+  code: i32,
+  chance_of_rain: i32,
+  chance_of_snow: i32,
+}
+
+/// A weather front passing a place, stronger fronts bringing more rain, wind and cloud.
+#[derive(Debug, Clone, PartialEq)]
+struct Front {
+  /// When the front is overhead.
+  centre: DateTime<Utc>,
+  strength: f64,
+}
+
+/// Answer a provider request with made up, but plausible, weather.
+/// The weather only depends on the place and the time, so the same request at the same time
+/// always gets the same answer.
+/// Any other URL, such as a `--cap` source, gets the alerts as a CAP document,
+/// for the place in `q` or else `TOWN`.
+pub fn respond(url: &str, now: DateTime<Utc>) -> Result<String, Error> {
+  let url = reqwest::Url::parse(url).map_err(|err| Error::Fetch {
+    message: err.to_string(),
+  })?;
+  let param = |name: &str| {
+    url
+      .query_pairs()
+      .find(|(key, _)| key == name)
+      .map(|(_, value)| value.into_owned())
+  };
+  let query = param("q");
+  let days = param("days")
+    .and_then(|days| days.parse().ok())
+    .unwrap_or(1);
+  let endpoint = url.path().rsplit('/').next().unwrap_or_default();
+  if !endpoint.ends_with(".json") {
+    return Ok(cap(&place(query.as_deref().unwrap_or(TOWN)), now, days));
+  }
+  let place = place(&query.unwrap_or_default());
+
+  let body = match endpoint {
+    "search.json" => json!([location(&place)]),
+    "current.json" => json!({
+      "location": location(&place),
+      "current": current(&place, now),
+    }),
+    "forecast.json" => {
+      let mut body = json!({
+        "location": location(&place),
+        "current": current(&place, now),
+        "forecast": { "forecastday": forecast(&place, now, days) },
+      });
+      if param("alerts").as_deref() == Some("yes") {
+        body["alerts"] = json!({ "alert": alerts(&place, now, days) });
+      }
+      body
+    }
+    "astronomy.json" => {
+      let date = param("dt")
+        .and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok())
+        .unwrap_or_else(|| local(&place, now).date_naive());
+      json!({
+        "location": location(&place),
+        "astronomy": { "astro": astro(&place, date, now) },
+      })
+    }
+    endpoint => {
+      return Err(Error::Response {
+        message: format!(
+          "unexpected status 404: the fake provider has no {}",
+          endpoint
+        ),
+      })
+    }
+  };
+  Ok(body.to_string())
+}
+
+/// The place for a query: coordinates are used as given, and names are put somewhere
+/// that stays the same for the name.
+pub fn place(query: &str) -> Place {
+  let query = query.trim();
+  let coordinates = query
+    .split_once(',')
+    .and_then(|(lat, lon)| Some((lat.trim().parse().ok()?, lon.trim().parse().ok()?)));
+  match coordinates {
+    Some((lat, lon)) => Place {
+      name: format!("{:.2},{:.2}", lat, lon),
+      region: "Fake Region".to_string(),
+      country: "Fakeland".to_string(),
+      lat,
+      lon,
+    },
+    None => Place {
+      name: title_case(if query.is_empty() { "Nowhere" } else { query }),
+      region: "Fake Region".to_string(),
+      country: "Fakeland".to_string(),
+      lat: round(noise(&["lat", query]) * 120.0 - 60.0, 2),
+      lon: round(noise(&["lon", query]) * 360.0 - 180.0, 2),
+    },
+  }
+}
+
+fn title_case(text: &str) -> String {
+  text
+    .split_whitespace()
+    .map(|word| {
+      let mut chars = word.chars();
+      match chars.next() {
+        Some(first) => first
+          .to_uppercase()
+          .chain(chars.flat_map(char::to_lowercase))
+          .collect(),
+        None => String::new(),
+      }
+    })
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+/// A number in `[0, 1)` that only depends on `parts`.
+fn noise(parts: &[&str]) -> f64 {
+  let hash = u64::from_str_radix(&fingerprint(parts), 16).unwrap_or_default();
+  (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn round(value: f64, places: i32) -> f64 {
+  let factor = 10f64.powi(places);
+  (value * factor).round() / factor
+}
+
+/// Time at a place, by its longitude rather than its time zone.
+fn local(place: &Place, time: DateTime<Utc>) -> DateTime<Utc> {
+  time + Duration::minutes((place.lon * 4.0).round() as i64)
+}
+
+fn seed(place: &Place) -> String {
+  format!("{:.2},{:.2}", place.lat, place.lon)
+}
+
+/// Fronts passing a place, one every few days, at times that differ between places.
+fn front(place: &Place, time: DateTime<Utc>) -> (Front, f64) {
+  let seed = seed(place);
+  let shift = noise(&[&seed, "front"]) * FRONT_PERIOD;
+  let hours = time.timestamp() as f64 / 3600.0 + shift;
+  let index = (hours / FRONT_PERIOD).floor();
+  let phase = hours / FRONT_PERIOD - index;
+  // On the hour, so alerts of the front stay the same between requests.
+  let centre = ((index + 0.5) * FRONT_PERIOD - shift).round() as i64 * 3600;
+  let centre = Utc.timestamp_opt(centre, 0).unwrap();
+  let strength = noise(&[&seed, "front", &index.to_string()]);
+  (Front { centre, strength }, phase)
+}
+
+/// Elevation of the sun in degrees.
+fn sun_elevation(place: &Place, time: DateTime<Utc>) -> f64 {
+  let local = local(place, time);
+  let hour = local.hour() as f64 + local.minute() as f64 / 60.0;
+  let declination = declination(local.ordinal());
+  let hour_angle = (15.0 * (hour - 12.0)).to_radians();
+  let lat = place.lat.to_radians();
+  (lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos())
+    .asin()
+    .to_degrees()
+}
+
+/// Declination of the sun on a day of the year, in radians.
+fn declination(day: u32) -> f64 {
+  (23.44f64).to_radians() * (2.0 * PI * (284.0 + day as f64) / 365.0).sin()
+}
+
+fn sample(place: &Place, time: DateTime<Utc>) -> Sample {
+  let seed = seed(place);
+  let local = local(place, time);
+  let hour = local.hour() as f64 + local.minute() as f64 / 60.0;
+  let day = local.ordinal() as f64;
+  let hourly = noise(&[&seed, "hour", &(time.timestamp() / 3600).to_string()]);
+  let daily = noise(&[&seed, "day", &(time.timestamp() / 86400).to_string()]);
+
+  let (front, phase) = front(place, time);
+  // How close the front is, from 0 far away to its strength overhead.
+  let nearness = (-((phase - 0.5) / 0.12).powi(2)).exp() * front.strength;
+
+  let cloud = (0.15 + hourly * 0.25 + daily * 0.2 + nearness * 1.1).min(1.0);
+  let precipitation = match nearness > 0.35 {
+    true => round((nearness - 0.35) * 8.0 * front.strength, 1),
+    false => 0.0,
+  };
+
+  let season = -(2.0 * PI * (day - 15.0) / 365.25).cos() * place.lat.signum();
+  let mean = 28.0 - 0.35 * place.lat.abs() + 0.13 * place.lat.abs() * season;
+  let diurnal = (3.0 + 3.0 * (1.0 - cloud)) * (2.0 * PI * (hour - 15.0) / 24.0).cos();
+  // Warm ahead of a front and cold behind it.
+  let fronts = -4.0 * front.strength * ((phase - 0.5) / 0.1).tanh();
+  let temperature = round(mean + diurnal + fronts + (daily - 0.5) * 3.0, 1);
+
+  let wind_speed = round(6.0 + daily * 12.0 + nearness * 45.0, 1);
+  let wind_degree = (220.0 + 140.0 * (phase - 0.5) + hourly * 30.0).rem_euclid(360.0);
+  let humidity = (55.0 + cloud * 35.0 + precipitation.min(1.0) * 8.0).min(100.0);
+  let dew_point = round(temperature - (100.0 - humidity) / 5.0, 1);
+  let pressure = round(
+    1013.0 + noise(&[&seed, "pressure", &front.centre.timestamp().to_string()]) * 16.0
+      - nearness * 28.0,
+    0,
+  );
+
+  let elevation = sun_elevation(place, time);
+  let is_day = elevation > 0.0;
+  let uv = round(
+    (elevation.to_radians().sin().max(0.0) * 11.0 * (1.0 - 0.7 * cloud)).max(0.0),
+    0,
+  );
+  let foggy = humidity > 92.0 && wind_speed < 9.0 && !is_day && precipitation == 0.0;
+  let visibility = match foggy {
+    true => 0.4,
+    false => round(10.0 - 6.0 * nearness, 1),
+  };
+
+  let snowing = temperature < 1.0;
+  let code = match (precipitation, snowing, foggy) {
+    (p, _, _) if p > 0.0 && front.strength > 0.85 && temperature > 18.0 => 1276,
+    (p, true, _) if p >= 4.0 => 1225,
+    (p, true, _) if p >= 1.0 => 1219,
+    (p, true, _) if p > 0.0 => 1213,
+    (p, false, _) if p >= 4.0 => 1195,
+    (p, false, _) if p >= 1.0 => 1189,
+    (p, false, _) if p > 0.0 => 1183,
+    (_, _, true) => 1135,
+    _ if cloud < 0.25 => 1000,
+    _ if cloud < 0.5 => 1003,
+    _ if cloud < 0.8 => 1006,
+    _ => 1009,
+  };
+  let chance = (nearness * 130.0).min(100.0).round() as i32;
+
+  Sample {
+    temperature,
+    feels_like: feels_like(temperature, wind_speed, humidity),
+    dew_point,
+    humidity: humidity.round() as i32,
+    cloud: (cloud * 100.0).round() as i32,
+    precipitation,
+    wind_speed,
+    wind_degree: wind_degree.round() as i32 % 360,
+    gust: round(wind_speed * (1.3 + hourly * 0.3), 1),
+    pressure,
+    visibility,
+    uv,
+    is_day,
+    code,
+    chance_of_rain: if snowing { 0 } else { chance },
+    chance_of_snow: if snowing { chance } else { 0 },
+  }
+}
+
+/// Wind chill when cold and windy, a rough heat index when hot, otherwise the temperature.
+fn feels_like(temperature: f64, wind_speed: f64, humidity: f64) -> f64 {
+  let feels = match (temperature, wind_speed) {
+    (t, v) if t <= 10.0 && v > 4.8 => {
+      13.12 + 0.6215 * t - 11.37 * v.powf(0.16) + 0.3965 * t * v.powf(0.16)
+    }
+    (t, _) if t >= 27.0 => t + 0.1 * (humidity - 40.0).max(0.0),
+    (t, _) => t,
+  };
+  round(feels, 1)
+}
+
+fn compass(degree: i32) -> &'static str {
+  const POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW",
+  ];
+  POINTS[((degree as f64 / 22.5).round() as usize) % 16]
+}
+
+fn fahrenheit(celsius: f64) -> f64 {
+  round(celsius * 9.0 / 5.0 + 32.0, 1)
+}
+
+fn mph(kph: f64) -> f64 {
+  round(kph / 1.609344, 1)
+}
+
+fn inches(mm: f64) -> f64 {
+  round(mm / 25.4, 2)
+}
+
+fn condition(code: i32, is_day: bool) -> Value {
+  let entry = condition::lookup(code);
+  json!({
+    "text": entry.map(|entry| entry.text(is_day)).unwrap_or_default(),
+    "icon": format!(
+      "//cdn.weatherapi.com/weather/64x64/{}/{}.png",
+      if is_day { "day" } else { "night" },
+      entry.map(|entry| entry.icon).unwrap_or(113)
+    ),
+    "code": code,
+  })
+}
+
+fn location(place: &Place) -> Value {
+  json!({
+    "name": place.name,
+    "region": place.region,
+    "country": place.country,
+    "lat": place.lat,
+    "lon": place.lon,
+    "tz_id": "Etc/UTC",
+  })
+}
+
+fn current(place: &Place, now: DateTime<Utc>) -> Value {
+  let sample = sample(place, now);
+  json!({
+    "last_updated": local(place, now).format("%Y-%m-%d %H:%M").to_string(),
+    "temp_c": sample.temperature,
+    "temp_f": fahrenheit(sample.temperature),
+    "is_day": sample.is_day as i32,
+    "condition": condition(sample.code, sample.is_day),
+    "wind_mph": mph(sample.wind_speed),
+    "wind_kph": sample.wind_speed,
+    "wind_degree": sample.wind_degree,
+    "wind_dir": compass(sample.wind_degree),
+    "pressure_mb": sample.pressure,
+    "pressure_in": round(sample.pressure * 0.02953, 2),
+    "precip_mm": sample.precipitation,
+    "precip_in": inches(sample.precipitation),
+    "humidity": sample.humidity,
+    "cloud": sample.cloud,
+    "feelslike_c": sample.feels_like,
+    "feelslike_f": fahrenheit(sample.feels_like),
+    "windchill_c": sample.feels_like.min(sample.temperature),
+    "windchill_f": fahrenheit(sample.feels_like.min(sample.temperature)),
+    "heatindex_c": sample.feels_like.max(sample.temperature),
+    "heatindex_f": fahrenheit(sample.feels_like.max(sample.temperature)),
+    "dewpoint_c": sample.dew_point,
+    "dewpoint_f": fahrenheit(sample.dew_point),
+    "vis_km": sample.visibility,
+    "vis_miles": round(sample.visibility / 1.609344, 1),
+    "uv": sample.uv,
+    "gust_mph": mph(sample.gust),
+    "gust_kph": sample.gust,
+  })
+}
+
+fn hour(place: &Place, time: DateTime<Utc>, sample: &Sample) -> Value {
+  json!({
+    "time": local(place, time).format("%Y-%m-%d %H:%M").to_string(),
+    "temp_c": sample.temperature,
+    "temp_f": fahrenheit(sample.temperature),
+    "is_day": sample.is_day as i32,
+    "condition": condition(sample.code, sample.is_day),
+    "wind_mph": mph(sample.wind_speed),
+    "wind_kph": sample.wind_speed,
+    "wind_degree": sample.wind_degree,
+    "wind_dir": compass(sample.wind_degree),
+    "pressure_mb": sample.pressure,
+    "pressure_in": round(sample.pressure * 0.02953, 2),
+    "precip_mm": sample.precipitation,
+    "precip_in": inches(sample.precipitation),
+    "humidity": sample.humidity,
+    "cloud": sample.cloud,
+    "feelslike_c": sample.feels_like,
+    "feelslike_f": fahrenheit(sample.feels_like),
+    "chance_of_rain": sample.chance_of_rain,
+    "chance_of_snow": sample.chance_of_snow,
+    "gust_mph": mph(sample.gust),
+    "gust_kph": sample.gust,
+    "uv": sample.uv,
+  })
+}
+
+/// Start of a local day at a place, in UTC.
+fn day_start(place: &Place, date: NaiveDate) -> DateTime<Utc> {
+  Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN))
+    - Duration::minutes((place.lon * 4.0).round() as i64)
+}
+
+fn forecast(place: &Place, now: DateTime<Utc>, days: i64) -> Vec<Value> {
+  let today = local(place, now).date_naive();
+  (0..days.clamp(1, 14))
+    .map(|offset| {
+      let date = today + Duration::days(offset);
+      let start = day_start(place, date);
+      let hours: Vec<(DateTime<Utc>, Sample)> = (0..24)
+        .map(|hour| {
+          let time = start + Duration::hours(hour);
+          (time, sample(place, time))
+        })
+        .collect();
+      let samples: Vec<&Sample> = hours.iter().map(|(_, sample)| sample).collect();
+
+      let max = |value: fn(&Sample) -> f64| samples.iter().map(|s| value(s)).fold(f64::MIN, f64::max);
+      let min = |value: fn(&Sample) -> f64| samples.iter().map(|s| value(s)).fold(f64::MAX, f64::min);
+      let mean = |value: fn(&Sample) -> f64| samples.iter().map(|s| value(s)).sum::<f64>() / 24.0;
+      let total_precipitation = round(samples.iter().map(|s| s.precipitation).sum(), 1);
+      // The wettest hour when it rains, otherwise midday.
+      let main = match total_precipitation > 0.0 {
+        true => samples
+          .iter()
+          .max_by(|a, b| a.precipitation.total_cmp(&b.precipitation))
+          .unwrap(),
+        false => &samples[12],
+      };
+
+      json!({
+        "date": date.format("%Y-%m-%d").to_string(),
+        "day": {
+          "maxtemp_c": max(|s| s.temperature),
+          "maxtemp_f": fahrenheit(max(|s| s.temperature)),
+          "mintemp_c": min(|s| s.temperature),
+          "mintemp_f": fahrenheit(min(|s| s.temperature)),
+          "avgtemp_c": round(mean(|s| s.temperature), 1),
+          "avgtemp_f": fahrenheit(mean(|s| s.temperature)),
+          "maxwind_mph": mph(max(|s| s.wind_speed)),
+          "maxwind_kph": max(|s| s.wind_speed),
+          "totalprecip_mm": total_precipitation,
+          "totalprecip_in": inches(total_precipitation),
+          "avghumidity": mean(|s| s.humidity as f64).round() as i32,
+          "daily_chance_of_rain": samples.iter().map(|s| s.chance_of_rain).max(),
+          "daily_chance_of_snow": samples.iter().map(|s| s.chance_of_snow).max(),
+          "condition": condition(main.code, true),
+          "uv": max(|s| s.uv),
+        },
+        "astro": astro(place, date, now),
+        "hour": hours.iter().map(|(time, sample)| hour(place, *time, sample)).collect::<Vec<Value>>(),
+      })
+    })
+    .collect()
+}
+
+/// Alerts for the strong fronts passing in the forecast days.
+fn alerts(place: &Place, now: DateTime<Utc>, days: i64) -> Vec<Value> {
+  let end = day_start(
+    place,
+    local(place, now).date_naive() + Duration::days(days.clamp(1, 14)),
+  );
+  let mut alerts = Vec::new();
+  let mut time = now - Duration::hours(12);
+  while time < end {
+    let (front, _) = front(place, time);
+    let expires = front.centre + Duration::hours(6);
+    if front.strength > ALERT_STRENGTH && expires > now && front.centre < end {
+      alerts.push(alert(place, &front, sample(place, front.centre)));
+    }
+    time = front.centre + Duration::hours(FRONT_PERIOD as i64);
+  }
+  alerts
+}
+
+fn alert(place: &Place, front: &Front, sample: Sample) -> Value {
+  let (colour, severity) = match front.strength > 0.93 {
+    true => ("Amber", "Severe"),
+    false => ("Yellow", "Moderate"),
+  };
+  let (event, description, instruction) = match (sample.temperature < 1.0, sample.wind_speed > 45.0)
+  {
+    (true, _) => (
+      "Snow",
+      "Snow may cause travel delays and power cuts.",
+      "Allow extra time for journeys.",
+    ),
+    (false, true) => (
+      "Wind",
+      "Strong winds may bring down trees and cause travel disruption.",
+      "Secure loose objects and take care near the coast.",
+    ),
+    (false, false) => (
+      "Rain",
+      "Heavy rain may lead to flooding of homes and roads.",
+      "Avoid driving through flood water.",
+    ),
+  };
+  json!({
+    "headline": format!("{} warning for {} issued for {}", colour, event.to_lowercase(), place.region),
+    "msgtype": "Alert",
+    "severity": severity,
+    "urgency": "Expected",
+    "areas": place.region,
+    "category": "Met",
+    "certainty": "Likely",
+    "event": format!("{} {} warning", colour, event),
+    "note": "Generated by the fake provider.",
+    "effective": (front.centre - Duration::hours(6)).to_rfc3339(),
+    "expires": (front.centre + Duration::hours(6)).to_rfc3339(),
+    "desc": description,
+    "instruction": instruction,
+  })
+}
+
+/// The alerts as CAP 1.2 messages, in a document of their own.
+/// A message without info blocks stands in when there are no alerts, as CAP needs a message.
+fn cap(place: &Place, now: DateTime<Utc>, days: i64) -> String {
+  let alerts = alerts(place, now, days);
+  let mut output = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<alerts>\n");
+  let message = |identifier: &str, sent: &str, note: &str| {
+    format!(
+      "  <alert xmlns=\"urn:oasis:names:tc:emergency:cap:1.2\">\n    <identifier>{}</identifier>\n    <sender>fake@example.invalid</sender>\n    <sent>{}</sent>\n    <status>Actual</status>\n    <msgType>Alert</msgType>\n    <scope>Public</scope>\n    <note>{}</note>\n",
+      identifier,
+      sent,
+      escape(note)
+    )
+  };
+  if alerts.is_empty() {
+    output.push_str(&message(
+      &fingerprint(&[&place.name]),
+      &now.to_rfc3339(),
+      "No alerts from the fake provider.",
+    ));
+    output.push_str("  </alert>\n");
+  }
+  for alert in &alerts {
+    let field = |name: &str| escape(alert[name].as_str().unwrap_or_default());
+    output.push_str(&message(
+      &fingerprint(&[&place.name, &field("event"), &field("effective")]),
+      &field("effective"),
+      &field("note"),
+    ));
+    output.push_str("    <info>\n");
+    for (element, name) in [
+      ("category", "category"),
+      ("event", "event"),
+      ("urgency", "urgency"),
+      ("severity", "severity"),
+      ("certainty", "certainty"),
+      ("effective", "effective"),
+      ("expires", "expires"),
+      ("headline", "headline"),
+      ("description", "desc"),
+      ("instruction", "instruction"),
+    ] {
+      output.push_str(&format!(
+        "      <{}>{}</{}>\n",
+        element,
+        field(name),
+        element
+      ));
+    }
+    output.push_str(&format!(
+      "      <area>\n        <areaDesc>{}</areaDesc>\n        <circle>{},{} 25</circle>\n      </area>\n",
+      field("areas"),
+      place.lat,
+      place.lon
+    ));
+    output.push_str("    </info>\n  </alert>\n");
+  }
+  output.push_str("</alerts>\n");
+  output
+}
+
+/// Sun and moon times of a date, in local time.
+fn astro(place: &Place, date: NaiveDate, now: DateTime<Utc>) -> Value {
+  let lat = place.lat.to_radians();
+  let declination = declination(date.ordinal());
+  let cos_hour_angle = ((-0.833f64).to_radians().sin() - lat.sin() * declination.sin())
+    / (lat.cos() * declination.cos());
+  // Hours from sunrise to noon, from none in polar night to twelve in polar day.
+  let half_day = cos_hour_angle.clamp(-1.0, 1.0).acos().to_degrees() / 15.0;
+
+  let noon = date.and_time(NaiveTime::MIN) + Duration::hours(12);
+  let at = |hours: f64| noon + Duration::minutes((hours * 60.0).round() as i64);
+  let sunrise = at(-half_day);
+  let sunset = at(half_day);
+
+  let midnight = day_start(place, date) + Duration::hours(12);
+  let age = ((midnight.timestamp() - NEW_MOON) as f64 / 86400.0).rem_euclid(LUNAR_MONTH);
+  let moonrise = sunrise + Duration::minutes((age / LUNAR_MONTH * 24.0 * 60.0) as i64);
+  let moonset = moonrise + Duration::minutes(12 * 60 + 25);
+  let illumination = (1.0 - (2.0 * PI * age / LUNAR_MONTH).cos()) / 2.0 * 100.0;
+
+  let time = |time: chrono::NaiveDateTime| time.format("%I:%M %p").to_string();
+  let local_now = local(place, now).naive_utc();
+  let moon_up = match moonset.date() > date {
+    true => local_now >= moonrise || local_now < moonset - Duration::days(1),
+    false => local_now >= moonrise && local_now < moonset,
+  };
+  json!({
+    "sunrise": time(sunrise),
+    "sunset": time(sunset),
+    "moonrise": time(moonrise),
+    "moonset": time(moonset),
+    "moon_phase": moon_phase(age),
+    "moon_illumination": illumination.round() as i32,
+    "is_moon_up": (local_now.date() == date && moon_up) as i32,
+    "is_sun_up": (local_now.date() == date && local_now >= sunrise && local_now < sunset) as i32,
+  })
+}
+
+fn moon_phase(age: f64) -> &'static str {
+  const PHASES: [&str; 8] = [
+    "New Moon",
+    "Waxing Crescent",
+    "First Quarter",
+    "Waxing Gibbous",
+    "Full Moon",
+    "Waning Gibbous",
+    "Last Quarter",
+    "Waning Crescent",
+  ];
+  PHASES[((age / LUNAR_MONTH * 8.0).round() as usize) % 8]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn london() -> Place {
+    place("51.5171,-0.1062")
+  }
+
+  fn time(hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 7, 10, hour, 0, 0).unwrap()
+  }
+
+  #[test]
+  fn test_place() {
+    assert_eq!(london().lat, 51.5171);
+    assert_eq!(london().lon, -0.1062);
+
+    let named = place("new york");
+    assert_eq!(named.name, "New York");
+    assert_eq!(named, place("new york"));
+    assert_ne!(named.lat, place("Paris").lat);
+    assert!((-60.0..60.0).contains(&named.lat));
+  }
+
+  #[test]
+  fn test_deterministic() {
+    let url = "http://localhost/forecast.json?key=&q=51.5171,-0.1062&days=3&alerts=yes";
+    assert_eq!(respond(url, time(12)), respond(url, time(12)));
+    assert_ne!(respond(url, time(12)), respond(url, time(13)));
+  }
+
+  #[test]
+  fn test_diurnal_curve() {
+    // Averaged over a month, so fronts do not hide the daily cycle.
+    let mean = |hour: u32| {
+      (0..30)
+        .map(|day| sample(&london(), time(hour) + Duration::days(day)).temperature)
+        .sum::<f64>()
+        / 30.0
+    };
+    assert!(mean(15) > mean(4) + 3.0);
+
+    let winter = Utc.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap();
+    assert!(sample(&london(), winter).temperature < sample(&london(), time(12)).temperature);
+    assert!(sample(&london(), time(12)).is_day);
+    assert!(!sample(&london(), time(0)).is_day);
+  }
+
+  #[test]
+  fn test_fronts() {
+    let samples: Vec<Sample> = (0..24 * 60)
+      .map(|hour| sample(&london(), time(0) + Duration::hours(hour)))
+      .collect();
+    assert!(samples.iter().any(|sample| sample.precipitation > 0.0));
+    assert!(samples.iter().any(|sample| sample.code == 1000));
+    assert!(samples
+      .iter()
+      .all(|sample| condition::lookup(sample.code).is_some()));
+  }
+
+  #[test]
+  fn test_alerts() {
+    let count: usize = (0..60)
+      .map(|day| alerts(&london(), time(0) + Duration::days(day * 3), 3).len())
+      .sum();
+    assert!(count > 0);
+
+    // The same alerts, whenever they are asked for.
+    let day = (0..60)
+      .map(|day| time(0) + Duration::days(day * 3))
+      .find(|time| !alerts(&london(), *time, 3).is_empty())
+      .unwrap();
+    assert_eq!(
+      alerts(&london(), day, 3),
+      alerts(&london(), day + Duration::minutes(17), 3)
+    );
+
+    let body: Value = serde_json::from_str(
+      &respond(
+        "http://localhost/forecast.json?q=51.5171,-0.1062&days=3",
+        time(12),
+      )
+      .unwrap(),
+    )
+    .unwrap();
+    assert!(body.get("alerts").is_none());
+    assert_eq!(body["forecast"]["forecastday"].as_array().unwrap().len(), 3);
+    assert_eq!(
+      body["forecast"]["forecastday"][0]["hour"]
+        .as_array()
+        .unwrap()
+        .len(),
+      24
+    );
+  }
+
+  #[test]
+  fn test_cap() {
+    let day = (0..240)
+      .map(|day| time(0) + Duration::days(day * 3))
+      .find(|time| !alerts(&place(TOWN), *time, 3).is_empty())
+      .unwrap();
+    let body = respond("https://alerts.example.com/cap.xml?days=3", day).unwrap();
+    let parsed = crate::cap::parse(&body).unwrap();
+    assert_eq!(parsed.len(), alerts(&place(TOWN), day, 3).len());
+    assert_eq!(parsed.alerts[0].areas, "Fake Region");
+    assert_eq!(parsed.alerts[0].geometry[0].circles.len(), 1);
+
+    let calm = (0..60)
+      .map(|day| time(0) + Duration::days(day * 3))
+      .find(|time| alerts(&place(TOWN), *time, 1).is_empty())
+      .unwrap();
+    let body = respond("https://alerts.example.com/cap.xml", calm).unwrap();
+    assert!(crate::cap::parse(&body).unwrap().is_empty());
+
+    assert!(respond("http://localhost/history.json?q=London", day).is_err());
+  }
+
+  #[test]
+  fn test_astro() {
+    let astro = astro(
+      &london(),
+      NaiveDate::from_ymd_opt(2024, 12, 21).unwrap(),
+      time(12),
+    );
+    assert_eq!(astro["sunrise"], "08:05 AM");
+    assert_eq!(astro["sunset"], "03:55 PM");
+    assert!(moon_phase(0.0) == "New Moon" && moon_phase(14.8) == "Full Moon");
+  }
+
+  #[test]
+  fn test_unknown_endpoint() {
+    assert!(matches!(
+      respond("http://localhost/history.json?q=London", time(12)),
+      Err(Error::Response { .. })
+    ));
+  }
+}
@@ -0,0 +1,300 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{
+  alert::{Alert, Alerts},
+  error::Error,
+  fingerprint,
+};
+
+/// Syndication formats alerts can be rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+  Atom,
+  Rss,
+}
+
+impl Format {
+  pub fn content_type(&self) -> &'static str {
+    match self {
+      Format::Atom => "application/atom+xml; charset=utf-8",
+      Format::Rss => "application/rss+xml; charset=utf-8",
+    }
+  }
+}
+
+impl FromStr for Format {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_lowercase().as_str() {
+      "atom" => Ok(Format::Atom),
+      "rss" => Ok(Format::Rss),
+      _ => Err(Error::InvalidArgument {
+        arg: "format".to_string(),
+        message: format!("unknown feed format {}", s),
+      }),
+    }
+  }
+}
+
+/// Render alerts for a place as a feed, so they can be followed in a feed reader.
+/// Entry ids are derived from the alert content and the feed id from the place,
+/// so readers recognise entries they have already shown.
+/// `link` is the URL an Atom feed is served from, if it is served.
+pub fn render(
+  format: Format,
+  place: &str,
+  alerts: &Alerts,
+  now: DateTime<Utc>,
+  link: Option<&str>,
+) -> String {
+  match format {
+    Format::Atom => atom(place, alerts, now, link),
+    Format::Rss => rss(place, alerts, now),
+  }
+}
+
+fn atom(place: &str, alerts: &Alerts, now: DateTime<Utc>, link: Option<&str>) -> String {
+  let updated = alerts
+    .alerts
+    .iter()
+    .filter_map(Alert::effective_at)
+    .max()
+    .unwrap_or(now);
+
+  let mut output = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+  output.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+  output.push_str(&format!(
+    "  <id>urn:weather:alerts:{}</id>\n",
+    fingerprint(&[place])
+  ));
+  output.push_str(&format!("  <title>{}</title>\n", escape(&title(place))));
+  if let Some(link) = link {
+    output.push_str(&format!(
+      "  <link rel=\"self\" href=\"{}\"/>\n",
+      escape(link)
+    ));
+  }
+  output.push_str(&format!("  <updated>{}</updated>\n", updated.to_rfc3339()));
+  output.push_str("  <author>\n    <name>weather</name>\n  </author>\n");
+  output.push_str("  <generator>weather</generator>\n");
+
+  for alert in &alerts.alerts {
+    output.push_str("  <entry>\n");
+    output.push_str(&format!("    <id>urn:weather:alert:{}</id>\n", alert.id()));
+    output.push_str(&format!("    <title>{}</title>\n", escape(&alert.headline)));
+    output.push_str(&format!(
+      "    <updated>{}</updated>\n",
+      alert.effective_at().unwrap_or(updated).to_rfc3339()
+    ));
+    if let Some(effective) = alert.effective_at() {
+      output.push_str(&format!(
+        "    <published>{}</published>\n",
+        effective.to_rfc3339()
+      ));
+    }
+    output.push_str(&format!(
+      "    <category term=\"{}\"/>\n",
+      escape(&alert.severity_level().to_string().to_lowercase())
+    ));
+    output.push_str(&format!(
+      "    <summary>{}</summary>\n",
+      escape(&summary(alert))
+    ));
+    output.push_str(&format!(
+      "    <content type=\"text\">{}</content>\n",
+      escape(&content(alert))
+    ));
+    output.push_str("  </entry>\n");
+  }
+
+  output.push_str("</feed>\n");
+  output
+}
+
+fn rss(place: &str, alerts: &Alerts, now: DateTime<Utc>) -> String {
+  let mut output = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+  output.push_str("<rss version=\"2.0\">\n");
+  output.push_str("  <channel>\n");
+  output.push_str(&format!("    <title>{}</title>\n", escape(&title(place))));
+  output.push_str("    <link>https://www.weatherapi.com/</link>\n");
+  output.push_str(&format!(
+    "    <description>{}</description>\n",
+    escape(&title(place))
+  ));
+  output.push_str(&format!(
+    "    <lastBuildDate>{}</lastBuildDate>\n",
+    now.to_rfc2822()
+  ));
+  output.push_str("    <generator>weather</generator>\n");
+
+  for alert in &alerts.alerts {
+    output.push_str("    <item>\n");
+    output.push_str(&format!(
+      "      <guid isPermaLink=\"false\">urn:weather:alert:{}</guid>\n",
+      alert.id()
+    ));
+    output.push_str(&format!(
+      "      <title>{}</title>\n",
+      escape(&alert.headline)
+    ));
+    if let Some(effective) = alert.effective_at() {
+      output.push_str(&format!(
+        "      <pubDate>{}</pubDate>\n",
+        effective.to_rfc2822()
+      ));
+    }
+    output.push_str(&format!(
+      "      <category>{}</category>\n",
+      escape(&alert.severity_level().to_string().to_lowercase())
+    ));
+    output.push_str(&format!(
+      "      <description>{}</description>\n",
+      escape(&content(alert))
+    ));
+    output.push_str("    </item>\n");
+  }
+
+  output.push_str("  </channel>\n");
+  output.push_str("</rss>\n");
+  output
+}
+
+fn title(place: &str) -> String {
+  format!("Weather alerts for {}", place)
+}
+
+fn summary(alert: &Alert) -> String {
+  format!("{} {}: {}", alert.severity, alert.event, alert.areas)
+}
+
+/// Body of an entry, leaving out empty fields.
+fn content(alert: &Alert) -> String {
+  [
+    ("Severity", &alert.severity),
+    ("Areas", &alert.areas),
+    ("Effective", &alert.effective),
+    ("Expires", &alert.expires),
+    ("Description", &alert.description),
+    ("Instruction", &alert.instruction),
+  ]
+  .into_iter()
+  .filter(|(_, value)| !value.trim().is_empty())
+  .map(|(label, value)| format!("{}: {}", label, value.trim()))
+  .collect::<Vec<_>>()
+  .join("\n")
+}
+
+/// Escape text for use in XML content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  fn alerts() -> Alerts {
+    Alerts::new(vec![Alert {
+      headline: "Flood warning <River Thames>".to_string(),
+      description: "Flooding is expected.".to_string(),
+      urgency: "Expected".to_string(),
+      areas: "London & Kent".to_string(),
+      event: "Flood Warning".to_string(),
+      effective: "2024-12-28T06:00:00+00:00".to_string(),
+      expires: "2024-12-29T06:00:00+00:00".to_string(),
+      instruction: "Move to higher ground.".to_string(),
+      ..Alert::sample()
+    }])
+  }
+
+  fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 12, 28, 9, 0, 0).unwrap()
+  }
+
+  #[test]
+  fn test_atom() {
+    let output = render(Format::Atom, "London", &alerts(), now(), None);
+    let id = alerts().alerts[0].id();
+
+    assert!(output.contains("<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+    assert!(output.contains("<title>Weather alerts for London</title>"));
+    assert!(output.contains("<updated>2024-12-28T06:00:00+00:00</updated>"));
+    assert!(output.contains("<author>\n    <name>weather</name>\n  </author>"));
+    assert!(!output.contains("<link"));
+    assert!(output.contains(&format!("<id>urn:weather:alert:{}</id>", id)));
+    assert!(output.contains("<title>Flood warning &lt;River Thames&gt;</title>"));
+    assert!(output.contains("<category term=\"severe\"/>"));
+    assert!(output.contains("Areas: London &amp; Kent\nEffective: 2024-12-28T06:00:00+00:00"));
+    assert!(output.contains("Instruction: Move to higher ground.</content>"));
+    assert!(output.ends_with("</feed>\n"));
+  }
+
+  #[test]
+  fn test_atom_link() {
+    let link = "http://localhost:8080/alerts?place=office&format=atom";
+    let output = render(Format::Atom, "London", &alerts(), now(), Some(link));
+    assert!(output.contains(
+      "<link rel=\"self\" href=\"http://localhost:8080/alerts?place=office&amp;format=atom\"/>"
+    ));
+  }
+
+  #[test]
+  fn test_atom_empty() {
+    let output = render(
+      Format::Atom,
+      "London",
+      &Alerts::new(Vec::new()),
+      now(),
+      None,
+    );
+    assert!(output.contains("<updated>2024-12-28T09:00:00+00:00</updated>"));
+    assert!(!output.contains("<entry>"));
+  }
+
+  #[test]
+  fn test_rss() {
+    let output = render(Format::Rss, "London", &alerts(), now(), None);
+    let id = alerts().alerts[0].id();
+
+    assert!(output.contains("<rss version=\"2.0\">"));
+    assert!(output.contains("<lastBuildDate>Sat, 28 Dec 2024 09:00:00 +0000</lastBuildDate>"));
+    assert!(output.contains(&format!(
+      "<guid isPermaLink=\"false\">urn:weather:alert:{}</guid>",
+      id
+    )));
+    assert!(output.contains("<pubDate>Sat, 28 Dec 2024 06:00:00 +0000</pubDate>"));
+    assert!(output.contains("<category>severe</category>"));
+    assert!(output.ends_with("</rss>\n"));
+  }
+
+  #[test]
+  fn test_stable_ids() {
+    let first = render(Format::Atom, "London", &alerts(), now(), None);
+    let later = render(
+      Format::Atom,
+      "London",
+      &alerts(),
+      now() + chrono::Duration::hours(1),
+      None,
+    );
+    assert_eq!(first, later);
+  }
+
+  #[test]
+  fn test_format_from_str() {
+    assert_eq!("Atom".parse::<Format>().unwrap(), Format::Atom);
+    assert_eq!("rss".parse::<Format>().unwrap(), Format::Rss);
+    assert!("json".parse::<Format>().is_err());
+  }
+}
@@ -0,0 +1,375 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  alert::{Alerts, Response as AlertsResponse},
+  astronomy::{AstroResponse, Astronomy},
+  error::Error,
+  http, keys, locale,
+  location::Location,
+  weather::{self, Condition, ConditionResponse, Response as WeatherResponse, Units, Weather},
+  Query,
+};
+
+#[derive(Debug, Deserialize)]
+struct Response {
+  forecast: ForecastResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastResponse {
+  forecastday: Vec<ForecastDayResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastDayResponse {
+  date: String,
+  day: DayResponse,
+  astro: AstroResponse,
+  hour: Vec<HourResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DayResponse {
+  maxtemp_c: f64,
+  maxtemp_f: f64,
+  mintemp_c: f64,
+  mintemp_f: f64,
+  avgtemp_c: f64,
+  avgtemp_f: f64,
+  maxwind_mph: f64,
+  maxwind_kph: f64,
+  totalprecip_mm: f64,
+  totalprecip_in: f64,
+  avghumidity: f64,
+  #[serde(default)]
+  daily_chance_of_rain: i32,
+  #[serde(default)]
+  daily_chance_of_snow: i32,
+  condition: ConditionResponse,
+  uv: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct HourResponse {
+  time: String,
+  temp_c: f64,
+  temp_f: f64,
+  is_day: i32,
+  condition: ConditionResponse,
+  wind_mph: f64,
+  wind_kph: f64,
+  wind_degree: i32,
+  wind_dir: String,
+  pressure_mb: f64,
+  pressure_in: f64,
+  precip_mm: f64,
+  precip_in: f64,
+  humidity: i32,
+  cloud: i32,
+  feelslike_c: f64,
+  feelslike_f: f64,
+  #[serde(default)]
+  chance_of_rain: i32,
+  #[serde(default)]
+  chance_of_snow: i32,
+  gust_mph: f64,
+  gust_kph: f64,
+  uv: f64,
+}
+
+/// Forecast for one or more days, each with hourly detail.
+#[derive(Debug, Clone, Serialize)]
+pub struct Forecast {
+  pub days: Vec<ForecastDay>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastDay {
+  /// Date of the day, formatted as `YYYY-MM-DD`.
+  pub date: String,
+  pub max_temperature: f64,
+  pub min_temperature: f64,
+  pub avg_temperature: f64,
+  pub max_wind_speed: f64,
+  pub total_precipitation: f64,
+  pub avg_humidity: f64,
+  pub chance_of_rain: i32,
+  pub chance_of_snow: i32,
+  pub condition: Condition,
+  pub uv: f64,
+  pub astronomy: Astronomy,
+  pub hours: Vec<Hour>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hour {
+  /// Local time of the hour, formatted as `YYYY-MM-DD HH:MM`.
+  pub time: String,
+  pub is_day: bool,
+  pub temperature: f64,
+  pub feels_like: f64,
+  pub condition: Condition,
+  pub wind_speed: f64,
+  pub wind_degree: i32,
+  pub wind_dir: String,
+  pub wind_gust_speed: f64,
+  pub pressure: f64,
+  pub precipitation: f64,
+  pub humidity: i32,
+  pub cloud: i32,
+  pub chance_of_rain: i32,
+  pub chance_of_snow: i32,
+  pub uv: f64,
+}
+
+impl From<(Response, Units)> for Forecast {
+  fn from((response, units): (Response, Units)) -> Self {
+    let days = response
+      .forecast
+      .forecastday
+      .into_iter()
+      .map(|day| ForecastDay::from((day, units)))
+      .collect();
+    Forecast { days }
+  }
+}
+
+impl From<(ForecastDayResponse, Units)> for ForecastDay {
+  fn from((response, units): (ForecastDayResponse, Units)) -> Self {
+    let day = response.day;
+    let imperial = units == Units::Imperial;
+    let pick = |metric: f64, imperial_value: f64| match imperial {
+      true => imperial_value,
+      false => metric,
+    };
+
+    ForecastDay {
+      date: response.date,
+      max_temperature: pick(day.maxtemp_c, day.maxtemp_f),
+      min_temperature: pick(day.mintemp_c, day.mintemp_f),
+      avg_temperature: pick(day.avgtemp_c, day.avgtemp_f),
+      max_wind_speed: pick(day.maxwind_kph, day.maxwind_mph),
+      total_precipitation: pick(day.totalprecip_mm, day.totalprecip_in),
+      avg_humidity: day.avghumidity,
+      chance_of_rain: day.daily_chance_of_rain,
+      chance_of_snow: day.daily_chance_of_snow,
+      condition: Condition::from(day.condition),
+      uv: day.uv,
+      astronomy: Astronomy::from(response.astro),
+      hours: response
+        .hour
+        .into_iter()
+        .map(|hour| Hour::from((hour, units)))
+        .collect(),
+    }
+  }
+}
+
+impl From<(HourResponse, Units)> for Hour {
+  fn from((response, units): (HourResponse, Units)) -> Self {
+    let imperial = units == Units::Imperial;
+    let pick = |metric: f64, imperial_value: f64| match imperial {
+      true => imperial_value,
+      false => metric,
+    };
+
+    Hour {
+      time: response.time,
+      is_day: response.is_day == 1,
+      temperature: pick(response.temp_c, response.temp_f),
+      feels_like: pick(response.feelslike_c, response.feelslike_f),
+      condition: Condition::from(response.condition),
+      wind_speed: pick(response.wind_kph, response.wind_mph),
+      wind_degree: response.wind_degree,
+      wind_dir: response.wind_dir,
+      wind_gust_speed: pick(response.gust_kph, response.gust_mph),
+      pressure: pick(response.pressure_mb, response.pressure_in),
+      precipitation: pick(response.precip_mm, response.precip_in),
+      humidity: response.humidity,
+      cloud: response.cloud,
+      chance_of_rain: response.chance_of_rain,
+      chance_of_snow: response.chance_of_snow,
+      uv: response.uv,
+    }
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for ForecastDay {
+  fn to_string(&self) -> String {
+    let locale = locale::current();
+    format!(
+      "{}: {}, {}: {}, {}: {}, {}: {}%, {}: {}",
+      locale.date(&self.date),
+      self.condition.text,
+      locale.label("High"),
+      locale.number(self.max_temperature),
+      locale.label("Low"),
+      locale.number(self.min_temperature),
+      locale.label("Chance of Rain"),
+      self.chance_of_rain,
+      locale.label("Precipitation"),
+      locale.number(self.total_precipitation)
+    )
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Forecast {
+  fn to_string(&self) -> String {
+    self
+      .days
+      .iter()
+      .map(ForecastDay::to_string)
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+}
+
+/// Get the daily and hourly forecast for a location.
+/// `days` is the number of days to forecast, including today.
+pub async fn get_forecast(location: Location, units: Units, days: i32) -> Result<Forecast, Error> {
+  let location_query = location.query()?;
+
+  let address = http::address();
+  let key = keys::env_key();
+
+  let url = format!(
+    "{}/forecast.json?key={}&q={}&days={}{}",
+    address,
+    key,
+    location_query,
+    days,
+    locale::current().query()
+  );
+
+  let forecast: Response = http::get_json(&url).await?;
+  Ok(Forecast::from((forecast, units)))
+}
+
+/// Current weather, forecast and alerts of a location.
+#[derive(Debug, Clone)]
+pub struct Outlook {
+  pub weather: Weather,
+  pub forecast: Forecast,
+  pub alerts: Alerts,
+}
+
+/// Get the current weather, forecast and alerts of a location with a single request,
+/// for commands that show all three and run often.
+pub async fn get_outlook(location: Location, units: Units, days: i32) -> Result<Outlook, Error> {
+  let location_query = location.query()?;
+
+  let address = http::address();
+  let key = keys::env_key();
+
+  let url = format!(
+    "{}/forecast.json?key={}&q={}&days={}&alerts=yes{}",
+    address,
+    key,
+    location_query,
+    days,
+    locale::current().query()
+  );
+
+  let body = http::get(&url).await?;
+  let weather: WeatherResponse = http::parse_json(&body)?;
+  let forecast: Response = http::parse_json(&body)?;
+  let alerts: AlertsResponse = http::parse_json(&body)?;
+  Ok(Outlook {
+    weather: weather::observed(&location, weather, units),
+    forecast: Forecast::from((forecast, units)),
+    alerts: Alerts::from(alerts),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::replay::{self, fixtures};
+  use tokio;
+
+  fn setup() {
+    dotenv::dotenv().ok();
+    std::env::set_var("WEATHER_KEY", "test_key");
+  }
+
+  fn location() -> Location {
+    Location {
+      country: "United Kingdom".to_string(),
+      region: Some("City of London, Greater London".to_string()),
+      city: Some("London".to_string()),
+      lat: Some(51.5171),
+      lon: Some(-0.1062),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_get_forecast_metric() {
+    setup();
+
+    let result = replay::scope(
+      fixtures("london"),
+      get_forecast(location(), Units::Metric, 1),
+    )
+    .await;
+    assert!(result.is_ok());
+
+    let forecast = result.unwrap();
+    assert_eq!(forecast.days.len(), 1);
+
+    let day = &forecast.days[0];
+    assert_eq!(day.date, "2024-12-28");
+    assert_eq!(day.max_temperature, 7.2);
+    assert_eq!(day.min_temperature, 4.1);
+    assert_eq!(day.chance_of_rain, 40);
+    assert_eq!(day.condition.text, "Overcast");
+    assert_eq!(day.astronomy.sunrise, "08:06 AM");
+    assert_eq!(day.hours.len(), 1);
+    assert_eq!(day.hours[0].temperature, 4.5);
+    assert_eq!(day.hours[0].wind_degree, 206);
+  }
+
+  #[tokio::test]
+  async fn test_get_outlook() {
+    setup();
+
+    let outlook = replay::scope(
+      fixtures("london"),
+      get_outlook(location(), Units::Metric, 3),
+    )
+    .await
+    .unwrap();
+    assert_eq!(outlook.weather.temperature, 5.1);
+    assert_eq!(outlook.forecast.days[0].max_temperature, 7.2);
+    assert_eq!(outlook.alerts.len(), 1);
+  }
+
+  #[tokio::test]
+  async fn test_get_forecast_imperial() {
+    setup();
+
+    let forecast = replay::scope(
+      fixtures("london"),
+      get_forecast(location(), Units::Imperial, 1),
+    )
+    .await
+    .unwrap();
+    let day = &forecast.days[0];
+    assert_eq!(day.max_temperature, 45.0);
+    assert_eq!(day.max_wind_speed, 6.3);
+    assert_eq!(day.hours[0].feels_like, 39.0);
+  }
+
+  #[tokio::test]
+  async fn test_get_forecast_no_location() {
+    setup();
+
+    let mut location = location();
+    location.lat = None;
+    location.lon = None;
+
+    let result = get_forecast(location, Units::Metric, 1).await;
+    assert_eq!(result.err().unwrap(), Error::NoLocation);
+  }
+}
@@ -0,0 +1,787 @@
+use std::{
+  cell::Cell,
+  future::Future,
+  str::FromStr,
+  sync::{Arc, RwLock},
+  time::{Duration, Instant, SystemTime},
+};
+
+use chrono::{DateTime, Utc};
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use serde::de::DeserializeOwned;
+use tracing::{field, Instrument, Span};
+
+use crate::{
+  cache::Cache,
+  config::HttpConfig,
+  error::Error,
+  fake,
+  keys::{rejects_key, KeyRing},
+  quota::Quota,
+  redact::{describe, redact},
+  replay::{self, Mode},
+};
+
+static CLIENT: RwLock<Option<reqwest::Client>> = RwLock::new(None);
+static CACHE: RwLock<Option<Cache>> = RwLock::new(None);
+static POLICY: RwLock<Option<Policy>> = RwLock::new(None);
+static QUOTA: RwLock<Option<Arc<Quota>>> = RwLock::new(None);
+static KEYS: RwLock<Option<Arc<KeyRing>>> = RwLock::new(None);
+static PROVIDER: RwLock<Option<Provider>> = RwLock::new(None);
+
+tokio::task_local! {
+  static FETCHED: Cell<Option<DateTime<Utc>>>;
+}
+
+/// Address of the provider API when `WEATHER_API` is not set.
+const DEFAULT_ADDRESS: &str = "https://api.weatherapi.com/v1";
+
+/// Where weather comes from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Provider {
+  /// The provider API at `WEATHER_API`.
+  WeatherApi,
+  /// Made up weather, without network or key, for development and tests.
+  Fake,
+}
+
+impl FromStr for Provider {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_lowercase().as_str() {
+      "weatherapi" => Ok(Provider::WeatherApi),
+      "fake" => Ok(Provider::Fake),
+      _ => Err(Error::InvalidArgument {
+        arg: "provider".to_string(),
+        message: format!("unknown provider {}", s),
+      }),
+    }
+  }
+}
+
+/// How requests are sent, timed out and retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+  pub connect_timeout: Duration,
+  /// Time allowed for a whole request, from connecting to reading the last byte.
+  pub timeout: Duration,
+  /// Retries after the first attempt, on connection errors, server errors and rate limits.
+  pub retries: u32,
+  /// Delay before the first retry, doubled for each retry after it.
+  pub backoff: Duration,
+  /// Longest delay between attempts. Rate limits asking to wait longer fail at once.
+  pub max_delay: Duration,
+  /// Header the API key is sent in, taken out of the URL, when set.
+  pub key_header: Option<String>,
+}
+
+impl Default for Policy {
+  fn default() -> Self {
+    (&HttpConfig::default()).into()
+  }
+}
+
+impl From<&HttpConfig> for Policy {
+  fn from(config: &HttpConfig) -> Self {
+    Policy {
+      connect_timeout: Duration::from_secs(config.connect_timeout),
+      timeout: Duration::from_secs(config.timeout),
+      retries: config.retries,
+      backoff: Duration::from_millis(config.backoff),
+      max_delay: Duration::from_secs(config.max_delay),
+      key_header: config.key_header.clone(),
+    }
+  }
+}
+
+impl Policy {
+  /// Delay before retry number `attempt`, counting from zero.
+  /// Half of the exponential delay is fixed and half is random, so clients do not retry in step.
+  pub fn backoff(&self, attempt: u32) -> Duration {
+    let delay = self
+      .backoff
+      .saturating_mul(2u32.saturating_pow(attempt))
+      .min(self.max_delay);
+    delay / 2 + delay.mul_f64(jitter() / 2.0)
+  }
+
+  fn client(&self) -> reqwest::Client {
+    reqwest::Client::builder()
+      .connect_timeout(self.connect_timeout)
+      .timeout(self.timeout)
+      .build()
+      .unwrap_or_default()
+  }
+}
+
+/// A pseudo random number in `[0, 1)`, from the clock.
+/// Good enough to spread retries out, without needing a random number generator.
+fn jitter() -> f64 {
+  let nanos = SystemTime::now()
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .unwrap_or_default()
+    .subsec_nanos();
+  (nanos.wrapping_mul(2654435761) % 1_000_000) as f64 / 1_000_000.0
+}
+
+/// Set the timeouts and retries used by every request.
+pub fn set_policy(policy: Policy) {
+  *CLIENT.write().unwrap() = Some(policy.client());
+  *POLICY.write().unwrap() = Some(policy);
+}
+
+fn policy() -> Policy {
+  POLICY.read().unwrap().clone().unwrap_or_default()
+}
+
+/// Shared HTTP client, reused by every request so connections are pooled.
+/// Uses the timeouts of the request policy.
+pub fn client() -> reqwest::Client {
+  if let Some(client) = CLIENT.read().unwrap().as_ref() {
+    return client.clone();
+  }
+  CLIENT
+    .write()
+    .unwrap()
+    .get_or_insert_with(|| policy().client())
+    .clone()
+}
+
+/// Set the response cache used by every provider request.
+/// `None` disables caching, which is the default.
+pub fn set_cache(cache: Option<Cache>) {
+  *CACHE.write().unwrap() = cache;
+}
+
+fn cache() -> Option<Cache> {
+  CACHE.read().unwrap().clone()
+}
+
+/// Set the quota that requests with an API key are counted against.
+/// `None` neither limits nor counts requests, which is the default.
+pub fn set_quota(quota: Option<Quota>) {
+  *QUOTA.write().unwrap() = quota.map(Arc::new);
+}
+
+fn quota() -> Option<Arc<Quota>> {
+  QUOTA.read().unwrap().clone()
+}
+
+/// Set the keys that requests with an API key are spread over, in place of the key in the URL.
+/// `None` uses the key in the URL, which is the default.
+pub fn set_keys(keys: Option<KeyRing>) {
+  *KEYS.write().unwrap() = keys.map(Arc::new);
+}
+
+fn keys() -> Option<Arc<KeyRing>> {
+  KEYS.read().unwrap().clone()
+}
+
+/// Set the provider that every request is answered by.
+/// The default is the provider API.
+pub fn set_provider(provider: Provider) {
+  *PROVIDER.write().unwrap() = Some(provider);
+}
+
+/// Provider that requests are answered by.
+pub fn provider() -> Provider {
+  PROVIDER.read().unwrap().unwrap_or(Provider::WeatherApi)
+}
+
+/// Address of the provider API, from `WEATHER_API`.
+pub fn address() -> String {
+  std::env::var("WEATHER_API")
+    .ok()
+    .filter(|address| !address.is_empty())
+    .unwrap_or_else(|| DEFAULT_ADDRESS.to_string())
+}
+
+/// API key given in the `key` parameter of a URL.
+fn api_key(url: &str) -> Option<String> {
+  reqwest::Url::parse(url)
+    .ok()?
+    .query_pairs()
+    .find(|(name, _)| name == "key")
+    .map(|(_, key)| key.into_owned())
+}
+
+/// Path of a URL, naming the provider endpoint.
+fn endpoint(url: &str) -> String {
+  reqwest::Url::parse(url)
+    .map(|url| url.path().to_string())
+    .unwrap_or_default()
+}
+
+/// Location query given in the `q` parameter of a URL.
+fn location_query(url: &str) -> String {
+  reqwest::Url::parse(url)
+    .ok()
+    .and_then(|url| {
+      url
+        .query_pairs()
+        .find(|(name, _)| name == "q")
+        .map(|(_, query)| query.into_owned())
+    })
+    .unwrap_or_default()
+}
+
+/// The URL with its `key` parameter set to `key`, or taken out when `None`.
+fn with_key(url: &str, key: Option<&str>) -> String {
+  let Ok(mut url) = reqwest::Url::parse(url) else {
+    return url.to_string();
+  };
+  let pairs: Vec<(String, String)> = url
+    .query_pairs()
+    .filter_map(|(name, value)| match name == "key" {
+      true => Some((name.into_owned(), key?.to_string())),
+      false => Some((name.into_owned(), value.into_owned())),
+    })
+    .collect();
+  match pairs.is_empty() {
+    true => url.set_query(None),
+    false => {
+      url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+  }
+  url.to_string()
+}
+
+/// Run `future`, also returning when the oldest response it used was fetched from the provider.
+/// Responses served from the cache count as fetched when they were stored.
+pub async fn with_fetched<F: Future>(future: F) -> (F::Output, Option<DateTime<Utc>>) {
+  FETCHED
+    .scope(Cell::new(None), async {
+      let output = future.await;
+      (output, FETCHED.with(Cell::get))
+    })
+    .await
+}
+
+/// Note that a response fetched at `time` was used, when in `with_fetched`.
+fn fetched(time: DateTime<Utc>) {
+  FETCHED
+    .try_with(|fetched| fetched.set(Some(fetched.get().map_or(time, |oldest| oldest.min(time)))))
+    .ok();
+}
+
+/// Fetch a URL and return the response body.
+/// Successful responses are stored in, and served from, the cache when one is set.
+/// Requests that reach the provider with an API key are counted against the quota when one is set.
+/// Failed requests are retried as set by the request policy.
+/// Responses are recorded to, or replayed from, fixtures when the replay mode is set.
+/// With the fake provider, responses are made up without cache, quota or keys.
+/// Each request is traced in a `request` span with its endpoint, location query, cache use, status and latency.
+pub async fn get(url: &str) -> Result<String, Error> {
+  let span = tracing::info_span!(
+    "request",
+    endpoint = %endpoint(url),
+    query = %redact(&location_query(url)),
+    cache = field::Empty,
+    status = field::Empty,
+    latency_ms = field::Empty,
+  );
+  request(url).instrument(span).await
+}
+
+async fn request(url: &str) -> Result<String, Error> {
+  let mode = replay::mode();
+  if let Some(Mode::Replay(dir)) = &mode {
+    Span::current().record("cache", "replay");
+    return replay::load(dir, url);
+  }
+  if provider() == Provider::Fake {
+    Span::current().record("cache", "fake");
+    fetched(Utc::now());
+    return record(mode, url, fake::respond(url, Utc::now())?);
+  }
+
+  let cache = cache();
+  if let Some((body, stored)) = cache.as_ref().and_then(|cache| cache.get_stored(url)) {
+    Span::current().record("cache", "hit");
+    fetched(stored.into());
+    tracing::debug!("served from cache");
+    return record(mode, url, body);
+  }
+  Span::current().record("cache", if cache.is_some() { "miss" } else { "off" });
+
+  let start = Instant::now();
+  let result = send_with_key(url).await;
+  Span::current().record("latency_ms", start.elapsed().as_millis() as u64);
+  let body = match result {
+    Ok(body) => {
+      tracing::info!(bytes = body.len(), "request done");
+      fetched(Utc::now());
+      body
+    }
+    Err(err) => {
+      tracing::warn!(error = %describe(&err), "request failed");
+      return Err(err);
+    }
+  };
+
+  if let Some(cache) = cache {
+    cache.put(url, &body);
+  }
+  record(mode, url, body)
+}
+
+/// Write the response to a fixture when recording.
+fn record(mode: Option<Mode>, url: &str, body: String) -> Result<String, Error> {
+  if let Some(Mode::Record(dir)) = mode {
+    replay::save(&dir, url, &body)?;
+    tracing::debug!(fixture = %replay::fixture_name(url), "recorded");
+  }
+  Ok(body)
+}
+
+/// Fetch a URL with the key ring when one is set, otherwise with the key in the URL.
+async fn send_with_key(url: &str) -> Result<String, Error> {
+  match (keys(), api_key(url)) {
+    (Some(keys), Some(_)) => fetch_with_keys(&keys, url).await,
+    _ => fetch(&client(), &policy(), url).await,
+  }
+}
+
+/// Fetch a URL with each key of the ring in turn, until one is not rejected.
+/// Rejected keys are left unused for their cooldown.
+async fn fetch_with_keys(keys: &KeyRing, url: &str) -> Result<String, Error> {
+  let now = Utc::now();
+  let mut last = None;
+  for key in keys.order(now, &mut jitter)? {
+    match fetch(&client(), &policy(), &with_key(url, Some(&key.key))).await {
+      Err(err) if rejects_key(&err) => {
+        keys.exhaust(key, &err, now)?;
+        last = Some(err);
+      }
+      Ok(body) => {
+        keys.served(key);
+        return Ok(body);
+      }
+      Err(err) => return Err(err),
+    }
+  }
+  Err(last.unwrap_or(Error::Error {
+    message: "every API key is cooling down".to_string(),
+  }))
+}
+
+/// Outcome of a single attempt that may be worth retrying, after a delay.
+enum Attempt {
+  Done(Result<String, Error>),
+  Retry(Error, Option<Duration>),
+}
+
+/// Fetch a URL, retrying as set by the policy.
+/// Every attempt with an API key is counted against the quota when one is set.
+async fn fetch(client: &reqwest::Client, policy: &Policy, url: &str) -> Result<String, Error> {
+  let quota = quota().zip(api_key(url).filter(|key| !key.is_empty()));
+  let mut attempt = 0;
+  loop {
+    if let Some((quota, key)) = &quota {
+      quota.acquire(key).await?;
+    }
+    let (err, delay) = match send(client, policy, url).await {
+      Attempt::Done(result) => return result,
+      Attempt::Retry(err, delay) => (err, delay),
+    };
+    let delay = delay.unwrap_or_else(|| policy.backoff(attempt));
+    if attempt >= policy.retries || delay > policy.max_delay {
+      return Err(err);
+    }
+    tracing::warn!(
+      attempt = attempt + 1,
+      delay_ms = delay.as_millis() as u64,
+      error = %describe(&err),
+      "retrying"
+    );
+    tokio::time::sleep(delay).await;
+    attempt += 1;
+  }
+}
+
+async fn send(client: &reqwest::Client, policy: &Policy, url: &str) -> Attempt {
+  let request = match (&policy.key_header, api_key(url)) {
+    (Some(header), Some(key)) => client.get(with_key(url, None)).header(header, key),
+    _ => client.get(url),
+  };
+  tracing::trace!(url = %redact(url), "sending");
+  let response = match request.send().await {
+    Ok(response) => response,
+    Err(err) if err.is_timeout() => {
+      return Attempt::Done(Err(Error::Timeout {
+        message: redact(&err.to_string()),
+      }))
+    }
+    Err(err) if err.is_connect() => {
+      return Attempt::Retry(
+        Error::Fetch {
+          message: redact(&err.to_string()),
+        },
+        None,
+      )
+    }
+    Err(err) => {
+      return Attempt::Done(Err(Error::Fetch {
+        message: redact(&err.to_string()),
+      }))
+    }
+  };
+
+  let status = response.status();
+  Span::current().record("status", status.as_u16());
+  let retry_after = response
+    .headers()
+    .get(RETRY_AFTER)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| parse_retry_after(value, Utc::now()));
+  let body = match response.text().await {
+    Ok(body) => body,
+    Err(err) if err.is_timeout() => {
+      return Attempt::Done(Err(Error::Timeout {
+        message: redact(&err.to_string()),
+      }))
+    }
+    Err(err) => {
+      return Attempt::Done(Err(Error::Fetch {
+        message: redact(&err.to_string()),
+      }))
+    }
+  };
+
+  if status == StatusCode::TOO_MANY_REQUESTS {
+    return Attempt::Retry(
+      Error::RateLimited {
+        retry_after: retry_after.map(|delay| delay.as_secs()),
+      },
+      retry_after,
+    );
+  }
+
+  if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+    return Attempt::Done(Err(Error::KeyRejected {
+      status: status.as_u16(),
+      message: body,
+    }));
+  }
+
+  let err = Error::Response {
+    message: format!("unexpected status {}: {}", status, body),
+  };
+  match status {
+    status if status.is_success() => Attempt::Done(Ok(body)),
+    status if status.is_server_error() => Attempt::Retry(err, None),
+    _ => Attempt::Done(Err(err)),
+  }
+}
+
+/// Parse a `Retry-After` header, either a number of seconds or an HTTP date.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+  if let Ok(seconds) = value.trim().parse::<u64>() {
+    return Some(Duration::from_secs(seconds));
+  }
+  let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+  Some(
+    (date.with_timezone(&Utc) - now)
+      .to_std()
+      .unwrap_or_default(),
+  )
+}
+
+/// Fetch a URL and deserialize the JSON response body.
+pub async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
+  parse_json(&get(url).await?)
+}
+
+/// Parse a response body, for responses read as more than one type.
+pub(crate) fn parse_json<T: DeserializeOwned>(body: &str) -> Result<T, Error> {
+  serde_json::from_str(body).map_err(|err| Error::Response {
+    message: err.to_string(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+  use mockito::mock;
+
+  fn policy(retries: u32) -> Policy {
+    Policy {
+      connect_timeout: Duration::from_secs(1),
+      timeout: Duration::from_millis(500),
+      retries,
+      backoff: Duration::from_millis(10),
+      max_delay: Duration::from_secs(2),
+      key_header: None,
+    }
+  }
+
+  async fn get(path: &str, policy: &Policy) -> Result<String, Error> {
+    let url = format!("{}{}", mockito::server_url(), path);
+    fetch(&policy.client(), policy, &url).await
+  }
+
+  #[tokio::test]
+  async fn test_retry_server_errors() {
+    let failing = mock("GET", "/http/flaky")
+      .with_status(503)
+      .expect(2)
+      .create();
+    let ok = mock("GET", "/http/flaky")
+      .with_status(200)
+      .with_body("ok")
+      .create();
+
+    assert_eq!(get("/http/flaky", &policy(2)).await.unwrap(), "ok");
+    failing.assert();
+    ok.assert();
+  }
+
+  #[tokio::test]
+  async fn test_retries_exhausted() {
+    let failing = mock("GET", "/http/down")
+      .with_status(500)
+      .with_body("down")
+      .expect(3)
+      .create();
+
+    assert!(matches!(
+      get("/http/down", &policy(2)).await,
+      Err(Error::Response { .. })
+    ));
+    failing.assert();
+  }
+
+  #[tokio::test]
+  async fn test_client_errors_not_retried() {
+    let missing = mock("GET", "/http/missing")
+      .with_status(404)
+      .expect(1)
+      .create();
+
+    assert!(matches!(
+      get("/http/missing", &policy(2)).await,
+      Err(Error::Response { .. })
+    ));
+    missing.assert();
+  }
+
+  #[tokio::test]
+  async fn test_rate_limited() {
+    let limited = mock("GET", "/http/limited")
+      .with_status(429)
+      .with_header("retry-after", "0")
+      .expect(1)
+      .create();
+    let ok = mock("GET", "/http/limited")
+      .with_status(200)
+      .with_body("ok")
+      .create();
+
+    assert_eq!(get("/http/limited", &policy(1)).await.unwrap(), "ok");
+    limited.assert();
+    ok.assert();
+
+    let _m = mock("GET", "/http/throttled")
+      .with_status(429)
+      .with_header("retry-after", "3600")
+      .create();
+    assert_eq!(
+      get("/http/throttled", &policy(3)).await,
+      Err(Error::RateLimited {
+        retry_after: Some(3600)
+      })
+    );
+  }
+
+  #[tokio::test]
+  async fn test_connect_error() {
+    // Nothing listens on the discard port.
+    let policy = policy(1);
+    let result = fetch(&policy.client(), &policy, "http://127.0.0.1:9/").await;
+    assert!(matches!(result, Err(Error::Fetch { .. })));
+  }
+
+  #[tokio::test]
+  async fn test_timeout() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    // Accept the connection but never answer.
+    let server = tokio::spawn(async move {
+      let (_stream, _) = listener.accept().await.unwrap();
+      tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let policy = policy(2);
+    let url = format!("http://{}/", address);
+    assert!(matches!(
+      fetch(&policy.client(), &policy, &url).await,
+      Err(Error::Timeout { .. })
+    ));
+    server.abort();
+  }
+
+  #[test]
+  fn test_api_key() {
+    assert_eq!(
+      api_key("http://localhost/current.json?key=abc&q=London"),
+      Some("abc".to_string())
+    );
+    assert_eq!(api_key("http://localhost/cap/alert.xml"), None);
+  }
+
+  #[tokio::test]
+  async fn test_key_rejected() {
+    let _m = mock("GET", "/http/forbidden")
+      .with_status(403)
+      .with_body("API key has been disabled")
+      .create();
+
+    assert_eq!(
+      get("/http/forbidden", &policy(2)).await,
+      Err(Error::KeyRejected {
+        status: 403,
+        message: "API key has been disabled".to_string()
+      })
+    );
+  }
+
+  #[tokio::test]
+  async fn test_fetch_with_keys() {
+    use crate::config::ApiKey;
+    use mockito::Matcher;
+
+    let rejected = mock("GET", "/http/keys")
+      .match_query(Matcher::UrlEncoded("key".into(), "team-key".into()))
+      .with_status(401)
+      .expect(1)
+      .create();
+    let served = mock("GET", "/http/keys")
+      .match_query(Matcher::UrlEncoded("key".into(), "tier-key".into()))
+      .with_status(200)
+      .with_body("ok")
+      .expect(2)
+      .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let keys = KeyRing::new(
+      &[
+        ApiKey {
+          key: "team-key".to_string(),
+          weight: 1,
+          name: None,
+        },
+        ApiKey {
+          key: "tier-key".to_string(),
+          weight: 0,
+          name: None,
+        },
+      ],
+      Duration::from_secs(3600),
+      dir.path().join("keys.json"),
+      |_| {},
+    );
+    let url = format!("{}/http/keys?key=&q=London", mockito::server_url());
+
+    assert_eq!(fetch_with_keys(&keys, &url).await.unwrap(), "ok");
+    // The rejected key is cooling down, so is not tried again.
+    assert_eq!(fetch_with_keys(&keys, &url).await.unwrap(), "ok");
+    rejected.assert();
+    served.assert();
+  }
+
+  #[tokio::test]
+  async fn test_key_header() {
+    let m = mock("GET", "/http/header?q=London")
+      .match_header("key", "secret-key")
+      .with_status(200)
+      .with_body("ok")
+      .create();
+
+    let policy = Policy {
+      key_header: Some("key".to_string()),
+      ..policy(0)
+    };
+    let url = format!(
+      "{}/http/header?key=secret-key&q=London",
+      mockito::server_url()
+    );
+    assert_eq!(fetch(&policy.client(), &policy, &url).await.unwrap(), "ok");
+    m.assert();
+  }
+
+  #[tokio::test]
+  async fn test_errors_redacted() {
+    let policy = policy(0);
+    let result = fetch(
+      &policy.client(),
+      &policy,
+      "http://127.0.0.1:9/?key=secret-key",
+    )
+    .await;
+    match result {
+      Err(Error::Fetch { message }) => {
+        assert!(!message.contains("secret-key"), "{}", message);
+      }
+      result => panic!("unexpected {:?}", result),
+    }
+  }
+
+  #[test]
+  fn test_with_key() {
+    assert_eq!(
+      with_key("http://localhost/current.json?key=&q=London", Some("abc")),
+      "http://localhost/current.json?key=abc&q=London"
+    );
+    assert_eq!(
+      with_key("http://localhost/current.json?key=abc&q=London", None),
+      "http://localhost/current.json?q=London"
+    );
+    assert_eq!(
+      with_key("http://localhost/current.json?key=abc", None),
+      "http://localhost/current.json"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_with_fetched() {
+    let stored = Utc.with_ymd_and_hms(2024, 12, 28, 12, 0, 0).unwrap();
+    let ((), oldest) = with_fetched(async {
+      fetched(stored + chrono::Duration::minutes(5));
+      fetched(stored);
+    })
+    .await;
+    assert_eq!(oldest, Some(stored));
+    assert_eq!(with_fetched(async {}).await.1, None);
+  }
+
+  #[test]
+  fn test_backoff() {
+    let policy = policy(5);
+    for attempt in 0..5 {
+      let full = Duration::from_millis(10 * 2u64.pow(attempt));
+      let delay = policy.backoff(attempt);
+      assert!(delay >= full / 2 && delay <= full, "{:?}", delay);
+    }
+    assert!(policy.backoff(20) <= policy.max_delay);
+  }
+
+  #[test]
+  fn test_parse_retry_after() {
+    let now = Utc.with_ymd_and_hms(2024, 12, 28, 12, 0, 0).unwrap();
+    assert_eq!(
+      parse_retry_after("120", now),
+      Some(Duration::from_secs(120))
+    );
+    assert_eq!(
+      parse_retry_after("Sat, 28 Dec 2024 12:01:30 GMT", now),
+      Some(Duration::from_secs(90))
+    );
+    assert_eq!(
+      parse_retry_after("Sat, 28 Dec 2024 11:00:00 GMT", now),
+      Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon", now), None);
+  }
+}
@@ -0,0 +1,335 @@
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+
+use crate::{
+  alert::{Alert, Alerts},
+  fingerprint,
+  forecast::{Forecast, ForecastDay},
+  location::Location,
+};
+
+/// Longest line allowed by RFC 5545 before it must be folded, in octets.
+const LINE_LENGTH: usize = 75;
+
+/// An event of the calendar.
+/// Events without an end are instants, events with only dates last whole days.
+#[derive(Debug, Clone, PartialEq)]
+struct Event {
+  uid: String,
+  start: Time,
+  end: Option<Time>,
+  summary: String,
+  description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Time {
+  /// A whole day.
+  Date(NaiveDate),
+  /// A local time at the location, written without a time zone.
+  Floating(NaiveDate, NaiveTime),
+  Utc(DateTime<Utc>),
+}
+
+/// Render the forecast and alerts of a location as an iCalendar document.
+/// Each forecast day becomes an all-day event, with sunrise, sunset and moon phase events,
+/// and each alert spans from when it is effective until it expires.
+/// Event ids only depend on the location and what they describe, so calendars
+/// subscribing to the export update events in place instead of duplicating them.
+pub fn calendar(
+  location: &Location,
+  forecast: &Forecast,
+  alerts: &Alerts,
+  now: DateTime<Utc>,
+) -> String {
+  let place = location.to_string();
+
+  let mut events = Vec::new();
+  for day in &forecast.days {
+    events.extend(day_events(&place, day));
+  }
+  events.extend(alerts.alerts.iter().filter_map(alert_event));
+
+  let mut lines = vec![
+    "BEGIN:VCALENDAR".to_string(),
+    "VERSION:2.0".to_string(),
+    "PRODID:-//weather//weather-core//EN".to_string(),
+    "CALSCALE:GREGORIAN".to_string(),
+    format!("X-WR-CALNAME:{}", escape(&format!("Weather {}", place))),
+  ];
+  for event in events {
+    lines.push("BEGIN:VEVENT".to_string());
+    lines.push(format!("UID:{}", event.uid));
+    lines.push(format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")));
+    lines.push(format!("DTSTART{}", event.start.to_string()));
+    if let Some(end) = event.end {
+      lines.push(format!("DTEND{}", end.to_string()));
+    }
+    lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+    if !event.description.is_empty() {
+      lines.push(format!("DESCRIPTION:{}", escape(&event.description)));
+    }
+    lines.push("END:VEVENT".to_string());
+  }
+  lines.push("END:VCALENDAR".to_string());
+
+  lines.iter().map(|line| fold(line)).collect()
+}
+
+fn day_events(place: &str, day: &ForecastDay) -> Vec<Event> {
+  let Ok(date) = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") else {
+    return Vec::new();
+  };
+  let uid = |kind: &str| format!("{}@weather", fingerprint(&[kind, place, &day.date]));
+  let whole_day = |summary: String, description: String, kind: &str| Event {
+    uid: uid(kind),
+    start: Time::Date(date),
+    end: date.checked_add_days(Days::new(1)).map(Time::Date),
+    summary,
+    description,
+  };
+
+  let mut events = vec![whole_day(
+    format!(
+      "{} {:.0}°/{:.0}°",
+      day.condition.text, day.max_temperature, day.min_temperature
+    ),
+    format!(
+      "High {:.1}°C, low {:.1}°C\nChance of rain {}%, chance of snow {}%\nPrecipitation {:.1} mm\nMax wind {:.1} km/h\nUV {:.1}",
+      day.max_temperature,
+      day.min_temperature,
+      day.chance_of_rain,
+      day.chance_of_snow,
+      day.total_precipitation,
+      day.max_wind_speed,
+      day.uv
+    ),
+    "day",
+  )];
+
+  let astronomy = &day.astronomy;
+  for (kind, summary, time) in [
+    ("sunrise", "Sunrise", &astronomy.sunrise),
+    ("sunset", "Sunset", &astronomy.sunset),
+  ] {
+    // Polar days and nights have no sunrise or sunset, given as text instead of a time.
+    if let Ok(time) = NaiveTime::parse_from_str(time, "%I:%M %p") {
+      events.push(Event {
+        uid: uid(kind),
+        start: Time::Floating(date, time),
+        end: None,
+        summary: summary.to_string(),
+        description: String::new(),
+      });
+    }
+  }
+
+  events.push(whole_day(
+    format!(
+      "Moon: {} ({}%)",
+      astronomy.moon_phase, astronomy.moon_illumination
+    ),
+    format!(
+      "Moonrise {}, moonset {}",
+      astronomy.moonrise, astronomy.moonset
+    ),
+    "moon",
+  ));
+
+  events
+}
+
+/// Alerts without a valid effective time cannot be placed in the calendar and are skipped.
+fn alert_event(alert: &Alert) -> Option<Event> {
+  let start = alert.effective_at()?;
+  let description = [&alert.description, &alert.instruction, &alert.areas]
+    .into_iter()
+    .filter(|text| !text.is_empty())
+    .cloned()
+    .collect::<Vec<_>>()
+    .join("\n\n");
+
+  Some(Event {
+    uid: format!("{}@weather", alert.id()),
+    start: Time::Utc(start),
+    end: alert.expires_at().map(Time::Utc),
+    summary: format!("{}: {}", alert.severity, alert.headline),
+    description,
+  })
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Time {
+  /// Parameters and value of a date-time property, following the property name.
+  fn to_string(&self) -> String {
+    match self {
+      Time::Date(date) => format!(";VALUE=DATE:{}", date.format("%Y%m%d")),
+      Time::Floating(date, time) => format!(":{}T{}", date.format("%Y%m%d"), time.format("%H%M%S")),
+      Time::Utc(time) => format!(":{}", time.format("%Y%m%dT%H%M%SZ")),
+    }
+  }
+}
+
+/// Escape text values as required by RFC 5545.
+fn escape(text: &str) -> String {
+  text
+    .replace('\\', "\\\\")
+    .replace(';', "\\;")
+    .replace(',', "\\,")
+    .replace("\r\n", "\\n")
+    .replace('\n', "\\n")
+}
+
+/// Fold a content line into lines of at most 75 octets, ending each with CRLF.
+/// Continuation lines start with a space, and multi-byte characters are never split.
+fn fold(line: &str) -> String {
+  let mut folded = String::new();
+  let mut length = 0;
+  for c in line.chars() {
+    if length + c.len_utf8() > LINE_LENGTH {
+      folded.push_str("\r\n ");
+      length = 1;
+    }
+    folded.push(c);
+    length += c.len_utf8();
+  }
+  folded.push_str("\r\n");
+  folded
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{astronomy::Astronomy, weather::Condition};
+  use chrono::TimeZone;
+
+  fn location() -> Location {
+    Location {
+      country: "United Kingdom".to_string(),
+      region: Some("City of London, Greater London".to_string()),
+      city: Some("London".to_string()),
+      lat: Some(51.5171),
+      lon: Some(-0.1062),
+    }
+  }
+
+  fn forecast() -> Forecast {
+    Forecast {
+      days: vec![ForecastDay {
+        date: "2024-12-28".to_string(),
+        max_temperature: 7.2,
+        min_temperature: 4.1,
+        avg_temperature: 5.6,
+        max_wind_speed: 10.1,
+        total_precipitation: 0.2,
+        avg_humidity: 91.0,
+        chance_of_rain: 40,
+        chance_of_snow: 0,
+        condition: Condition::new("Overcast".to_string(), String::new(), 1009),
+        uv: 0.1,
+        astronomy: Astronomy::new(
+          "08:06 AM".to_string(),
+          "03:58 PM".to_string(),
+          "06:11 AM".to_string(),
+          "No moonset".to_string(),
+          "Waning Crescent".to_string(),
+          9,
+          0,
+          0,
+        ),
+        hours: Vec::new(),
+      }],
+    }
+  }
+
+  fn alerts() -> Alerts {
+    Alerts::new(vec![Alert {
+      headline: "Yellow warning, fog".to_string(),
+      description: "Dense fog is expected.".to_string(),
+      severity: "Moderate".to_string(),
+      urgency: "Expected".to_string(),
+      event: "Fog".to_string(),
+      effective: "2024-12-28T06:00:00+00:00".to_string(),
+      expires: "2024-12-28T12:00:00+00:00".to_string(),
+      instruction: "Drive carefully.".to_string(),
+      ..Alert::sample()
+    }])
+  }
+
+  fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 12, 27, 18, 0, 0).unwrap()
+  }
+
+  #[test]
+  fn test_calendar() {
+    let output = calendar(&location(), &forecast(), &alerts(), now());
+
+    assert!(output.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(output.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(output.matches("BEGIN:VEVENT").count(), 5);
+    assert!(output.contains("DTSTAMP:20241227T180000Z\r\n"));
+
+    assert!(output.contains(
+      "DTSTART;VALUE=DATE:20241228\r\nDTEND;VALUE=DATE:20241229\r\nSUMMARY:Overcast 7°/4°\r\n"
+    ));
+    assert!(output.contains("DTSTART:20241228T080600\r\nSUMMARY:Sunrise\r\n"));
+    assert!(output.contains("DTSTART:20241228T155800\r\nSUMMARY:Sunset\r\n"));
+    assert!(output.contains("SUMMARY:Moon: Waning Crescent (9%)\r\n"));
+    assert!(output.contains(
+      "DTSTART:20241228T060000Z\r\nDTEND:20241228T120000Z\r\nSUMMARY:Moderate: Yellow warning\\, fog\r\n"
+    ));
+    assert!(output.contains(&format!("UID:{}@weather\r\n", alerts().alerts[0].id())));
+  }
+
+  #[test]
+  fn test_calendar_stable_uids() {
+    let uids = |output: String| -> Vec<String> {
+      output
+        .lines()
+        .filter(|line| line.starts_with("UID:"))
+        .map(str::to_string)
+        .collect()
+    };
+
+    let first = uids(calendar(&location(), &forecast(), &alerts(), now()));
+    let later = uids(calendar(
+      &location(),
+      &forecast(),
+      &alerts(),
+      now() + chrono::Duration::hours(6),
+    ));
+    assert_eq!(first, later);
+
+    let mut unique = first.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), first.len());
+  }
+
+  #[test]
+  fn test_calendar_skips_missing_times() {
+    let mut forecast = forecast();
+    forecast.days[0].astronomy.sunrise = "No sunrise".to_string();
+    let mut alerts = alerts();
+    alerts.alerts[0].effective = String::new();
+
+    let output = calendar(&location(), &forecast, &alerts, now());
+    assert_eq!(output.matches("BEGIN:VEVENT").count(), 3);
+    assert!(!output.contains("Sunrise"));
+  }
+
+  #[test]
+  fn test_escape() {
+    assert_eq!(escape("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+  }
+
+  #[test]
+  fn test_fold() {
+    let line = format!("DESCRIPTION:{}", "é".repeat(40));
+    let folded = fold(&line);
+    assert!(folded.ends_with("\r\n"));
+    for part in folded.trim_end().split("\r\n") {
+      assert!(part.len() <= LINE_LENGTH);
+    }
+    assert_eq!(folded.replace("\r\n ", "").trim_end(), line);
+  }
+}
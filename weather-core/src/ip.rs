@@ -1,7 +1,12 @@
-use reqwest;
 use serde::Deserialize;
 use std::env;
 
+use crate::{
+  error::Error,
+  fake,
+  http::{self, Provider},
+};
+
 #[derive(Debug, Deserialize)]
 struct Response {
   pub status: Status,
@@ -40,6 +45,7 @@ pub struct IP {
 }
 
 impl IP {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     status: Status,
     country: String,
@@ -86,68 +92,69 @@ impl From<Response> for IP {
 
 /// Get the location from a given IP address.
 /// IP given as string.
-/// Returns a Result with the IP struct or a reqwest error.
-pub async fn get_location(ip: String) -> Result<IP, reqwest::Error> {
+/// Returns a Result with the IP struct or an error.
+/// Requests are timed out, retried and traced like provider requests.
+/// With the fake provider, every IP is in a made up town.
+pub async fn get_location(ip: String) -> Result<IP, Error> {
+  if http::provider() == Provider::Fake {
+    return Ok(fake_location());
+  }
   let address = env::var("IP_LOCATION_API").unwrap();
   let url = format!("{}/{}", address, ip);
-  let response = reqwest::get(&url).await?;
-  let location: Response = response.json::<Response>().await?;
+  let location: Response = http::get_json(&url).await?;
   Ok(IP::from(location))
 }
 
 /// Get the public IP address of the current machine.
-/// Returns a Result with the IP address or a reqwest error.
-pub async fn get_public_ip() -> Result<String, reqwest::Error> {
+/// Returns a Result with the IP address or an error.
+/// With the fake provider, an address reserved for documentation.
+pub async fn get_public_ip() -> Result<String, Error> {
+  if http::provider() == Provider::Fake {
+    return Ok(fake::IP.to_string());
+  }
   #[derive(Deserialize)]
   struct Response {
     ip: String,
   }
 
   let address = env::var("PUBLIC_IP_API").unwrap();
-  let response = reqwest::get(address).await?;
-  let ip: Response = response.json().await?;
+  let ip: Response = http::get_json(&address).await?;
   Ok(ip.ip)
 }
 
+fn fake_location() -> IP {
+  let place = fake::place(fake::TOWN);
+  IP::new(
+    Status::Success,
+    place.country,
+    "XX".to_string(),
+    "XX".to_string(),
+    place.region,
+    place.name,
+    "00000".to_string(),
+    place.lat,
+    place.lon,
+    "Fake ISP".to_string(),
+  )
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::replay::{self, fixtures};
   use mockito::mock;
   use tokio;
 
   fn setup() {
     dotenv::dotenv().ok();
-    std::env::set_var("IP_LOCATION_API", &mockito::server_url());
-    std::env::set_var("PUBLIC_IP_API", &mockito::server_url());
+    std::env::set_var("IP_LOCATION_API", mockito::server_url());
+    std::env::set_var("PUBLIC_IP_API", mockito::server_url());
   }
 
   #[tokio::test]
   async fn test_get_location_success() {
     setup();
-    let _m = mock("GET", "/8.8.8.8")
-      .with_status(200)
-      .with_header("content-type", "application/json")
-      .with_body(
-        r#"{
-          "query": "8.8.8.8",
-          "status": "success",
-          "country": "United States",
-          "countryCode": "US",
-          "region": "VA",
-          "regionName": "Virginia",
-          "city": "Ashburn",
-          "zip": "20149",
-          "lat": 39.03,
-          "lon": -77.5,
-          "timezone": "America/New_York",
-          "isp": "Google LLC",
-          "org": "Google Public DNS",
-          "as": "AS15169 Google LLC"
-        }"#,
-      )
-      .create();
-
-    let result = get_location("8.8.8.8".to_string()).await;
+    let result = replay::scope(fixtures("ip"), get_location("8.8.8.8".to_string())).await;
     assert!(result.is_ok());
 
     let location = result.unwrap();
@@ -163,50 +170,46 @@ mod tests {
     assert_eq!(location.isp, "Google LLC");
   }
 
+  #[tokio::test]
+  async fn test_get_location_status_fail() {
+    setup();
+    let result = replay::scope(fixtures("ip"), get_location("1".to_string())).await;
+    assert!(result.is_ok());
+
+    let location = result.unwrap();
+    assert_eq!(location.status, Status::Fail);
+  }
+
   #[tokio::test]
   async fn test_get_location_fail() {
     setup();
     let _m = mock("GET", "/1")
       .with_status(500)
       .with_header("content-type", "application/json")
-      .with_body(
-        r#"{
-          "query": "1",
-          "status": "fail",
-          "country": "",
-          "countryCode": "",
-          "region": "",
-          "regionName": "",
-          "city": "",
-          "zip": "",
-          "lat": 0.0,
-          "lon": 0.0,
-          "isp": ""
-        }"#,
-      )
+      .with_body(include_str!("../fixtures/ip/1.json"))
       .create();
 
     let result = get_location("1".to_string()).await;
-    assert!(result.is_ok());
-
-    let location = result.unwrap();
-    assert_eq!(location.status, Status::Fail);
+    assert!(matches!(result, Err(Error::Response { .. })));
   }
 
   #[tokio::test]
   async fn test_get_public_ip_success() {
     setup();
-    let _m = mock("GET", "/")
-      .with_status(200)
-      .with_header("content-type", "application/json")
-      .with_body(r#"{ "ip": "8.8.8.8" }"#)
-      .create();
-
-    let result = get_public_ip().await;
+    let result = replay::scope(fixtures("ip"), get_public_ip()).await;
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), "8.8.8.8");
   }
 
+  #[test]
+  fn test_fake_location() {
+    let location = fake_location();
+    assert_eq!(location.status, Status::Success);
+    assert_eq!(location.country_code, "XX");
+    assert_eq!(location.city, fake::TOWN);
+    assert_eq!(location.lat, fake::place(fake::TOWN).lat);
+  }
+
   #[tokio::test]
   async fn test_get_public_ip_fail() {
     setup();
@@ -0,0 +1,289 @@
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  config::ApiKey,
+  error::Error,
+  quota::{key_id, mask},
+  redact::describe,
+  state::state_dir,
+};
+
+/// Key set in `WEATHER_KEY`.
+/// Empty when not set, for the key ring to fill in when keys are configured instead.
+pub fn env_key() -> String {
+  std::env::var("WEATHER_KEY").unwrap_or_default()
+}
+
+/// Whether an error means the key itself is unusable for now, rather than the request.
+pub fn rejects_key(err: &Error) -> bool {
+  matches!(err, Error::KeyRejected { .. } | Error::QuotaExceeded { .. })
+}
+
+/// Persisted times until which keys are left unused, by key fingerprint so keys are never written to disk.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cooldowns {
+  keys: BTreeMap<String, DateTime<Utc>>,
+}
+
+impl Cooldowns {
+  /// Default location of the cooldown file.
+  pub fn default_path() -> PathBuf {
+    state_dir().join("keys.json")
+  }
+
+  /// Load the cooldowns from a file.
+  /// A missing file is not an error and results in no cooldowns.
+  pub fn load(path: &Path) -> Result<Self, Error> {
+    if !path.exists() {
+      return Ok(Cooldowns::default());
+    }
+
+    let content = fs::read_to_string(path).map_err(|err| io_error(path, err.to_string()))?;
+    serde_json::from_str(&content).map_err(|err| io_error(path, err.to_string()))
+  }
+
+  /// Save the cooldowns to a file, creating any missing parent directories.
+  pub fn save(&self, path: &Path) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|err| io_error(parent, err.to_string()))?;
+    }
+
+    let content =
+      serde_json::to_string_pretty(self).map_err(|err| io_error(path, err.to_string()))?;
+    fs::write(path, content).map_err(|err| io_error(path, err.to_string()))
+  }
+
+  /// End of the cooldown of a key, if it is cooling down at `now`.
+  pub fn until(&self, key: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    self
+      .keys
+      .get(&key_id(key))
+      .copied()
+      .filter(|until| *until > now)
+  }
+
+  /// Leave a key unused until `until`.
+  pub fn start(&mut self, key: &str, until: DateTime<Utc>) {
+    self.keys.insert(key_id(key), until);
+  }
+
+  /// Forget cooldowns that have ended.
+  pub fn prune(&mut self, now: DateTime<Utc>) {
+    self.keys.retain(|_, until| *until > now);
+  }
+}
+
+/// Several API keys that requests are spread over by weight.
+/// A key rejected by the provider, or over its quota, is left unused for a cooldown
+/// and requests fail over to the other keys.
+pub struct KeyRing {
+  keys: Vec<ApiKey>,
+  cooldown: Duration,
+  path: PathBuf,
+  warn: fn(&str),
+}
+
+impl KeyRing {
+  /// `warn` is called when a key starts a cooldown.
+  pub fn new(keys: &[ApiKey], cooldown: Duration, path: PathBuf, warn: fn(&str)) -> Self {
+    KeyRing {
+      keys: keys.to_vec(),
+      cooldown,
+      path,
+      warn,
+    }
+  }
+
+  /// Keys not cooling down, in the order to try them.
+  /// Each key comes first in proportion to its weight, `random` gives numbers in `[0, 1)`.
+  /// Keys with a weight of zero are only tried after all others.
+  pub fn order(
+    &self,
+    now: DateTime<Utc>,
+    random: &mut dyn FnMut() -> f64,
+  ) -> Result<Vec<&ApiKey>, Error> {
+    let cooldowns = Cooldowns::load(&self.path)?;
+    let mut left: Vec<&ApiKey> = self
+      .keys
+      .iter()
+      .filter(|key| cooldowns.until(&key.key, now).is_none())
+      .collect();
+
+    let mut order = Vec::new();
+    while !left.is_empty() {
+      let total: u32 = left.iter().map(|key| key.weight).sum();
+      let index = match total {
+        0 => 0,
+        total => {
+          let mut pick = random() * total as f64;
+          left
+            .iter()
+            .position(|key| {
+              pick -= key.weight as f64;
+              key.weight > 0 && pick < 0.0
+            })
+            .unwrap_or(left.len() - 1)
+        }
+      };
+      order.push(left.remove(index));
+    }
+    Ok(order)
+  }
+
+  /// Leave a key unused for the cooldown, after it failed with `err`.
+  pub fn exhaust(&self, key: &ApiKey, err: &Error, now: DateTime<Utc>) -> Result<(), Error> {
+    let until = now + chrono::Duration::from_std(self.cooldown).unwrap_or_default();
+    let mut cooldowns = Cooldowns::load(&self.path)?;
+    cooldowns.prune(now);
+    cooldowns.start(&key.key, until);
+    cooldowns.save(&self.path)?;
+
+    (self.warn)(&format!(
+      "{} failed with {}, not using it until {}",
+      label(key),
+      describe(err),
+      until.to_rfc3339()
+    ));
+    Ok(())
+  }
+
+  /// Trace the key that served the current request.
+  pub fn served(&self, key: &ApiKey) {
+    tracing::info!(key = %label(key), "served with key");
+  }
+
+  /// A line for each configured key, saying whether it is in use or cooling down.
+  pub fn status(&self, now: DateTime<Utc>) -> Result<Vec<String>, Error> {
+    let cooldowns = Cooldowns::load(&self.path)?;
+    Ok(
+      self
+        .keys
+        .iter()
+        .map(|key| match cooldowns.until(&key.key, now) {
+          Some(until) => format!("{}: cooling down until {}", label(key), until.to_rfc3339()),
+          None => format!("{}: in use, weight {}", label(key), key.weight),
+        })
+        .collect(),
+    )
+  }
+}
+
+/// A key as shown to users: its name if it has one, and never more than the start of the key.
+pub fn label(key: &ApiKey) -> String {
+  match &key.name {
+    Some(name) => format!("{} ({})", mask(&key.key), name),
+    None => mask(&key.key),
+  }
+}
+
+fn io_error(path: &Path, message: String) -> Error {
+  Error::Io {
+    path: path.display().to_string(),
+    message,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  fn key(key: &str, weight: u32) -> ApiKey {
+    ApiKey {
+      key: key.to_string(),
+      weight,
+      name: None,
+    }
+  }
+
+  fn ring(path: PathBuf) -> KeyRing {
+    KeyRing::new(
+      &[
+        key("team-api-key", 3),
+        key("tier-api-key", 1),
+        key("spare-api-key", 0),
+      ],
+      Duration::from_secs(3600),
+      path,
+      |_| {},
+    )
+  }
+
+  fn first(ring: &KeyRing, now: DateTime<Utc>, pick: f64) -> String {
+    ring.order(now, &mut || pick).unwrap()[0].key.clone()
+  }
+
+  #[test]
+  fn test_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let ring = ring(dir.path().join("keys.json"));
+    let now = Utc.with_ymd_and_hms(2024, 12, 28, 12, 0, 0).unwrap();
+
+    assert_eq!(first(&ring, now, 0.0), "team-api-key");
+    assert_eq!(first(&ring, now, 0.7), "team-api-key");
+    assert_eq!(first(&ring, now, 0.8), "tier-api-key");
+
+    let order: Vec<&str> = ring
+      .order(now, &mut || 0.9)
+      .unwrap()
+      .iter()
+      .map(|key| key.key.as_str())
+      .collect();
+    assert_eq!(order, vec!["tier-api-key", "team-api-key", "spare-api-key"]);
+  }
+
+  #[test]
+  fn test_exhaust() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("keys.json");
+    let ring = ring(path.clone());
+    let now = Utc.with_ymd_and_hms(2024, 12, 28, 12, 0, 0).unwrap();
+
+    let err = Error::KeyRejected {
+      status: 403,
+      message: "API key has exceeded calls per month quota".to_string(),
+    };
+    ring.exhaust(&ring.keys[0], &err, now).unwrap();
+    assert_eq!(first(&ring, now, 0.0), "tier-api-key");
+    assert_eq!(ring.order(now, &mut || 0.0).unwrap().len(), 2);
+    assert!(!fs::read_to_string(&path).unwrap().contains("team-api-key"));
+
+    let later = now + chrono::Duration::hours(2);
+    assert_eq!(first(&ring, later, 0.0), "team-api-key");
+    assert_eq!(
+      ring.status(now).unwrap(),
+      vec![
+        "team…: cooling down until 2024-12-28T13:00:00+00:00",
+        "tier…: in use, weight 1",
+        "spar…: in use, weight 0"
+      ]
+    );
+  }
+
+  #[test]
+  fn test_label() {
+    let mut named = key("0123456789abcdef", 1);
+    assert_eq!(label(&named), "0123…");
+    named.name = Some("team-a".to_string());
+    assert_eq!(label(&named), "0123… (team-a)");
+  }
+
+  #[test]
+  fn test_rejects_key() {
+    assert!(rejects_key(&Error::QuotaExceeded {
+      period: "daily".to_string(),
+      limit: 10
+    }));
+    assert!(!rejects_key(&Error::Timeout {
+      message: String::new()
+    }));
+  }
+}
@@ -1,11 +1,30 @@
 use error::Error;
 
 pub mod alert;
+pub mod area;
 pub mod astronomy;
+pub mod cache;
+pub mod cap;
+pub mod condition;
 pub mod config;
+pub mod derive;
 pub mod error;
+pub mod fake;
+pub mod feed;
+pub mod forecast;
+pub mod http;
+pub mod ical;
 pub mod ip;
+pub mod keys;
+pub mod locale;
 pub mod location;
+pub mod notify;
+pub mod quota;
+pub mod redact;
+pub mod replay;
+pub mod state;
+pub mod store;
+pub mod template;
 pub mod weather;
 
 /// Query tratif ro generating a query result for a generti type `T`
@@ -16,3 +35,16 @@ pub trait Query<T> {
   /// Otherwise, the query result is returned.
   fn query(&self) -> Result<T, Error>;
 }
+
+/// Generate a stable, hex encoded fingerprint from a list of parts.
+/// Uses 64 bit FNV-1a so the result does not change between builds or platforms.
+pub fn fingerprint(parts: &[&str]) -> String {
+  let mut hash: u64 = 0xcbf29ce484222325;
+  for part in parts {
+    for byte in part.bytes().chain(std::iter::once(0x1f)) {
+      hash ^= byte as u64;
+      hash = hash.wrapping_mul(0x100000001b3);
+    }
+  }
+  format!("{:016x}", hash)
+}
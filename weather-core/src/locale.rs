@@ -0,0 +1,779 @@
+use std::{str::FromStr, sync::RwLock};
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::error::Error;
+
+static LOCALE: RwLock<Option<Locale>> = RwLock::new(None);
+
+/// A language and optional region, such as `de_AT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+  /// Lowercase ISO 639-1 language code.
+  pub language: String,
+  /// Uppercase ISO 3166 region code.
+  pub region: Option<String>,
+}
+
+/// A label in English and each translated language, in the order of `LANGUAGES`.
+/// English labels that mean different things in different places start with a context,
+/// such as `UV|High`, which is left out of the English text.
+struct Translation {
+  english: &'static str,
+  translations: [&'static str; 6],
+}
+
+/// Languages labels are translated into. Other languages use the English labels.
+const LANGUAGES: [&str; 6] = ["de", "fr", "es", "it", "nl", "pt"];
+
+const fn translation(english: &'static str, translations: [&'static str; 6]) -> Translation {
+  Translation {
+    english,
+    translations,
+  }
+}
+
+const LABELS: [Translation; 84] = [
+  translation(
+    "Is Day",
+    ["Tag", "Jour", "Es de día", "Giorno", "Dag", "É dia"],
+  ),
+  translation(
+    "Temperature",
+    [
+      "Temperatur",
+      "Température",
+      "Temperatura",
+      "Temperatura",
+      "Temperatuur",
+      "Temperatura",
+    ],
+  ),
+  translation(
+    "Feels Like",
+    [
+      "Gefühlt",
+      "Ressenti",
+      "Sensación",
+      "Percepita",
+      "Voelt als",
+      "Sensação",
+    ],
+  ),
+  translation(
+    "Heat Index",
+    [
+      "Hitzeindex",
+      "Indice de chaleur",
+      "Índice de calor",
+      "Indice di calore",
+      "Hitte-index",
+      "Índice de calor",
+    ],
+  ),
+  translation(
+    "Condition",
+    [
+      "Wetterlage",
+      "Conditions",
+      "Estado",
+      "Condizioni",
+      "Weertype",
+      "Condição",
+    ],
+  ),
+  translation(
+    "Wind Speed",
+    [
+      "Windgeschwindigkeit",
+      "Vitesse du vent",
+      "Velocidad del viento",
+      "Velocità del vento",
+      "Windsnelheid",
+      "Velocidade do vento",
+    ],
+  ),
+  translation(
+    "Wind Degree",
+    [
+      "Windrichtung (Grad)",
+      "Direction du vent (degrés)",
+      "Dirección del viento (grados)",
+      "Direzione del vento (gradi)",
+      "Windrichting (graden)",
+      "Direção do vento (graus)",
+    ],
+  ),
+  translation(
+    "Wind Direction",
+    [
+      "Windrichtung",
+      "Direction du vent",
+      "Dirección del viento",
+      "Direzione del vento",
+      "Windrichting",
+      "Direção do vento",
+    ],
+  ),
+  translation(
+    "Wind Gust Speed",
+    [
+      "Böen",
+      "Rafales",
+      "Ráfagas",
+      "Raffiche",
+      "Windstoten",
+      "Rajadas",
+    ],
+  ),
+  translation(
+    "Wind Chill",
+    [
+      "Windchill",
+      "Refroidissement éolien",
+      "Sensación por viento",
+      "Wind chill",
+      "Gevoelstemperatuur door wind",
+      "Sensação por vento",
+    ],
+  ),
+  translation(
+    "Pressure",
+    [
+      "Luftdruck",
+      "Pression",
+      "Presión",
+      "Pressione",
+      "Luchtdruk",
+      "Pressão",
+    ],
+  ),
+  translation(
+    "Precipitation",
+    [
+      "Niederschlag",
+      "Précipitations",
+      "Precipitación",
+      "Precipitazioni",
+      "Neerslag",
+      "Precipitação",
+    ],
+  ),
+  translation(
+    "Humidity",
+    [
+      "Luftfeuchtigkeit",
+      "Humidité",
+      "Humedad",
+      "Umidità",
+      "Luchtvochtigheid",
+      "Umidade",
+    ],
+  ),
+  translation(
+    "Cloud",
+    [
+      "Bewölkung",
+      "Nébulosité",
+      "Nubosidad",
+      "Nuvolosità",
+      "Bewolking",
+      "Nebulosidade",
+    ],
+  ),
+  translation("UV", ["UV", "UV", "UV", "UV", "UV", "UV"]),
+  translation(
+    "Visibility",
+    [
+      "Sichtweite",
+      "Visibilité",
+      "Visibilidad",
+      "Visibilità",
+      "Zicht",
+      "Visibilidade",
+    ],
+  ),
+  translation(
+    "Dew Point",
+    [
+      "Taupunkt",
+      "Point de rosée",
+      "Punto de rocío",
+      "Punto di rugiada",
+      "Dauwpunt",
+      "Ponto de orvalho",
+    ],
+  ),
+  translation(
+    "Icon",
+    ["Symbol", "Icône", "Icono", "Icona", "Icoon", "Ícone"],
+  ),
+  translation(
+    "Code",
+    ["Code", "Code", "Código", "Codice", "Code", "Código"],
+  ),
+  translation(
+    "Sunrise",
+    [
+      "Sonnenaufgang",
+      "Lever du soleil",
+      "Salida del sol",
+      "Alba",
+      "Zonsopkomst",
+      "Nascer do sol",
+    ],
+  ),
+  translation(
+    "Sunset",
+    [
+      "Sonnenuntergang",
+      "Coucher du soleil",
+      "Puesta del sol",
+      "Tramonto",
+      "Zonsondergang",
+      "Pôr do sol",
+    ],
+  ),
+  translation(
+    "Moonrise",
+    [
+      "Mondaufgang",
+      "Lever de la lune",
+      "Salida de la luna",
+      "Sorgere della luna",
+      "Maanopkomst",
+      "Nascer da lua",
+    ],
+  ),
+  translation(
+    "Moonset",
+    [
+      "Monduntergang",
+      "Coucher de la lune",
+      "Puesta de la luna",
+      "Tramonto della luna",
+      "Maanondergang",
+      "Pôr da lua",
+    ],
+  ),
+  translation(
+    "Moon Phase",
+    [
+      "Mondphase",
+      "Phase de la lune",
+      "Fase lunar",
+      "Fase lunare",
+      "Maanfase",
+      "Fase da lua",
+    ],
+  ),
+  translation(
+    "Moon Illumination",
+    [
+      "Mondbeleuchtung",
+      "Illumination de la lune",
+      "Iluminación lunar",
+      "Illuminazione lunare",
+      "Maanverlichting",
+      "Iluminação da lua",
+    ],
+  ),
+  translation(
+    "Is Moon Up",
+    [
+      "Mond sichtbar",
+      "Lune levée",
+      "Luna visible",
+      "Luna visibile",
+      "Maan op",
+      "Lua visível",
+    ],
+  ),
+  translation(
+    "Is Sun Up",
+    [
+      "Sonne sichtbar",
+      "Soleil levé",
+      "Sol visible",
+      "Sole visibile",
+      "Zon op",
+      "Sol visível",
+    ],
+  ),
+  translation(
+    "High",
+    ["Höchstwert", "Max", "Máxima", "Massima", "Max", "Máxima"],
+  ),
+  translation(
+    "Low",
+    ["Tiefstwert", "Min", "Mínima", "Minima", "Min", "Mínima"],
+  ),
+  translation(
+    "Chance of Rain",
+    [
+      "Regenwahrscheinlichkeit",
+      "Risque de pluie",
+      "Probabilidad de lluvia",
+      "Probabilità di pioggia",
+      "Kans op regen",
+      "Probabilidade de chuva",
+    ],
+  ),
+  translation(
+    "Headline",
+    ["Überschrift", "Titre", "Titular", "Titolo", "Kop", "Título"],
+  ),
+  translation("Type", ["Typ", "Type", "Tipo", "Tipo", "Type", "Tipo"]),
+  translation(
+    "Description",
+    [
+      "Beschreibung",
+      "Description",
+      "Descripción",
+      "Descrizione",
+      "Beschrijving",
+      "Descrição",
+    ],
+  ),
+  translation(
+    "Severity",
+    [
+      "Schweregrad",
+      "Gravité",
+      "Gravedad",
+      "Gravità",
+      "Ernst",
+      "Gravidade",
+    ],
+  ),
+  translation(
+    "Urgency",
+    [
+      "Dringlichkeit",
+      "Urgence",
+      "Urgencia",
+      "Urgenza",
+      "Urgentie",
+      "Urgência",
+    ],
+  ),
+  translation(
+    "Areas",
+    ["Gebiete", "Zones", "Zonas", "Zone", "Gebieden", "Áreas"],
+  ),
+  translation(
+    "Category",
+    [
+      "Kategorie",
+      "Catégorie",
+      "Categoría",
+      "Categoria",
+      "Categorie",
+      "Categoria",
+    ],
+  ),
+  translation(
+    "Certainty",
+    [
+      "Gewissheit",
+      "Certitude",
+      "Certeza",
+      "Certezza",
+      "Zekerheid",
+      "Certeza",
+    ],
+  ),
+  translation(
+    "Event",
+    [
+      "Ereignis",
+      "Événement",
+      "Evento",
+      "Evento",
+      "Gebeurtenis",
+      "Evento",
+    ],
+  ),
+  translation(
+    "Note",
+    ["Hinweis", "Note", "Nota", "Nota", "Opmerking", "Nota"],
+  ),
+  translation(
+    "Effective",
+    [
+      "Gültig ab",
+      "En vigueur",
+      "Vigente desde",
+      "Valido dal",
+      "Geldig vanaf",
+      "Em vigor",
+    ],
+  ),
+  translation(
+    "Expires",
+    [
+      "Gültig bis",
+      "Expire",
+      "Expira",
+      "Scade",
+      "Verloopt",
+      "Expira",
+    ],
+  ),
+  translation(
+    "Instruction",
+    [
+      "Anweisung",
+      "Consigne",
+      "Instrucción",
+      "Istruzioni",
+      "Instructie",
+      "Instrução",
+    ],
+  ),
+  translation(
+    "Beaufort",
+    [
+      "Beaufort", "Beaufort", "Beaufort", "Beaufort", "Beaufort", "Beaufort",
+    ],
+  ),
+  translation(
+    "UV Category",
+    [
+      "UV-Kategorie",
+      "Catégorie UV",
+      "Categoría UV",
+      "Categoria UV",
+      "UV-categorie",
+      "Categoria UV",
+    ],
+  ),
+  translation(
+    "Humidex",
+    [
+      "Humidex", "Humidex", "Humidex", "Humidex", "Humidex", "Humidex",
+    ],
+  ),
+  translation(
+    "Wet Bulb",
+    [
+      "Feuchtkugeltemperatur",
+      "Température humide",
+      "Bulbo húmedo",
+      "Bulbo umido",
+      "Natteboltemperatuur",
+      "Bulbo úmido",
+    ],
+  ),
+  translation(
+    "Apparent Temperature",
+    [
+      "Scheinbare Temperatur",
+      "Température apparente",
+      "Temperatura aparente",
+      "Temperatura apparente",
+      "Schijnbare temperatuur",
+      "Temperatura aparente",
+    ],
+  ),
+  translation(
+    "Absolute Humidity",
+    [
+      "Absolute Feuchte",
+      "Humidité absolue",
+      "Humedad absoluta",
+      "Umidità assoluta",
+      "Absolute vochtigheid",
+      "Umidade absoluta",
+    ],
+  ),
+  translation(
+    "Cloud Base",
+    [
+      "Wolkenuntergrenze",
+      "Base des nuages",
+      "Base de las nubes",
+      "Base delle nubi",
+      "Wolkenbasis",
+      "Base das nuvens",
+    ],
+  ),
+  translation(
+    "Comfort",
+    [
+      "Komfort", "Confort", "Confort", "Comfort", "Comfort", "Conforto",
+    ],
+  ),  translation("Wind", ["Wind", "Vent", "Viento", "Vento", "Wind", "Vento"]),
+  translation("Gust", ["Böen", "Rafales", "Ráfagas", "Raffiche", "Windstoten", "Rajadas"]),
+  translation("true", ["ja", "oui", "sí", "sì", "ja", "sim"]),
+  translation("false", ["nein", "non", "no", "no", "nee", "não"]),
+  translation("Calm", ["Windstille", "Calme", "Calma", "Calma", "Windstil", "Calmaria"]),
+  translation("Light air", ["Leiser Zug", "Très légère brise", "Ventolina", "Bava di vento", "Flauw en stil", "Aragem"]),
+  translation("Light breeze", ["Leichte Brise", "Légère brise", "Flojito", "Brezza leggera", "Flauwe koelte", "Brisa leve"]),
+  translation("Gentle breeze", ["Schwache Brise", "Petite brise", "Flojo", "Brezza tesa", "Lichte koelte", "Brisa fraca"]),
+  translation("Moderate breeze", ["Mäßige Brise", "Jolie brise", "Bonancible", "Vento moderato", "Matige wind", "Brisa moderada"]),
+  translation("Fresh breeze", ["Frische Brise", "Bonne brise", "Fresquito", "Vento teso", "Vrij krachtige wind", "Brisa forte"]),
+  translation("Strong breeze", ["Starker Wind", "Vent frais", "Fresco", "Vento fresco", "Krachtige wind", "Vento fresco"]),
+  translation("Near gale", ["Steifer Wind", "Grand frais", "Frescachón", "Vento forte", "Harde wind", "Vento forte"]),
+  translation("Gale", ["Stürmischer Wind", "Coup de vent", "Temporal", "Burrasca", "Stormachtige wind", "Ventania"]),
+  translation("Strong gale", ["Sturm", "Fort coup de vent", "Temporal fuerte", "Burrasca forte", "Storm", "Ventania forte"]),
+  translation("Storm", ["Schwerer Sturm", "Tempête", "Temporal duro", "Tempesta", "Zware storm", "Tempestade"]),
+  translation("Violent storm", ["Orkanartiger Sturm", "Violente tempête", "Temporal muy duro", "Fortunale", "Zeer zware storm", "Tempestade violenta"]),
+  translation("Hurricane force", ["Orkan", "Ouragan", "Temporal huracanado", "Uragano", "Orkaan", "Furacão"]),
+  translation("UV|Low", ["Niedrig", "Faible", "Bajo", "Basso", "Laag", "Baixo"]),
+  translation("UV|Moderate", ["Mäßig", "Modéré", "Moderado", "Moderato", "Matig", "Moderado"]),
+  translation("UV|High", ["Hoch", "Élevé", "Alto", "Alto", "Hoog", "Alto"]),
+  translation("UV|Very high", ["Sehr hoch", "Très élevé", "Muy alto", "Molto alto", "Zeer hoog", "Muito alto"]),
+  translation("UV|Extreme", ["Extrem", "Extrême", "Extremo", "Estremo", "Extreem", "Extremo"]),
+  translation("No protection needed.", ["Kein Schutz erforderlich.", "Aucune protection nécessaire.", "No se necesita protección.", "Nessuna protezione necessaria.", "Geen bescherming nodig.", "Não é necessária proteção."]),
+  translation("Seek shade around midday, wear a hat and use sunscreen.", ["Mittags Schatten suchen, einen Hut tragen und Sonnenschutz verwenden.", "Recherchez l'ombre vers midi, portez un chapeau et utilisez de la crème solaire.", "Busque la sombra al mediodía, use sombrero y protector solar.", "Cerca l'ombra a mezzogiorno, indossa un cappello e usa la crema solare.", "Zoek rond het middaguur de schaduw op, draag een hoed en gebruik zonnebrandcrème.", "Procure sombra ao meio-dia, use chapéu e protetor solar."]),
+  translation("Reduce time in the sun between 11am and 3pm, cover up and use sunscreen.", ["Zwischen 11 und 15 Uhr weniger Zeit in der Sonne verbringen, die Haut bedecken und Sonnenschutz verwenden.", "Limitez l'exposition au soleil entre 11 h et 15 h, couvrez-vous et utilisez de la crème solaire.", "Reduzca el tiempo al sol entre las 11 y las 15 h, cúbrase y use protector solar.", "Riduci il tempo al sole tra le 11 e le 15, copriti e usa la crema solare.", "Beperk de tijd in de zon tussen 11 en 15 uur, bedek je huid en gebruik zonnebrandcrème.", "Reduza o tempo ao sol entre as 11h e as 15h, cubra-se e use protetor solar."]),
+  translation("Avoid the sun between 11am and 3pm, shade, clothing and sunscreen are essential.", ["Die Sonne zwischen 11 und 15 Uhr meiden, Schatten, Kleidung und Sonnenschutz sind unerlässlich.", "Évitez le soleil entre 11 h et 15 h, l'ombre, les vêtements et la crème solaire sont indispensables.", "Evite el sol entre las 11 y las 15 h, la sombra, la ropa y el protector solar son imprescindibles.", "Evita il sole tra le 11 e le 15, ombra, indumenti e crema solare sono indispensabili.", "Vermijd de zon tussen 11 en 15 uur, schaduw, kleding en zonnebrandcrème zijn onmisbaar.", "Evite o sol entre as 11h e as 15h, sombra, roupa e protetor solar são essenciais."]),
+  translation("Avoid being outside during midday hours, unprotected skin burns in minutes.", ["Mittags nicht draußen aufhalten, ungeschützte Haut verbrennt in Minuten.", "Évitez de sortir aux heures de midi, la peau non protégée brûle en quelques minutes.", "Evite estar al aire libre al mediodía, la piel sin protección se quema en minutos.", "Evita di stare all'aperto nelle ore centrali, la pelle non protetta si scotta in pochi minuti.", "Blijf rond het middaguur binnen, onbeschermde huid verbrandt binnen enkele minuten.", "Evite estar ao ar livre ao meio-dia, a pele desprotegida queima em minutos."]),
+  translation("Dry", ["Trocken", "Sec", "Seco", "Secco", "Droog", "Seco"]),
+  translation("Comfortable", ["Angenehm", "Confortable", "Confortable", "Confortevole", "Aangenaam", "Confortável"]),
+  translation("Humid", ["Feucht", "Humide", "Húmedo", "Umido", "Vochtig", "Úmido"]),
+  translation("Muggy", ["Schwül", "Lourd", "Bochornoso", "Afoso", "Benauwd", "Abafado"]),
+  translation("Oppressive", ["Drückend", "Étouffant", "Sofocante", "Opprimente", "Drukkend", "Opressivo"]),
+  translation("Miserable", ["Unerträglich", "Insupportable", "Insoportable", "Insopportabile", "Ondraaglijk", "Insuportável"]),
+];
+
+/// Languages that write decimal numbers with a comma.
+const DECIMAL_COMMA: [&str; 20] = [
+  "bg", "cs", "da", "de", "el", "es", "fi", "fr", "hu", "id", "it", "nl", "pl", "pt", "ro", "ru",
+  "sk", "sr", "sv", "uk",
+];
+
+impl Default for Locale {
+  fn default() -> Self {
+    Locale {
+      language: "en".to_string(),
+      region: None,
+    }
+  }
+}
+
+impl FromStr for Locale {
+  type Err = Error;
+
+  /// Parse a POSIX locale such as `de_AT.UTF-8`, or a language tag such as `pt-BR`.
+  /// The `C` and `POSIX` locales are English.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let name = s.split(['.', '@']).next().unwrap_or_default().trim();
+    if name.is_empty() || name == "C" || name == "POSIX" {
+      return Ok(Locale::default());
+    }
+
+    let mut parts = name.split(['_', '-']);
+    let language = parts.next().unwrap_or_default().to_lowercase();
+    let region = parts.next().map(str::to_uppercase);
+    let valid = (2..=3).contains(&language.len())
+      && language.chars().all(|c| c.is_ascii_alphabetic())
+      && parts.next().is_none();
+    match valid {
+      true => Ok(Locale { language, region }),
+      false => Err(Error::InvalidArgument {
+        arg: s.to_string(),
+        message: "Language should be a language code such as de or pt_BR.".to_string(),
+      }),
+    }
+  }
+}
+
+impl Locale {
+  /// Locale of the environment, from `LC_ALL`, `LC_MESSAGES` or `LANG`.
+  /// English when none are set or the first set is not a valid locale.
+  pub fn from_env() -> Self {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+      .iter()
+      .filter_map(|name| std::env::var(name).ok())
+      .find(|value| !value.is_empty())
+      .and_then(|value| value.parse().ok())
+      .unwrap_or_default()
+  }
+
+  /// Value of the provider `lang` parameter, or `None` for English, the provider default.
+  pub fn provider_language(&self) -> Option<String> {
+    match (self.language.as_str(), self.region.as_deref()) {
+      ("en", _) => None,
+      ("zh", Some("TW" | "HK")) => Some("zh_tw".to_string()),
+      (language, _) => Some(language.to_string()),
+    }
+  }
+
+  /// Query string parameter selecting the provider language, empty for English.
+  pub fn query(&self) -> String {
+    match self.provider_language() {
+      Some(language) => format!("&lang={}", language),
+      None => String::new(),
+    }
+  }
+
+  /// Translate an English label, falling back to English when there is no translation.
+  pub fn label(&self, english: &'static str) -> &'static str {
+    let text = english.split_once('|').map_or(english, |(_, text)| text);
+    let Some(index) = LANGUAGES
+      .iter()
+      .position(|language| *language == self.language)
+    else {
+      return text;
+    };
+    LABELS
+      .iter()
+      .find(|label| label.english == english)
+      .map_or(text, |label| label.translations[index])
+  }
+
+  /// Translate `true` or `false`.
+  pub fn boolean(&self, value: bool) -> &'static str {
+    self.label(match value {
+      true => "true",
+      false => "false",
+    })
+  }
+
+  pub fn decimal_separator(&self) -> char {
+    match DECIMAL_COMMA.contains(&self.language.as_str()) {
+      true => ',',
+      false => '.',
+    }
+  }
+
+  /// Format a number as Rust would, with the decimal separator of the locale.
+  pub fn number(&self, number: f64) -> String {
+    self.separate(number.to_string())
+  }
+
+  /// Format a number with a fixed number of decimal places.
+  pub fn fixed(&self, number: f64, precision: usize) -> String {
+    self.separate(format!("{:.*}", precision, number))
+  }
+
+  fn separate(&self, number: String) -> String {
+    match self.decimal_separator() {
+      '.' => number,
+      separator => number.replace('.', &separator.to_string()),
+    }
+  }
+
+  /// Format a `YYYY-MM-DD` date in the order used by the locale.
+  /// English without a region keeps the ISO 8601 form, as do dates that do not parse.
+  pub fn date(&self, date: &str) -> String {
+    let Ok(parsed) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+      return date.to_string();
+    };
+    let format = match (self.language.as_str(), self.region.as_deref()) {
+      ("en", None) => "%Y-%m-%d",
+      ("en", Some("US")) => "%m/%d/%Y",
+      ("zh" | "ja" | "ko", _) => "%Y/%m/%d",
+      ("nl", _) => "%d-%m-%Y",
+      ("de" | "cs" | "fi" | "pl" | "ru" | "sk" | "uk", _) => "%d.%m.%Y",
+      ("sv" | "lt", _) => "%Y-%m-%d",
+      _ => "%d/%m/%Y",
+    };
+    parsed.format(format).to_string()
+  }
+
+  /// Format a provider time such as `08:06 PM` on the clock used by the locale.
+  /// English keeps the twelve hour clock, other languages use the twenty four hour clock.
+  pub fn time(&self, time: &str) -> String {
+    match (
+      self.language.as_str(),
+      NaiveTime::parse_from_str(time, "%I:%M %p"),
+    ) {
+      ("en", _) | (_, Err(_)) => time.to_string(),
+      (_, Ok(parsed)) => parsed.format("%H:%M").to_string(),
+    }
+  }
+}
+
+/// Set the locale used for labels, numbers, dates and provider requests.
+pub fn set_locale(locale: Locale) {
+  *LOCALE.write().unwrap() = Some(locale);
+}
+
+/// The locale set with `set_locale`, English if none was set.
+pub fn current() -> Locale {
+  LOCALE.read().unwrap().clone().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn locale(name: &str) -> Locale {
+    name.parse().unwrap()
+  }
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(
+      locale("de_AT.UTF-8@euro"),
+      Locale {
+        language: "de".to_string(),
+        region: Some("AT".to_string())
+      }
+    );
+    assert_eq!(locale("pt-br").region.as_deref(), Some("BR"));
+    assert_eq!(locale("C.UTF-8"), Locale::default());
+    assert_eq!(locale(""), Locale::default());
+    assert!("german!".parse::<Locale>().is_err());
+    assert!("de_DE_x".parse::<Locale>().is_err());
+  }
+
+  #[test]
+  fn test_provider_language() {
+    assert_eq!(locale("en_GB").query(), "");
+    assert_eq!(locale("fr_FR.UTF-8").query(), "&lang=fr");
+    assert_eq!(
+      locale("zh_TW").provider_language().as_deref(),
+      Some("zh_tw")
+    );
+  }
+
+  #[test]
+  fn test_label() {
+    assert_eq!(locale("de").label("Feels Like"), "Gefühlt");
+    assert_eq!(locale("fr").label("Sunrise"), "Lever du soleil");
+    assert_eq!(locale("ja").label("Sunrise"), "Sunrise");
+    assert_eq!(locale("de").label("Not a label"), "Not a label");
+    assert_eq!(Locale::default().label("Temperature"), "Temperature");
+    assert_eq!(locale("de").label("UV|High"), "Hoch");
+    assert_eq!(locale("de").label("High"), "Höchstwert");
+    assert_eq!(locale("ja").label("UV|High"), "High");
+    assert_eq!(locale("fr").boolean(true), "oui");
+    assert_eq!(Locale::default().boolean(false), "false");
+
+    for (index, label) in LABELS.iter().enumerate() {
+      assert!(label.translations.iter().all(|text| !text.is_empty()));
+      assert!(LABELS[..index]
+        .iter()
+        .all(|other| other.english != label.english));
+    }
+  }
+
+  #[test]
+  fn test_number() {
+    assert_eq!(Locale::default().number(5.1), "5.1");
+    assert_eq!(locale("de").number(5.1), "5,1");
+    assert_eq!(locale("de").number(12.0), "12");
+    assert_eq!(locale("fr").fixed(2.26, 1), "2,3");
+    assert_eq!(locale("ja").fixed(2.0, 1), "2.0");
+  }
+
+  #[test]
+  fn test_date() {
+    assert_eq!(Locale::default().date("2024-12-28"), "2024-12-28");
+    assert_eq!(locale("en_US").date("2024-12-28"), "12/28/2024");
+    assert_eq!(locale("en_GB").date("2024-12-28"), "28/12/2024");
+    assert_eq!(locale("de_DE").date("2024-12-28"), "28.12.2024");
+    assert_eq!(locale("de_DE").date("tomorrow"), "tomorrow");
+  }
+
+  #[test]
+  fn test_time() {
+    assert_eq!(locale("en_US").time("03:58 PM"), "03:58 PM");
+    assert_eq!(locale("de").time("03:58 PM"), "15:58");
+    assert_eq!(locale("de").time("No moonset"), "No moonset");
+  }
+}
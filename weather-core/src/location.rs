@@ -1,10 +1,25 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
+  config::Place,
   error::Error,
+  http,
   ip::{self},
+  keys,
+  redact::describe,
   Query,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+  name: String,
+  region: String,
+  country: String,
+  lat: f64,
+  lon: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Location {
   pub country: String,
   pub region: Option<String>,
@@ -29,16 +44,30 @@ impl Query<String> for Location {
   }
 }
 
+#[allow(clippy::to_string_trait_impl)]
 impl ToString for Location {
   fn to_string(&self) -> String {
-    match &self.region {
-      Some(region) => format!(
-        "{}, {}, {}",
-        self.city.as_ref().unwrap(),
-        region,
-        self.country
-      ),
-      None => format!("{}, {}", self.city.as_ref().unwrap(), self.country),
+    [
+      self.city.as_deref(),
+      self.region.as_deref(),
+      Some(self.country.as_str()),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<_>>()
+    .join(", ")
+  }
+}
+
+impl From<SearchResponse> for Location {
+  fn from(response: SearchResponse) -> Self {
+    Location {
+      country: response.country,
+      region: Some(response.region).filter(|region| !region.is_empty()),
+      city: Some(response.name),
+      lat: Some(response.lat),
+      lon: Some(response.lon),
     }
   }
 }
@@ -52,10 +81,50 @@ pub async fn get_location_from_ip(ip: &str) -> Result<Location, Error> {
       lat: Some(location.lat),
       lon: Some(location.lon),
     }),
+    Err(err @ Error::Timeout { .. }) => Err(err),
     Err(err) => Err(Error::BadIp {
       ip: ip.to_string(),
-      message: err.to_string(),
+      message: describe(&err),
+    }),
+  }
+}
+
+/// Search for a location by free text, such as a city name or post code.
+/// The best match from the provider is returned.
+pub async fn search_location(query: &str) -> Result<Location, Error> {
+  let address = http::address();
+  let key = keys::env_key();
+
+  let url = reqwest::Url::parse_with_params(
+    &format!("{}/search.json", address),
+    &[("key", key.as_str()), ("q", query)],
+  )
+  .map_err(|err| Error::InvalidArgument {
+    arg: query.to_string(),
+    message: err.to_string(),
+  })?;
+
+  let results: Vec<SearchResponse> = http::get_json(url.as_str()).await?;
+  match results.into_iter().next() {
+    Some(result) => Ok(Location::from(result)),
+    None => Err(Error::UnknownLocation {
+      location: query.to_string(),
+    }),
+  }
+}
+
+/// Get the location of a configured place.
+/// Coordinates are used as is, otherwise the query, or the name, is searched for.
+pub async fn get_location_from_place(place: &Place) -> Result<Location, Error> {
+  match (place.lat, place.lon) {
+    (Some(lat), Some(lon)) => Ok(Location {
+      country: String::new(),
+      region: None,
+      city: Some(place.name.clone()),
+      lat: Some(lat),
+      lon: Some(lon),
     }),
+    _ => search_location(place.query.as_deref().unwrap_or(&place.name)).await,
   }
 }
 
@@ -67,8 +136,10 @@ mod tests {
 
   fn setup() {
     dotenv::dotenv().ok();
-    std::env::set_var("IP_LOCATION_API", &mockito::server_url());
-    std::env::set_var("PUBLIC_IP_API", &mockito::server_url());
+    std::env::set_var("IP_LOCATION_API", mockito::server_url());
+    std::env::set_var("PUBLIC_IP_API", mockito::server_url());
+    std::env::set_var("WEATHER_API", mockito::server_url());
+    std::env::set_var("WEATHER_KEY", "test_key");
   }
 
   #[test]
@@ -148,4 +219,87 @@ mod tests {
     let result = get_location_from_ip("2.2.2.2").await;
     assert!(result.is_err());
   }
+
+  #[test]
+  fn test_to_string() {
+    let location = Location {
+      country: "United Kingdom".to_string(),
+      region: Some("City of London, Greater London".to_string()),
+      city: Some("London".to_string()),
+      lat: None,
+      lon: None,
+    };
+    assert_eq!(
+      location.to_string(),
+      "London, City of London, Greater London, United Kingdom"
+    );
+
+    let location = Location {
+      country: String::new(),
+      region: None,
+      city: Some("office".to_string()),
+      lat: None,
+      lon: None,
+    };
+    assert_eq!(location.to_string(), "office");
+  }
+
+  #[tokio::test]
+  async fn test_search_location_success() {
+    setup();
+    let _m = mock("GET", "/search.json?key=test_key&q=SW1A+1AA")
+      .with_status(200)
+      .with_header("content-type", "application/json")
+      .with_body(
+        r#"[
+          {
+            "id": 2801268,
+            "name": "London",
+            "region": "City of London, Greater London",
+            "country": "United Kingdom",
+            "lat": 51.52,
+            "lon": -0.11,
+            "url": "london-city-of-london-greater-london-united-kingdom"
+          }
+        ]"#,
+      )
+      .create();
+
+    let location = search_location("SW1A 1AA").await.unwrap();
+    assert_eq!(location.city, Some("London".to_string()));
+    assert_eq!(location.lat, Some(51.52));
+    assert_eq!(location.lon, Some(-0.11));
+  }
+
+  #[tokio::test]
+  async fn test_search_location_unknown() {
+    setup();
+    let _m = mock("GET", "/search.json?key=test_key&q=nowhere")
+      .with_status(200)
+      .with_header("content-type", "application/json")
+      .with_body("[]")
+      .create();
+
+    let result = search_location("nowhere").await;
+    assert_eq!(
+      result.err().unwrap(),
+      Error::UnknownLocation {
+        location: "nowhere".to_string()
+      }
+    );
+  }
+
+  #[tokio::test]
+  async fn test_location_from_place_coordinates() {
+    let place = Place {
+      name: "office".to_string(),
+      lat: Some(51.5171),
+      lon: Some(-0.1062),
+      query: None,
+    };
+
+    let location = get_location_from_place(&place).await.unwrap();
+    assert_eq!(location.query().unwrap(), "51.5171,-0.1062");
+    assert_eq!(location.to_string(), "office");
+  }
 }
@@ -0,0 +1,346 @@
+use std::{io::ErrorKind, process::Stdio, time::Instant};
+
+use serde_json::{json, Value};
+use tokio::{io::AsyncWriteExt, process::Command, task::spawn_blocking};
+use tracing::{field, Instrument, Span};
+
+use crate::{
+  alert::{Alert, Alerts, Severity},
+  config::NotifyConfig,
+  error::Error,
+  http,
+};
+
+/// A destination alerts can be pushed to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sink {
+  /// Freedesktop notification over D-Bus.
+  Desktop,
+  /// Generic webhook receiving the alert as JSON.
+  Webhook(String),
+  /// Slack incoming webhook.
+  Slack(String),
+  /// Discord webhook.
+  Discord(String),
+  /// Shell command receiving the alert as JSON on stdin.
+  Command(String),
+}
+
+impl Sink {
+  /// Send a single alert to the sink.
+  pub async fn send(&self, alert: &Alert) -> Result<(), Error> {
+    match self {
+      Sink::Desktop => send_desktop(alert).await,
+      Sink::Webhook(url) => post(self, url, webhook_payload(alert)).await,
+      Sink::Slack(url) => post(self, url, slack_payload(alert)).await,
+      Sink::Discord(url) => post(self, url, discord_payload(alert)).await,
+      Sink::Command(command) => run_command(self, command, alert).await,
+    }
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Sink {
+  fn to_string(&self) -> String {
+    match self {
+      Sink::Desktop => "desktop".to_string(),
+      Sink::Webhook(_) => "webhook".to_string(),
+      Sink::Slack(_) => "slack".to_string(),
+      Sink::Discord(_) => "discord".to_string(),
+      Sink::Command(command) => format!("command `{}`", command),
+    }
+  }
+}
+
+impl From<&NotifyConfig> for Vec<Sink> {
+  fn from(config: &NotifyConfig) -> Self {
+    let mut sinks = Vec::new();
+    if config.desktop {
+      sinks.push(Sink::Desktop);
+    }
+    sinks.extend(config.webhooks.iter().cloned().map(Sink::Webhook));
+    sinks.extend(config.slack.iter().cloned().map(Sink::Slack));
+    sinks.extend(config.discord.iter().cloned().map(Sink::Discord));
+    sinks.extend(config.commands.iter().cloned().map(Sink::Command));
+    sinks
+  }
+}
+
+/// Send every alert to every sink.
+/// A failing sink does not stop the others, all errors are collected and returned.
+pub async fn notify(sinks: &[Sink], alerts: &Alerts) -> Vec<Error> {
+  let mut errors = Vec::new();
+  for alert in &alerts.alerts {
+    for sink in sinks {
+      if let Err(err) = sink.send(alert).await {
+        errors.push(err);
+      }
+    }
+  }
+  errors
+}
+
+fn title(alert: &Alert) -> String {
+  format!("{}: {}", alert.severity_level().to_string(), alert.headline)
+}
+
+fn summary(alert: &Alert) -> String {
+  let mut summary = format!("{}\nAreas: {}", alert.description, alert.areas);
+  if !alert.instruction.is_empty() {
+    summary.push_str(&format!("\n{}", alert.instruction));
+  }
+  if !alert.expires.is_empty() {
+    summary.push_str(&format!("\nExpires: {}", alert.expires));
+  }
+  summary
+}
+
+/// Colour associated with a severity, as a 24 bit RGB value.
+fn colour(severity: Severity) -> u32 {
+  match severity {
+    Severity::Extreme => 0x8b0000,
+    Severity::Severe => 0xe53935,
+    Severity::Moderate => 0xfb8c00,
+    Severity::Minor => 0xfdd835,
+    Severity::Unknown => 0x9e9e9e,
+  }
+}
+
+fn webhook_payload(alert: &Alert) -> Value {
+  json!({
+    "id": alert.id(),
+    "alert": alert,
+  })
+}
+
+fn slack_payload(alert: &Alert) -> Value {
+  json!({
+    "text": title(alert),
+    "attachments": [{
+      "color": format!("#{:06x}", colour(alert.severity_level())),
+      "title": alert.headline,
+      "text": summary(alert),
+    }],
+  })
+}
+
+fn discord_payload(alert: &Alert) -> Value {
+  json!({
+    "content": title(alert),
+    "embeds": [{
+      "title": alert.headline,
+      "description": summary(alert),
+      "color": colour(alert.severity_level()),
+    }],
+  })
+}
+
+/// Post the payload to a webhook, traced in a `request` span with the sink, status and latency.
+/// Webhook URLs are secrets themselves, so are left out of the span.
+async fn post(sink: &Sink, url: &str, payload: Value) -> Result<(), Error> {
+  let span = tracing::info_span!(
+    "request",
+    sink = %sink.to_string(),
+    status = field::Empty,
+    latency_ms = field::Empty,
+  );
+  send_post(sink, url, payload).instrument(span).await
+}
+
+async fn send_post(sink: &Sink, url: &str, payload: Value) -> Result<(), Error> {
+  let error = |message: String| Error::Notify {
+    sink: sink.to_string(),
+    message,
+  };
+
+  let start = Instant::now();
+  let result = http::client().post(url).json(&payload).send().await;
+  Span::current().record("latency_ms", start.elapsed().as_millis() as u64);
+  let response = result.map_err(|err| {
+    let message = err.without_url().to_string();
+    tracing::warn!(error = %message, "request failed");
+    error(message)
+  })?;
+
+  Span::current().record("status", response.status().as_u16());
+  match response.status().is_success() {
+    true => {
+      tracing::info!("request done");
+      Ok(())
+    }
+    false => {
+      tracing::warn!("request failed");
+      Err(error(format!("unexpected status {}", response.status())))
+    }
+  }
+}
+
+async fn run_command(sink: &Sink, command: &str, alert: &Alert) -> Result<(), Error> {
+  let error = |message: String| Error::Notify {
+    sink: sink.to_string(),
+    message,
+  };
+
+  let payload = webhook_payload(alert).to_string();
+  let mut child = Command::new("sh")
+    .arg("-c")
+    .arg(command)
+    .stdin(Stdio::piped())
+    .spawn()
+    .map_err(|err| error(err.to_string()))?;
+
+  // Commands that do not read the alert may exit before it is written, so only the exit
+  // status tells whether the command worked.
+  if let Some(mut stdin) = child.stdin.take() {
+    match stdin.write_all(payload.as_bytes()).await {
+      Err(err) if err.kind() != ErrorKind::BrokenPipe => {
+        tracing::warn!(sink = %sink.to_string(), error = %err, "alert not written to command");
+      }
+      _ => (),
+    }
+    drop(stdin);
+  }
+
+  let status = child.wait().await.map_err(|err| error(err.to_string()))?;
+  match status.success() {
+    true => Ok(()),
+    false => Err(error(format!("exited with {}", status))),
+  }
+}
+
+/// Show a desktop notification, on the blocking pool as D-Bus calls block.
+async fn send_desktop(alert: &Alert) -> Result<(), Error> {
+  let error = |message: String| Error::Notify {
+    sink: Sink::Desktop.to_string(),
+    message,
+  };
+  let urgency = match alert.severity_level() {
+    Severity::Extreme | Severity::Severe => notify_rust::Urgency::Critical,
+    Severity::Moderate => notify_rust::Urgency::Normal,
+    _ => notify_rust::Urgency::Low,
+  };
+
+  let mut notification = notify_rust::Notification::new();
+  notification
+    .appname("weather")
+    .summary(&title(alert))
+    .body(&summary(alert))
+    .urgency(urgency);
+  spawn_blocking(move || notification.show().map(|_| ()))
+    .await
+    .map_err(|err| error(err.to_string()))?
+    .map_err(|err| error(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use mockito::{mock, Matcher};
+  use tokio;
+
+  #[test]
+  fn test_sinks_from_config() {
+    let config = NotifyConfig {
+      desktop: true,
+      webhooks: vec!["http://localhost/hook".to_string()],
+      slack: vec![],
+      discord: vec!["http://localhost/discord".to_string()],
+      commands: vec!["cat".to_string()],
+    };
+
+    let sinks: Vec<Sink> = (&config).into();
+    assert_eq!(
+      sinks,
+      vec![
+        Sink::Desktop,
+        Sink::Webhook("http://localhost/hook".to_string()),
+        Sink::Discord("http://localhost/discord".to_string()),
+        Sink::Command("cat".to_string()),
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn test_webhook() {
+    let _m = mock("POST", "/notify/webhook")
+      .match_body(Matcher::PartialJson(json!({
+        "alert": { "headline": "Severe Weather Alert", "severity": "Severe" }
+      })))
+      .with_status(200)
+      .expect(1)
+      .create();
+
+    let sink = Sink::Webhook(format!("{}/notify/webhook", mockito::server_url()));
+    assert!(sink.send(&Alert::sample()).await.is_ok());
+    _m.assert();
+  }
+
+  #[tokio::test]
+  async fn test_slack() {
+    let _m = mock("POST", "/notify/slack")
+      .match_body(Matcher::PartialJson(json!({
+        "text": "Severe: Severe Weather Alert"
+      })))
+      .with_status(200)
+      .expect(1)
+      .create();
+
+    let sink = Sink::Slack(format!("{}/notify/slack", mockito::server_url()));
+    assert!(sink.send(&Alert::sample()).await.is_ok());
+    _m.assert();
+  }
+
+  #[tokio::test]
+  async fn test_discord() {
+    let _m = mock("POST", "/notify/discord")
+      .match_body(Matcher::PartialJson(json!({
+        "content": "Severe: Severe Weather Alert",
+        "embeds": [{ "color": 0xe53935 }]
+      })))
+      .with_status(204)
+      .expect(1)
+      .create();
+
+    let sink = Sink::Discord(format!("{}/notify/discord", mockito::server_url()));
+    assert!(sink.send(&Alert::sample()).await.is_ok());
+    _m.assert();
+  }
+
+  #[tokio::test]
+  async fn test_webhook_failure() {
+    let _m = mock("POST", "/notify/failing").with_status(500).create();
+
+    let sink = Sink::Webhook(format!("{}/notify/failing", mockito::server_url()));
+    let result = sink.send(&Alert::sample()).await;
+    assert!(matches!(result, Err(Error::Notify { .. })));
+  }
+
+  #[tokio::test]
+  async fn test_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("alert.json");
+
+    let sink = Sink::Command(format!("cat > '{}'", path.display()));
+    assert!(sink.send(&Alert::sample()).await.is_ok());
+
+    let payload: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(payload["id"], Alert::sample().id());
+    assert_eq!(payload["alert"]["event"], "Heavy Rain");
+  }
+
+  #[tokio::test]
+  async fn test_command_ignoring_stdin() {
+    for _ in 0..20 {
+      assert!(Sink::Command("true".to_string())
+        .send(&Alert::sample())
+        .await
+        .is_ok());
+    }
+  }
+
+  #[tokio::test]
+  async fn test_command_failure() {
+    let sink = Sink::Command("exit 3".to_string());
+    assert!(sink.send(&Alert::sample()).await.is_err());
+  }
+}
@@ -0,0 +1,503 @@
+use std::{
+  collections::BTreeMap,
+  fs::{self, File},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+  },
+  time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+
+use crate::{config::QuotaConfig, error::Error, fingerprint, state::state_dir};
+
+/// A token bucket, allowing short bursts while limiting the sustained request rate.
+#[derive(Debug, Clone)]
+pub struct Bucket {
+  /// Tokens added per second.
+  rate: f64,
+  capacity: f64,
+  /// Tokens left, negative when callers are waiting for tokens not yet added.
+  tokens: f64,
+  updated: Instant,
+}
+
+impl Bucket {
+  /// A full bucket of `burst` tokens, refilled at `rate` tokens a second.
+  pub fn new(rate: f64, burst: u32, now: Instant) -> Self {
+    let capacity = burst.max(1) as f64;
+    Bucket {
+      rate,
+      capacity,
+      tokens: capacity,
+      updated: now,
+    }
+  }
+
+  /// Take a token, returning how long to wait before it may be used.
+  /// The token is reserved at once, so concurrent callers queue up behind each other.
+  pub fn take(&mut self, now: Instant) -> Duration {
+    let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+    self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+    self.updated = now;
+    self.tokens -= 1.0;
+    match self.tokens >= 0.0 {
+      true => Duration::ZERO,
+      false => Duration::from_secs_f64(-self.tokens / self.rate),
+    }
+  }
+}
+
+/// Requests made with one key, today and this month, in UTC.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+  /// Fingerprint of the key, so the key itself is never written to disk.
+  pub key: String,
+  /// The key as shown to users, masked, or its fingerprint when the key is not known.
+  #[serde(skip)]
+  pub label: String,
+  pub day: Option<NaiveDate>,
+  pub today: u64,
+  /// Month of `this_month`, as `YYYY-MM`.
+  pub month: String,
+  pub this_month: u64,
+}
+
+/// Persisted usage of every key, by key fingerprint so keys are never written to disk.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UsageLog {
+  keys: BTreeMap<String, Usage>,
+}
+
+/// Longest key that `mask` hides entirely.
+const MASK_MIN: usize = 8;
+
+/// Show only the start of a key, enough to tell keys apart.
+/// Short keys are hidden entirely, as their start would give away too much of them.
+pub fn mask(key: &str) -> String {
+  if key.chars().count() <= MASK_MIN {
+    return "…".to_string();
+  }
+  let start: String = key.chars().take(4).collect();
+  format!("{}…", start)
+}
+
+pub(crate) fn key_id(key: &str) -> String {
+  fingerprint(&["key", key])
+}
+
+fn month(now: DateTime<Utc>) -> String {
+  format!("{:04}-{:02}", now.year(), now.month())
+}
+
+impl Usage {
+  /// Usage as of `now`, with counts of past days and months reset.
+  pub fn at(&self, now: DateTime<Utc>) -> Usage {
+    let mut usage = self.clone();
+    if usage.day != Some(now.date_naive()) {
+      usage.day = Some(now.date_naive());
+      usage.today = 0;
+    }
+    if usage.month != month(now) {
+      usage.month = month(now);
+      usage.this_month = 0;
+    }
+    usage
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Usage {
+  fn to_string(&self) -> String {
+    format!(
+      "{}: {} today, {} in {}",
+      self.label, self.today, self.this_month, self.month
+    )
+  }
+}
+
+impl UsageLog {
+  /// Default location of the usage file.
+  pub fn default_path() -> PathBuf {
+    state_dir().join("usage.json")
+  }
+
+  /// Load the log from a file.
+  /// A missing file is not an error and results in an empty log.
+  pub fn load(path: &Path) -> Result<Self, Error> {
+    if !path.exists() {
+      return Ok(UsageLog::default());
+    }
+
+    let content = fs::read_to_string(path).map_err(|err| io_error(path, err.to_string()))?;
+    serde_json::from_str(&content).map_err(|err| io_error(path, err.to_string()))
+  }
+
+  /// Save the log to a file, creating any missing parent directories.
+  pub fn save(&self, path: &Path) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|err| io_error(parent, err.to_string()))?;
+    }
+
+    let content =
+      serde_json::to_string_pretty(self).map_err(|err| io_error(path, err.to_string()))?;
+    fs::write(path, content).map_err(|err| io_error(path, err.to_string()))
+  }
+
+  /// Usage of a key as of `now`.
+  pub fn usage(&self, key: &str, now: DateTime<Utc>) -> Usage {
+    let usage = match self.keys.get(&key_id(key)) {
+      Some(usage) => usage.at(now),
+      None => Usage {
+        key: key_id(key),
+        ..Usage::default()
+      }
+      .at(now),
+    };
+    Usage {
+      label: mask(key),
+      ..usage
+    }
+  }
+
+  /// Usage of every key seen, as of `now`.
+  /// Only fingerprints of the keys are logged, so they are labelled by their fingerprint.
+  pub fn all(&self, now: DateTime<Utc>) -> Vec<Usage> {
+    self
+      .keys
+      .values()
+      .map(|usage| Usage {
+        label: usage.key.clone(),
+        ..usage.at(now)
+      })
+      .collect()
+  }
+
+  /// Count a request made with a key, returning the usage including it.
+  pub fn record(&mut self, key: &str, now: DateTime<Utc>) -> Usage {
+    let mut usage = self.usage(key, now);
+    usage.today += 1;
+    usage.this_month += 1;
+    self.keys.insert(key_id(key), usage.clone());
+    usage
+  }
+}
+
+/// Limits the rate of provider requests and counts them against the configured quota.
+/// Requests are refused once a daily or monthly quota is used up.
+pub struct Quota {
+  config: QuotaConfig,
+  path: PathBuf,
+  bucket: Mutex<Option<Bucket>>,
+  /// Held while the usage file is read and written back, so no request goes uncounted.
+  log: tokio::sync::Mutex<()>,
+  warned: AtomicBool,
+  warn: fn(&str),
+}
+
+impl Quota {
+  /// `warn` is called once, the first time usage passes the warning threshold.
+  pub fn new(config: &QuotaConfig, path: PathBuf, warn: fn(&str)) -> Self {
+    Quota {
+      config: config.clone(),
+      path,
+      bucket: Mutex::new(None),
+      log: tokio::sync::Mutex::new(()),
+      warned: AtomicBool::new(false),
+      warn,
+    }
+  }
+
+  /// Count a request made with `key`, then wait for the rate limit.
+  /// The request is counted before waiting, so concurrent requests cannot slip past the quota.
+  pub async fn acquire(&self, key: &str) -> Result<(), Error> {
+    let usage = self.reserve(key, Utc::now()).await?;
+    if let Some(message) = self.warning(&usage) {
+      if !self.warned.swap(true, Ordering::Relaxed) {
+        (self.warn)(&message);
+      }
+    }
+
+    if let Some(wait) = self.wait(Instant::now()) {
+      tracing::debug!(wait_ms = wait.as_millis() as u64, "waiting for rate limit");
+      tokio::time::sleep(wait).await;
+    }
+    Ok(())
+  }
+
+  /// Check the quota and count a request, with the usage file locked against other tasks
+  /// and other processes.
+  async fn reserve(&self, key: &str, now: DateTime<Utc>) -> Result<Usage, Error> {
+    let _guard = self.log.lock().await;
+    // Another process may hold the lock for a while, so wait for it off the runtime.
+    let path = self.path.clone();
+    let _lock = spawn_blocking(move || lock(&path))
+      .await
+      .map_err(|err| io_error(&self.path, err.to_string()))??;
+    let mut log = UsageLog::load(&self.path)?;
+    self.check(&log.usage(key, now))?;
+    let usage = log.record(key, now);
+    log.save(&self.path)?;
+    Ok(usage)
+  }
+
+  fn wait(&self, now: Instant) -> Option<Duration> {
+    if self.config.rate <= 0.0 {
+      return None;
+    }
+    let mut bucket = self.bucket.lock().unwrap();
+    let wait = bucket
+      .get_or_insert_with(|| Bucket::new(self.config.rate, self.config.burst, now))
+      .take(now);
+    Some(wait).filter(|wait| !wait.is_zero())
+  }
+
+  /// Periods of the quota, with their limit and the usage in them.
+  fn periods(&self, usage: &Usage) -> Vec<(&'static str, u64, u64)> {
+    [
+      ("daily", self.config.daily, usage.today),
+      ("monthly", self.config.monthly, usage.this_month),
+    ]
+    .into_iter()
+    .filter_map(|(period, limit, used)| Some((period, limit?, used)))
+    .collect()
+  }
+
+  /// Refuse a request once any quota is used up.
+  pub fn check(&self, usage: &Usage) -> Result<(), Error> {
+    for (period, limit, used) in self.periods(usage) {
+      if used >= limit {
+        return Err(Error::QuotaExceeded {
+          period: period.to_string(),
+          limit,
+        });
+      }
+    }
+    Ok(())
+  }
+
+  /// Warning to give when usage has passed the warning threshold of any quota.
+  pub fn warning(&self, usage: &Usage) -> Option<String> {
+    self
+      .periods(usage)
+      .into_iter()
+      .find(|(_, limit, used)| *used as f64 >= *limit as f64 * self.config.warn_at)
+      .map(|(period, limit, used)| {
+        format!(
+          "{} has used {} of its {} quota of {} requests",
+          usage.label, used, period, limit
+        )
+      })
+  }
+
+  /// Usage of a key with the quota it counts against, for reports.
+  pub fn report(&self, usage: &Usage) -> String {
+    let limit =
+      |limit: Option<u64>| limit.map_or("unlimited".to_string(), |limit| limit.to_string());
+    format!(
+      "{}: {} of {} today, {} of {} in {}",
+      usage.label,
+      usage.today,
+      limit(self.config.daily),
+      usage.this_month,
+      limit(self.config.monthly),
+      usage.month
+    )
+  }
+}
+
+/// Lock a file beside `path`, until the returned file is dropped.
+fn lock(path: &Path) -> Result<File, Error> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|err| io_error(parent, err.to_string()))?;
+  }
+  let path = path.with_extension("lock");
+  let file = File::options()
+    .create(true)
+    .truncate(false)
+    .write(true)
+    .open(&path)
+    .map_err(|err| io_error(&path, err.to_string()))?;
+  file
+    .lock()
+    .map_err(|err| io_error(&path, err.to_string()))?;
+  Ok(file)
+}
+
+fn io_error(path: &Path, message: String) -> Error {
+  Error::Io {
+    path: path.display().to_string(),
+    message,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  fn config(daily: Option<u64>, monthly: Option<u64>) -> QuotaConfig {
+    QuotaConfig {
+      daily,
+      monthly,
+      rate: 0.0,
+      ..QuotaConfig::default()
+    }
+  }
+
+  #[test]
+  fn test_bucket() {
+    let start = Instant::now();
+    let mut bucket = Bucket::new(2.0, 2, start);
+    assert_eq!(bucket.take(start), Duration::ZERO);
+    assert_eq!(bucket.take(start), Duration::ZERO);
+    assert_eq!(bucket.take(start), Duration::from_millis(500));
+    assert_eq!(bucket.take(start), Duration::from_millis(1000));
+
+    // Refilled after waiting, but never above the burst.
+    let later = start + Duration::from_secs(60);
+    assert_eq!(bucket.take(later), Duration::ZERO);
+    assert_eq!(bucket.take(later), Duration::ZERO);
+    assert!(!bucket.take(later).is_zero());
+  }
+
+  #[test]
+  fn test_mask() {
+    assert_eq!(mask("0123456789abcdef"), "0123…");
+    assert_eq!(mask("012345678"), "0123…");
+    assert_eq!(mask("01234567"), "…");
+    assert_eq!(mask("key"), "…");
+    assert_eq!(mask(""), "…");
+  }
+
+  #[test]
+  fn test_record() {
+    let mut log = UsageLog::default();
+    let now = Utc.with_ymd_and_hms(2024, 12, 30, 12, 0, 0).unwrap();
+    log.record("secret-key", now);
+    let usage = log.record("secret-key", now);
+    assert_eq!(usage.today, 2);
+    assert_eq!(usage.this_month, 2);
+    assert_eq!(usage.key, key_id("secret-key"));
+    assert_eq!(usage.label, "secr…");
+
+    let tomorrow = Utc.with_ymd_and_hms(2024, 12, 31, 12, 0, 0).unwrap();
+    let usage = log.record("secret-key", tomorrow);
+    assert_eq!(usage.today, 1);
+    assert_eq!(usage.this_month, 3);
+
+    let next_month = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let usage = log.usage("secret-key", next_month);
+    assert_eq!(usage.today, 0);
+    assert_eq!(usage.this_month, 0);
+    assert_eq!(usage.month, "2025-01");
+
+    assert_eq!(log.usage("other", now).today, 0);
+    assert_eq!(log.all(now).len(), 1);
+    assert_eq!(log.all(now)[0].label, key_id("secret-key"));
+  }
+
+  #[test]
+  fn test_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state").join("usage.json");
+    let now = Utc::now();
+
+    let mut log = UsageLog::default();
+    log.record("secret-key", now);
+    log.save(&path).unwrap();
+
+    let content = fs::read_to_string(&path).unwrap();
+    assert!(!content.contains("secret-key"));
+    assert!(!content.contains("secr"));
+    assert!(content.contains(&key_id("secret-key")));
+    assert_eq!(
+      UsageLog::load(&path)
+        .unwrap()
+        .usage("secret-key", now)
+        .today,
+      1
+    );
+  }
+
+  #[test]
+  fn test_check_and_warning() {
+    let quota = Quota::new(&config(Some(10), Some(100)), PathBuf::new(), |_| {});
+    let usage = |today, this_month| Usage {
+      label: "secr…".to_string(),
+      today,
+      this_month,
+      month: "2024-12".to_string(),
+      ..Usage::default()
+    };
+
+    assert!(quota.check(&usage(5, 50)).is_ok());
+    assert_eq!(quota.warning(&usage(5, 50)), None);
+    assert_eq!(
+      quota.warning(&usage(8, 50)),
+      Some("secr… has used 8 of its daily quota of 10 requests".to_string())
+    );
+    assert!(quota.warning(&usage(1, 85)).unwrap().contains("monthly"));
+    assert_eq!(
+      quota.check(&usage(10, 50)),
+      Err(Error::QuotaExceeded {
+        period: "daily".to_string(),
+        limit: 10
+      })
+    );
+
+    let unlimited = Quota::new(&config(None, None), PathBuf::new(), |_| {});
+    assert!(unlimited.check(&usage(1000, 100000)).is_ok());
+    assert_eq!(
+      unlimited.report(&usage(1, 2)),
+      "secr…: 1 of unlimited today, 2 of unlimited in 2024-12"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_acquire() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("usage.json");
+    let quota = Quota::new(&config(None, Some(2)), path.clone(), |_| {});
+
+    quota.acquire("secret-key").await.unwrap();
+    quota.acquire("secret-key").await.unwrap();
+    assert!(matches!(
+      quota.acquire("secret-key").await,
+      Err(Error::QuotaExceeded { .. })
+    ));
+    quota.acquire("other-key").await.unwrap();
+
+    let log = UsageLog::load(&path).unwrap();
+    assert_eq!(log.usage("secret-key", Utc::now()).this_month, 2);
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_acquire_concurrently() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("usage.json");
+    let limited = QuotaConfig {
+      rate: 50.0,
+      burst: 1,
+      ..config(None, None)
+    };
+    let quota = std::sync::Arc::new(Quota::new(&limited, path.clone(), |_| {}));
+
+    let tasks: Vec<_> = (0..10)
+      .map(|_| {
+        let quota = quota.clone();
+        tokio::spawn(async move { quota.acquire("secret-key").await })
+      })
+      .collect();
+    for task in tasks {
+      task.await.unwrap().unwrap();
+    }
+
+    let log = UsageLog::load(&path).unwrap();
+    assert_eq!(log.usage("secret-key", Utc::now()).today, 10);
+  }
+}
@@ -0,0 +1,150 @@
+use std::{
+  net::IpAddr,
+  sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::error::Error;
+
+static REDACT_IP: AtomicBool = AtomicBool::new(false);
+
+/// URL parameters whose values are secrets.
+const SECRET_PARAMS: [&str; 7] = [
+  "key",
+  "api_key",
+  "apikey",
+  "token",
+  "access_token",
+  "secret",
+  "password",
+];
+
+/// Shown in place of a redacted value.
+pub const REDACTED: &str = "REDACTED";
+
+/// Set whether IP addresses are redacted too, such as the client IP used to find the location.
+pub fn set_redact_ip(redact_ip: bool) {
+  REDACT_IP.store(redact_ip, Ordering::Relaxed);
+}
+
+/// Text with the values of secret URL parameters hidden, and IP addresses when set.
+/// Used on every error and log message that may contain a request URL.
+pub fn redact(text: &str) -> String {
+  let text = redact_params(text);
+  match REDACT_IP.load(Ordering::Relaxed) {
+    true => redact_ips(&text),
+    false => text,
+  }
+}
+
+/// An error as shown to users, redacted.
+pub fn describe(err: &Error) -> String {
+  redact(&format!("{:?}", err))
+}
+
+fn redact_params(text: &str) -> String {
+  let mut redacted = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find(['?', '&']) {
+    let (before, after) = rest.split_at(start + 1);
+    redacted.push_str(before);
+    rest = after;
+
+    let Some(equals) = rest.find('=') else {
+      continue;
+    };
+    let name = &rest[..equals];
+    if !SECRET_PARAMS
+      .iter()
+      .any(|param| param.eq_ignore_ascii_case(name))
+    {
+      continue;
+    }
+    let end = rest[equals + 1..]
+      .find(|c: char| c == '&' || c == '#' || c == '"' || c == ')' || c.is_whitespace())
+      .map_or(rest.len(), |end| equals + 1 + end);
+    redacted.push_str(name);
+    redacted.push('=');
+    redacted.push_str(REDACTED);
+    rest = &rest[end..];
+  }
+  redacted.push_str(rest);
+  redacted
+}
+
+fn redact_ips(text: &str) -> String {
+  let is_part = |c: char| c.is_ascii_hexdigit() || c == '.' || c == ':';
+  let mut redacted = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find(is_part) {
+    redacted.push_str(&rest[..start]);
+    rest = &rest[start..];
+    let end = rest.find(|c: char| !is_part(c)).unwrap_or(rest.len());
+    let word = &rest[..end];
+    match word.trim_end_matches([':', '.']).parse::<IpAddr>() {
+      Ok(_) => {
+        redacted.push_str(REDACTED);
+        redacted.push_str(&word[word.trim_end_matches([':', '.']).len()..]);
+      }
+      Err(_) => redacted.push_str(word),
+    }
+    rest = &rest[end..];
+  }
+  redacted.push_str(rest);
+  redacted
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_redact_params() {
+    assert_eq!(
+      redact_params(
+        "error sending request for url (http://localhost/current.json?key=abc123&q=London)"
+      ),
+      "error sending request for url (http://localhost/current.json?key=REDACTED&q=London)"
+    );
+    assert_eq!(
+      redact_params("http://localhost/current.json?q=London&key=abc123"),
+      "http://localhost/current.json?q=London&key=REDACTED"
+    );
+    assert_eq!(
+      redact_params("https://hooks.example/?Token=t0k3n monkey=1"),
+      "https://hooks.example/?Token=REDACTED monkey=1"
+    );
+    assert_eq!(
+      redact_params("http://localhost/search.json?q=keyworth&days=3"),
+      "http://localhost/search.json?q=keyworth&days=3"
+    );
+    assert_eq!(redact_params("Which? & why"), "Which? & why");
+  }
+
+  #[test]
+  fn test_describe() {
+    let err = Error::Fetch {
+      message: "error sending request for url (http://localhost/?key=abc123)".to_string(),
+    };
+    assert!(!describe(&err).contains("abc123"));
+  }
+
+  #[test]
+  fn test_redact_ips() {
+    assert_eq!(
+      redact_ips("BadIp { ip: \"203.0.113.7\", message: \"no location\" }"),
+      "BadIp { ip: \"REDACTED\", message: \"no location\" }"
+    );
+    assert_eq!(
+      redact_ips("/current.json?q=2001:db8::1&lang=de"),
+      "/current.json?q=REDACTED&lang=de"
+    );
+    assert_eq!(
+      redact_ips("connecting to 10.0.0.1: refused"),
+      "connecting to REDACTED: refused"
+    );
+    assert_eq!(
+      redact_ips("at 51.52,-0.11 on 2024-12-28T10:00:00"),
+      "at 51.52,-0.11 on 2024-12-28T10:00:00"
+    );
+  }
+}
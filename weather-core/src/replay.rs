@@ -0,0 +1,229 @@
+use std::{
+  fs,
+  future::Future,
+  path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{error::Error, fingerprint};
+
+/// Longest fixture name before the query part is replaced by its fingerprint.
+const MAX_NAME: usize = 120;
+
+/// Parameters left out of fixture names when they are today, the date they default to.
+const DATE_PARAMS: [&str; 1] = ["dt"];
+
+tokio::task_local! {
+  static SCOPED: Mode;
+}
+
+/// Whether provider responses are recorded to, or replayed from, a directory of fixtures.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+  /// Write every response to a fixture file.
+  Record(PathBuf),
+  /// Serve responses from fixture files, without touching the network.
+  Replay(PathBuf),
+}
+
+impl Mode {
+  /// Mode set by `WEATHER_REPLAY=dir` or `WEATHER_RECORD=dir`, replay taking precedence.
+  pub fn from_env() -> Option<Mode> {
+    let dir = |name| {
+      std::env::var(name)
+        .ok()
+        .filter(|dir: &String| !dir.is_empty())
+    };
+    match (dir("WEATHER_REPLAY"), dir("WEATHER_RECORD")) {
+      (Some(dir), _) => Some(Mode::Replay(dir.into())),
+      (None, Some(dir)) => Some(Mode::Record(dir.into())),
+      (None, None) => None,
+    }
+  }
+}
+
+/// Run `future` with requests recorded or replayed as set by `mode`, whatever the environment.
+/// Meant for tests, which run concurrently and so cannot share environment variables.
+pub async fn scope<F: Future>(mode: Mode, future: F) -> F::Output {
+  SCOPED.scope(mode, future).await
+}
+
+/// Mode of the current task, set by `scope` or else by the environment.
+pub fn mode() -> Option<Mode> {
+  SCOPED
+    .try_with(|mode| mode.clone())
+    .ok()
+    .or_else(Mode::from_env)
+}
+
+/// Name of the fixture file of a URL, without its extension.
+/// Made from the endpoint, the last part of the path, and the sorted parameters, leaving out
+/// the API key, so fixtures do not depend on the provider address and base path, the key or
+/// the order of parameters.
+/// Dates that are today are left out too, so fixtures of today's data can be replayed later.
+pub fn fixture_name(url: &str) -> String {
+  let Ok(url) = reqwest::Url::parse(url) else {
+    return sanitize(url);
+  };
+  let last = url.path().trim_end_matches('/').rsplit('/').next();
+  let endpoint = match last.unwrap_or_default().trim_end_matches(".json") {
+    "" => "index".to_string(),
+    endpoint => endpoint.to_string(),
+  };
+  let today = Local::now().format("%Y-%m-%d").to_string();
+  let mut params: Vec<String> = url
+    .query_pairs()
+    .filter(|(name, value)| {
+      name != "key" && !(DATE_PARAMS.contains(&name.as_ref()) && *value == today)
+    })
+    .map(|(name, value)| format!("{}_{}", name, value))
+    .collect();
+  params.sort();
+
+  let query = params.join("_");
+  let name = match query.is_empty() {
+    true => endpoint.clone(),
+    false => format!("{}-{}", endpoint, query),
+  };
+  match name.len() > MAX_NAME {
+    true => sanitize(&format!("{}-{}", endpoint, fingerprint(&[&query]))),
+    false => sanitize(&name),
+  }
+}
+
+fn sanitize(name: &str) -> String {
+  name
+    .chars()
+    .map(
+      |c| match c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ',' | '_') {
+        true => c,
+        false => '_',
+      },
+    )
+    .collect()
+}
+
+/// Extension of a fixture, by the kind of body.
+fn extension(body: &str) -> &'static str {
+  match body.trim_start().starts_with('<') {
+    true => "xml",
+    false => "json",
+  }
+}
+
+/// Response recorded for a URL.
+pub fn load(dir: &Path, url: &str) -> Result<String, Error> {
+  let name = fixture_name(url);
+  ["json", "xml"]
+    .iter()
+    .map(|extension| dir.join(format!("{}.{}", name, extension)))
+    .find(|path| path.exists())
+    .ok_or_else(|| Error::Fetch {
+      message: format!("no fixture {} in {}", name, dir.display()),
+    })
+    .and_then(|path| fs::read_to_string(&path).map_err(|err| io_error(&path, err.to_string())))
+}
+
+/// Record the response of a URL, replacing any earlier recording.
+pub fn save(dir: &Path, url: &str, body: &str) -> Result<(), Error> {
+  fs::create_dir_all(dir).map_err(|err| io_error(dir, err.to_string()))?;
+  let path = dir.join(format!("{}.{}", fixture_name(url), extension(body)));
+  fs::write(&path, body).map_err(|err| io_error(&path, err.to_string()))
+}
+
+/// Replay the fixtures of a scenario in the `fixtures` directory of this crate.
+#[cfg(test)]
+pub(crate) fn fixtures(scenario: &str) -> Mode {
+  Mode::Replay(
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("fixtures")
+      .join(scenario),
+  )
+}
+
+fn io_error(path: &Path, message: String) -> Error {
+  Error::Io {
+    path: path.display().to_string(),
+    message,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fixture_name() {
+    assert_eq!(
+      fixture_name("http://localhost:1234/current.json?key=secret&q=51.5171,-0.1062"),
+      "current-q_51.5171,-0.1062"
+    );
+    assert_eq!(
+      fixture_name("https://api.weatherapi.com/v1/forecast.json?key=k&q=London&days=3&alerts=yes"),
+      "forecast-alerts_yes_days_3_q_London"
+    );
+    assert_eq!(
+      fixture_name("http://localhost/search.json?q=New York"),
+      "search-q_New_York"
+    );
+    assert_eq!(
+      fixture_name(&format!(
+        "http://localhost/astronomy.json?q=London&dt={}",
+        Local::now().format("%Y-%m-%d")
+      )),
+      "astronomy-q_London"
+    );
+    assert_eq!(
+      fixture_name("http://localhost/astronomy.json?q=London&dt=2024-12-28"),
+      "astronomy-dt_2024-12-28_q_London"
+    );
+    assert_eq!(fixture_name("http://ip.localhost/"), "index");
+    assert_eq!(fixture_name("http://ip.localhost/8.8.8.8"), "8.8.8.8");
+    let long = format!("http://localhost/search.json?q={}", "x".repeat(200));
+    assert!(fixture_name(&long).starts_with("search-"));
+    assert!(fixture_name(&long).len() < 40);
+  }
+
+  #[test]
+  fn test_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = "http://localhost/current.json?key=secret&q=London";
+    save(dir.path(), url, r#"{"current": {}}"#).unwrap();
+    assert_eq!(load(dir.path(), url).unwrap(), r#"{"current": {}}"#);
+
+    // Recorded with another key and provider address.
+    let other = "http://127.0.0.1:9/current.json?q=London&key=other";
+    assert!(load(dir.path(), other).is_ok());
+    assert!(!fs::read_dir(dir.path()).unwrap().any(|entry| entry
+      .unwrap()
+      .file_name()
+      .to_string_lossy()
+      .contains("secret")));
+
+    save(dir.path(), "http://localhost/cap/alerts", "<alert/>").unwrap();
+    assert!(dir.path().join("alerts.xml").exists());
+
+    assert!(matches!(
+      load(dir.path(), "http://localhost/current.json?q=Paris"),
+      Err(Error::Fetch { .. })
+    ));
+  }
+
+  #[test]
+  fn test_other_base_url() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorded = "https://api.weatherapi.com/v1/current.json?key=secret&q=London";
+    save(dir.path(), recorded, r#"{"current": {}}"#).unwrap();
+
+    let replayed = "http://127.0.0.1:1234/current.json?key=test_key&q=London";
+    assert_eq!(load(dir.path(), replayed).unwrap(), r#"{"current": {}}"#);
+    assert!(load(dir.path(), "http://localhost/api/current.json?q=London").is_ok());
+  }
+
+  #[tokio::test]
+  async fn test_scope() {
+    let replay = Mode::Replay("fixtures".into());
+    assert_eq!(scope(replay.clone(), async { mode() }).await, Some(replay));
+  }
+}
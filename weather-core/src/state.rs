@@ -0,0 +1,163 @@
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  alert::{Alert, Alerts},
+  error::Error,
+};
+
+/// Persisted record of the alerts that have already been reported.
+/// Maps an alert id to the time it expires, if known, so stale entries can be pruned.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AlertState {
+  seen: BTreeMap<String, Option<DateTime<Utc>>>,
+}
+
+/// Directory used to persist state between runs.
+/// Uses `WEATHER_STATE_DIR` if set, otherwise the platform local data directory.
+pub fn state_dir() -> PathBuf {
+  match std::env::var("WEATHER_STATE_DIR") {
+    Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+    _ => dirs::data_local_dir()
+      .unwrap_or_else(std::env::temp_dir)
+      .join("weather"),
+  }
+}
+
+impl AlertState {
+  /// Default location of the alert state file.
+  pub fn default_path() -> PathBuf {
+    state_dir().join("alerts.json")
+  }
+
+  /// Load the state from a file.
+  /// A missing file is not an error and results in an empty state.
+  pub fn load(path: &Path) -> Result<Self, Error> {
+    if !path.exists() {
+      return Ok(AlertState::default());
+    }
+
+    let content = fs::read_to_string(path).map_err(|err| io_error(path, err.to_string()))?;
+    serde_json::from_str(&content).map_err(|err| io_error(path, err.to_string()))
+  }
+
+  /// Save the state to a file, creating any missing parent directories.
+  pub fn save(&self, path: &Path) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|err| io_error(parent, err.to_string()))?;
+    }
+
+    let content =
+      serde_json::to_string_pretty(self).map_err(|err| io_error(path, err.to_string()))?;
+    fs::write(path, content).map_err(|err| io_error(path, err.to_string()))
+  }
+
+  pub fn has_seen(&self, alert: &Alert) -> bool {
+    self.seen.contains_key(&alert.id())
+  }
+
+  /// Alerts that have not been seen before.
+  pub fn unseen(&self, alerts: &Alerts) -> Alerts {
+    let unseen = alerts
+      .alerts
+      .iter()
+      .filter(|alert| !self.has_seen(alert))
+      .cloned()
+      .collect();
+    Alerts::new(unseen)
+  }
+
+  /// Record every alert as seen.
+  pub fn mark(&mut self, alerts: &Alerts) {
+    for alert in &alerts.alerts {
+      self.seen.insert(alert.id(), alert.expires_at());
+    }
+  }
+
+  /// Forget alerts that expired before `now`, they can no longer be reported.
+  pub fn prune(&mut self, now: DateTime<Utc>) {
+    self
+      .seen
+      .retain(|_, expires| expires.is_none_or(|expires| expires > now));
+  }
+}
+
+fn io_error(path: &Path, message: String) -> Error {
+  Error::Io {
+    path: path.display().to_string(),
+    message,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn alerts() -> Alerts {
+    let alert = |headline: &str, expires: &str| Alert {
+      headline: headline.to_string(),
+      expires: expires.to_string(),
+      ..Alert::sample()
+    };
+
+    Alerts::new(vec![
+      alert("Rain", "2024-12-28T18:00:00Z"),
+      alert("More rain", "2024-12-30T18:00:00Z"),
+    ])
+  }
+
+  #[test]
+  fn test_unseen_and_mark() {
+    let mut state = AlertState::default();
+    assert_eq!(state.unseen(&alerts()).len(), 2);
+
+    state.mark(&alerts());
+    assert_eq!(state.unseen(&alerts()).len(), 0);
+  }
+
+  #[test]
+  fn test_prune() {
+    let mut state = AlertState::default();
+    state.mark(&alerts());
+
+    let now = DateTime::parse_from_rfc3339("2024-12-29T00:00:00Z")
+      .unwrap()
+      .with_timezone(&Utc);
+    state.prune(now);
+
+    let unseen = state.unseen(&alerts());
+    assert_eq!(unseen.len(), 1);
+    assert_eq!(unseen.alerts[0].headline, "Rain");
+  }
+
+  #[test]
+  fn test_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("alerts.json");
+
+    let missing = AlertState::load(&path).unwrap();
+    assert_eq!(missing.unseen(&alerts()).len(), 2);
+
+    let mut state = AlertState::default();
+    state.mark(&alerts());
+    state.save(&path).unwrap();
+
+    let loaded = AlertState::load(&path).unwrap();
+    assert_eq!(loaded.unseen(&alerts()).len(), 0);
+  }
+
+  #[test]
+  fn test_load_invalid() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("alerts.json");
+    fs::write(&path, "not json").unwrap();
+
+    assert!(matches!(AlertState::load(&path), Err(Error::Io { .. })));
+  }
+}
@@ -0,0 +1,560 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  fs,
+  path::{Path, PathBuf},
+  sync::Mutex,
+};
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use rusqlite::{params, Connection, Row};
+
+use crate::{
+  config::StoreConfig,
+  error::Error,
+  location::Location,
+  redact::describe,
+  state::state_dir,
+  weather::{Condition, Weather},
+  Query,
+};
+
+/// Store that fetched weather is recorded in, with the names of places by location query.
+/// A mutex rather than a lock, as the database connection cannot be shared between threads.
+static RECORDER: Mutex<Option<(Store, HashMap<String, String>)>> = Mutex::new(None);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS samples (
+  id INTEGER PRIMARY KEY,
+  time INTEGER NOT NULL,
+  place TEXT NOT NULL,
+  is_day INTEGER NOT NULL,
+  temperature REAL NOT NULL,
+  feels_like REAL NOT NULL,
+  heat_index REAL NOT NULL,
+  condition_text TEXT NOT NULL,
+  condition_icon TEXT NOT NULL,
+  condition_code INTEGER NOT NULL,
+  wind_speed REAL NOT NULL,
+  wind_degree INTEGER NOT NULL,
+  wind_dir TEXT NOT NULL,
+  wind_gust_speed REAL NOT NULL,
+  wind_chill REAL NOT NULL,
+  pressure REAL NOT NULL,
+  precipitation REAL NOT NULL,
+  humidity INTEGER NOT NULL,
+  cloud INTEGER NOT NULL,
+  uv REAL NOT NULL,
+  visibility REAL NOT NULL,
+  dew_point REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS samples_place_time ON samples (place, time);
+";
+
+/// Local SQLite database of weather samples, so history can be queried offline.
+/// Samples are always stored in metric units.
+#[derive(Debug)]
+pub struct Store {
+  path: PathBuf,
+  connection: Connection,
+}
+
+/// A stored observation of the weather at a place.
+#[derive(Debug, Clone)]
+pub struct Sample {
+  pub time: DateTime<Utc>,
+  pub place: String,
+  pub weather: Weather,
+}
+
+/// A numeric field of the weather that statistics are computed for.
+pub struct Field {
+  pub name: &'static str,
+  pub unit: &'static str,
+  pub value: fn(&Weather) -> f64,
+}
+
+pub const FIELDS: [Field; 9] = [
+  Field {
+    name: "temperature",
+    unit: "°C",
+    value: |weather| weather.temperature,
+  },
+  Field {
+    name: "feels_like",
+    unit: "°C",
+    value: |weather| weather.feels_like,
+  },
+  Field {
+    name: "dew_point",
+    unit: "°C",
+    value: |weather| weather.dew_point,
+  },
+  Field {
+    name: "humidity",
+    unit: "%",
+    value: |weather| weather.humidity as f64,
+  },
+  Field {
+    name: "pressure",
+    unit: "mb",
+    value: |weather| weather.pressure,
+  },
+  Field {
+    name: "wind_speed",
+    unit: "km/h",
+    value: |weather| weather.wind_speed,
+  },
+  Field {
+    name: "wind_gust_speed",
+    unit: "km/h",
+    value: |weather| weather.wind_gust_speed,
+  },
+  Field {
+    name: "precipitation",
+    unit: "mm",
+    value: |weather| weather.precipitation,
+  },
+  Field {
+    name: "uv",
+    unit: "",
+    value: |weather| weather.uv,
+  },
+];
+
+/// Summary statistics of a set of values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+  pub count: usize,
+  pub min: f64,
+  pub max: f64,
+  pub mean: f64,
+  pub p10: f64,
+  pub median: f64,
+  pub p90: f64,
+}
+
+/// Aggregates of the samples taken on one local day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Daily {
+  pub date: NaiveDate,
+  pub temperature: Stats,
+  pub humidity: Stats,
+  pub wind_speed: Stats,
+}
+
+/// Statistics of every field over a period, and per day.
+#[derive(Debug, Clone)]
+pub struct Report {
+  pub place: String,
+  pub fields: Vec<(&'static str, &'static str, Stats)>,
+  pub daily: Vec<Daily>,
+}
+
+impl Store {
+  /// Default location of the database, `weather.db` in the state directory.
+  pub fn default_path() -> PathBuf {
+    state_dir().join("weather.db")
+  }
+
+  /// Open the database configured in the config file.
+  pub fn from_config(config: &StoreConfig) -> Result<Self, Error> {
+    Store::open(&config.path.clone().unwrap_or_else(Store::default_path))
+  }
+
+  /// Open the database at `path`, creating it and any missing parent directories.
+  pub fn open(path: &Path) -> Result<Self, Error> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|err| Error::Io {
+        path: parent.display().to_string(),
+        message: err.to_string(),
+      })?;
+    }
+
+    let connection = Connection::open(path).map_err(|err| store_error(path, err))?;
+    connection
+      .execute_batch(SCHEMA)
+      .map_err(|err| store_error(path, err))?;
+
+    Ok(Store {
+      path: path.to_path_buf(),
+      connection,
+    })
+  }
+
+  /// Record the weather at a place.
+  pub fn record(&self, place: &str, time: DateTime<Utc>, weather: &Weather) -> Result<(), Error> {
+    self
+      .connection
+      .execute(
+        "INSERT INTO samples (
+          time, place, is_day, temperature, feels_like, heat_index,
+          condition_text, condition_icon, condition_code,
+          wind_speed, wind_degree, wind_dir, wind_gust_speed, wind_chill,
+          pressure, precipitation, humidity, cloud, uv, visibility, dew_point
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+        params![
+          time.timestamp(),
+          place,
+          weather.is_day,
+          weather.temperature,
+          weather.feels_like,
+          weather.heat_index,
+          weather.condition.text,
+          weather.condition.icon,
+          weather.condition.code,
+          weather.wind_speed,
+          weather.wind_degree,
+          weather.wind_dir,
+          weather.wind_gust_speed,
+          weather.wind_chill,
+          weather.pressure,
+          weather.precipitation,
+          weather.humidity,
+          weather.cloud,
+          weather.uv,
+          weather.visibility,
+          weather.dew_point,
+        ],
+      )
+      .map_err(|err| store_error(&self.path, err))?;
+    Ok(())
+  }
+
+  /// Samples of a place taken at or after `since`, oldest first.
+  /// Place names are matched case-insensitively, like places in the config file.
+  pub fn samples(&self, place: &str, since: DateTime<Utc>) -> Result<Vec<Sample>, Error> {
+    let mut statement = self
+      .connection
+      .prepare(
+        "SELECT time, place, is_day, temperature, feels_like, heat_index,
+          condition_text, condition_icon, condition_code,
+          wind_speed, wind_degree, wind_dir, wind_gust_speed, wind_chill,
+          pressure, precipitation, humidity, cloud, uv, visibility, dew_point
+        FROM samples
+        WHERE place = ?1 COLLATE NOCASE AND time >= ?2
+        ORDER BY time",
+      )
+      .map_err(|err| store_error(&self.path, err))?;
+
+    let samples = statement
+      .query_map(params![place, since.timestamp()], sample)
+      .and_then(|rows| rows.collect())
+      .map_err(|err| store_error(&self.path, err))?;
+    Ok(samples)
+  }
+}
+
+/// Set the store that the current weather fetched by any command is recorded in.
+/// `None` records nothing, which is the default.
+pub fn set_recorder(store: Option<Store>) {
+  *RECORDER.lock().unwrap() = store.map(|store| (store, HashMap::new()));
+}
+
+/// Record the weather at a location under `name`, such as the name of a configured place.
+/// Weather at other locations is recorded under the name of the location.
+pub fn name_place(location: &Location, name: &str) {
+  if let (Some((_, names)), Ok(query)) = (RECORDER.lock().unwrap().as_mut(), location.query()) {
+    names.insert(query, name.to_string());
+  }
+}
+
+/// Record weather just fetched for a location, when a recorder is set.
+/// Failing to record is logged as an error rather than failing the fetch.
+pub(crate) fn observe(location: &Location, weather: &Weather) {
+  let recorder = RECORDER.lock().unwrap();
+  let Some((store, names)) = recorder.as_ref() else {
+    return;
+  };
+  let name = location
+    .query()
+    .ok()
+    .and_then(|query| names.get(&query).cloned())
+    .unwrap_or_else(|| location.to_string());
+  match store.record(&name, Utc::now(), weather) {
+    Ok(()) => tracing::debug!(place = %name, "recorded sample"),
+    Err(err) => tracing::error!(place = %name, error = %describe(&err), "failed to record sample"),
+  }
+}
+
+fn sample(row: &Row) -> rusqlite::Result<Sample> {
+  Ok(Sample {
+    time: DateTime::from_timestamp(row.get(0)?, 0).unwrap_or_default(),
+    place: row.get(1)?,
+    weather: Weather::new(
+      row.get(2)?,
+      row.get(3)?,
+      row.get(4)?,
+      row.get(5)?,
+      Condition::new(row.get(6)?, row.get(7)?, row.get(8)?),
+      row.get(9)?,
+      row.get(10)?,
+      row.get(11)?,
+      row.get(12)?,
+      row.get(13)?,
+      row.get(14)?,
+      row.get(15)?,
+      row.get(16)?,
+      row.get(17)?,
+      row.get(18)?,
+      row.get(19)?,
+      row.get(20)?,
+    ),
+  })
+}
+
+fn store_error(path: &Path, err: rusqlite::Error) -> Error {
+  Error::Store {
+    path: path.display().to_string(),
+    message: err.to_string(),
+  }
+}
+
+impl Stats {
+  /// Compute statistics of some values, or `None` if there are none.
+  /// Percentiles are interpolated linearly between the closest ranks.
+  pub fn new(values: &[f64]) -> Option<Self> {
+    if values.is_empty() {
+      return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    Some(Stats {
+      count: sorted.len(),
+      min: sorted[0],
+      max: sorted[sorted.len() - 1],
+      mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+      p10: percentile(&sorted, 10.0),
+      median: percentile(&sorted, 50.0),
+      p90: percentile(&sorted, 90.0),
+    })
+  }
+}
+
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+  let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+  let lower = rank.floor() as usize;
+  let upper = rank.ceil() as usize;
+  sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+impl Report {
+  /// Summarise samples of a place, or `None` if there are none.
+  pub fn new(place: &str, samples: &[Sample]) -> Option<Self> {
+    let fields = FIELDS
+      .iter()
+      .map(|field| {
+        let values: Vec<f64> = samples
+          .iter()
+          .map(|sample| (field.value)(&sample.weather))
+          .collect();
+        Some((field.name, field.unit, Stats::new(&values)?))
+      })
+      .collect::<Option<Vec<_>>>()?;
+
+    let mut days: BTreeMap<NaiveDate, Vec<&Weather>> = BTreeMap::new();
+    for sample in samples {
+      let date = sample.time.with_timezone(&Local).date_naive();
+      days.entry(date).or_default().push(&sample.weather);
+    }
+
+    let daily = days
+      .into_iter()
+      .filter_map(|(date, weathers)| {
+        let stats = |value: fn(&Weather) -> f64| {
+          Stats::new(
+            &weathers
+              .iter()
+              .map(|weather| value(weather))
+              .collect::<Vec<_>>(),
+          )
+        };
+        Some(Daily {
+          date,
+          temperature: stats(|weather| weather.temperature)?,
+          humidity: stats(|weather| weather.humidity as f64)?,
+          wind_speed: stats(|weather| weather.wind_speed)?,
+        })
+      })
+      .collect();
+
+    Some(Report {
+      place: place.to_string(),
+      fields,
+      daily,
+    })
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Stats {
+  fn to_string(&self) -> String {
+    format!(
+      "min {:.1}, max {:.1}, mean {:.1}, p10 {:.1}, median {:.1}, p90 {:.1}",
+      self.min, self.max, self.mean, self.p10, self.median, self.p90
+    )
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Daily {
+  fn to_string(&self) -> String {
+    format!(
+      "{}: {} samples, temperature {:.1} to {:.1} (mean {:.1}), humidity mean {:.0}, wind max {:.1}",
+      self.date,
+      self.temperature.count,
+      self.temperature.min,
+      self.temperature.max,
+      self.temperature.mean,
+      self.humidity.mean,
+      self.wind_speed.max
+    )
+  }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for Report {
+  fn to_string(&self) -> String {
+    let count = self.fields.first().map_or(0, |(_, _, stats)| stats.count);
+    let mut lines = vec![format!("{}: {} samples", self.place, count)];
+    for (name, unit, stats) in &self.fields {
+      let name = match unit.is_empty() {
+        true => name.to_string(),
+        false => format!("{} ({})", name, unit),
+      };
+      lines.push(format!("{}: {}", name, stats.to_string()));
+    }
+    lines.extend(self.daily.iter().map(Daily::to_string));
+    lines.join("\n")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::{Duration, TimeZone};
+
+  fn weather(temperature: f64, humidity: i32) -> Weather {
+    Weather::new(
+      true,
+      temperature,
+      temperature - 1.0,
+      temperature,
+      Condition::new("Sunny".to_string(), "//icon.png".to_string(), 1000),
+      10.0,
+      180,
+      "S".to_string(),
+      20.0,
+      temperature - 2.0,
+      1012.0,
+      0.0,
+      humidity,
+      25,
+      3.0,
+      10.0,
+      8.0,
+    )
+  }
+
+  fn noon(day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 6, day, 12, 0, 0).unwrap()
+  }
+
+  #[test]
+  fn test_record_and_samples() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("weather.db");
+    let store = Store::open(&path).unwrap();
+
+    store.record("office", noon(1), &weather(15.0, 60)).unwrap();
+    store.record("office", noon(2), &weather(18.0, 50)).unwrap();
+    store.record("home", noon(2), &weather(21.0, 40)).unwrap();
+
+    let samples = store.samples("Office", noon(1)).unwrap();
+    assert_eq!(samples.len(), 2);
+    assert_eq!(samples[0].time, noon(1));
+    assert_eq!(samples[0].place, "office");
+    assert_eq!(samples[1].weather.temperature, 18.0);
+    assert_eq!(samples[1].weather.condition.code, 1000);
+    assert_eq!(samples[1].weather.wind_dir, "S");
+
+    let samples = store.samples("office", noon(2)).unwrap();
+    assert_eq!(samples.len(), 1);
+
+    drop(store);
+    let store = Store::open(&path).unwrap();
+    assert_eq!(store.samples("home", noon(1)).unwrap().len(), 1);
+  }
+
+  #[test]
+  fn test_recorder() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("weather.db");
+    let location = |lat: f64, lon: f64| Location {
+      country: "United Kingdom".to_string(),
+      region: None,
+      city: Some("London".to_string()),
+      lat: Some(lat),
+      lon: Some(lon),
+    };
+    let since = Utc::now() - Duration::hours(1);
+
+    set_recorder(Some(Store::open(&path).unwrap()));
+    name_place(&location(51.52, -0.11), "office");
+    observe(&location(51.52, -0.11), &weather(15.0, 60));
+    observe(&location(51.5, -0.12), &weather(16.0, 60));
+    set_recorder(None);
+
+    let store = Store::open(&path).unwrap();
+    assert_eq!(store.samples("office", since).unwrap().len(), 1);
+    assert_eq!(
+      store
+        .samples("London, United Kingdom", since)
+        .unwrap()
+        .len(),
+      1
+    );
+  }
+
+  #[test]
+  fn test_stats() {
+    let stats = Stats::new(&[4.0, 1.0, 3.0, 2.0, 5.0]).unwrap();
+    assert_eq!(stats.count, 5);
+    assert_eq!(stats.min, 1.0);
+    assert_eq!(stats.max, 5.0);
+    assert_eq!(stats.mean, 3.0);
+    assert_eq!(stats.median, 3.0);
+    assert!((stats.p10 - 1.4).abs() < 1e-9);
+    assert!((stats.p90 - 4.6).abs() < 1e-9);
+
+    assert_eq!(Stats::new(&[]), None);
+  }
+
+  #[test]
+  fn test_report() {
+    let samples: Vec<Sample> = [(1, 10.0), (1, 14.0), (2, 20.0)]
+      .into_iter()
+      .enumerate()
+      .map(|(i, (day, temperature))| Sample {
+        time: noon(day) + Duration::minutes(i as i64),
+        place: "office".to_string(),
+        weather: weather(temperature, 50),
+      })
+      .collect();
+
+    let report = Report::new("office", &samples).unwrap();
+    let (name, unit, temperature) = &report.fields[0];
+    assert_eq!((*name, *unit), ("temperature", "°C"));
+    assert_eq!(temperature.mean, 44.0 / 3.0);
+
+    assert_eq!(report.daily.len(), 2);
+    assert_eq!(report.daily[0].temperature.count, 2);
+    assert_eq!(report.daily[0].temperature.min, 10.0);
+    assert_eq!(report.daily[0].temperature.max, 14.0);
+    assert_eq!(report.daily[1].temperature.mean, 20.0);
+
+    assert!(report.to_string().starts_with("office: 3 samples\n"));
+    assert!(Report::new("office", &[]).is_none());
+  }
+}
@@ -0,0 +1,419 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{
+  astronomy::Astronomy,
+  derive::{to_celsius, to_fahrenheit},
+  error::Error,
+  forecast::ForecastDay,
+  locale,
+  weather::{Condition, Units, Weather},
+};
+
+/// A template such as `{temp|round}°{unit} {condition.glyph}`.
+/// Fields are dotted paths into a context, optionally followed by filters separated by `|`.
+/// Literal braces are written `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+  parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+  Text(String),
+  Field {
+    path: Vec<String>,
+    filters: Vec<(&'static Filter, Option<String>)>,
+  },
+}
+
+/// A named filter, applied to a value with an optional argument given after `:`.
+pub struct Filter {
+  pub name: &'static str,
+  pub help: &'static str,
+  apply: fn(Value, Option<&str>) -> Result<Value, String>,
+}
+
+impl std::fmt::Debug for Filter {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.name)
+  }
+}
+
+impl PartialEq for Filter {
+  fn eq(&self, other: &Self) -> bool {
+    self.name == other.name
+  }
+}
+
+pub const FILTERS: [Filter; 14] = [
+  Filter {
+    name: "round",
+    help: "round to a number of decimal places, 0 by default",
+    apply: |value, arg| {
+      let places = count(arg, 0)?;
+      Ok(Value::String(
+        locale::current().fixed(number(&value)?, places),
+      ))
+    },
+  },
+  Filter {
+    name: "lpad",
+    help: "pad on the left to a width, aligning right",
+    apply: |value, arg| {
+      let width = count(arg, 0)?;
+      Ok(Value::String(format!("{:>width$}", text(&value))))
+    },
+  },
+  Filter {
+    name: "rpad",
+    help: "pad on the right to a width, aligning left",
+    apply: |value, arg| {
+      let width = count(arg, 0)?;
+      Ok(Value::String(format!("{:<width$}", text(&value))))
+    },
+  },
+  Filter {
+    name: "upper",
+    help: "upper case",
+    apply: |value, _| Ok(Value::String(text(&value).to_uppercase())),
+  },
+  Filter {
+    name: "lower",
+    help: "lower case",
+    apply: |value, _| Ok(Value::String(text(&value).to_lowercase())),
+  },
+  Filter {
+    name: "default",
+    help: "replace an empty or missing value",
+    apply: |value, arg| match text(&value).is_empty() {
+      true => Ok(Value::String(arg.unwrap_or_default().to_string())),
+      false => Ok(value),
+    },
+  },
+  Filter {
+    name: "c_to_f",
+    help: "convert Celsius to Fahrenheit",
+    apply: |value, _| convert(value, to_fahrenheit),
+  },
+  Filter {
+    name: "f_to_c",
+    help: "convert Fahrenheit to Celsius",
+    apply: |value, _| convert(value, to_celsius),
+  },
+  Filter {
+    name: "kph_to_mph",
+    help: "convert kilometres per hour to miles per hour",
+    apply: |value, _| convert(value, |kph| kph / MILE),
+  },
+  Filter {
+    name: "mph_to_kph",
+    help: "convert miles per hour to kilometres per hour",
+    apply: |value, _| convert(value, |mph| mph * MILE),
+  },
+  Filter {
+    name: "kph_to_ms",
+    help: "convert kilometres per hour to metres per second",
+    apply: |value, _| convert(value, |kph| kph / 3.6),
+  },
+  Filter {
+    name: "mb_to_inhg",
+    help: "convert millibars to inches of mercury",
+    apply: |value, _| convert(value, |mb| mb / INCH_OF_MERCURY),
+  },
+  Filter {
+    name: "inhg_to_mb",
+    help: "convert inches of mercury to millibars",
+    apply: |value, _| convert(value, |inhg| inhg * INCH_OF_MERCURY),
+  },
+  Filter {
+    name: "mm_to_in",
+    help: "convert millimetres to inches",
+    apply: |value, _| convert(value, |mm| mm / 25.4),
+  },
+];
+
+/// Kilometres in a mile.
+const MILE: f64 = 1.609344;
+
+/// Millibars in an inch of mercury.
+const INCH_OF_MERCURY: f64 = 33.8639;
+
+fn number(value: &Value) -> Result<f64, String> {
+  match value {
+    Value::Number(number) => Ok(number.as_f64().unwrap_or_default()),
+    Value::String(text) => text
+      .replace(locale::current().decimal_separator(), ".")
+      .parse()
+      .map_err(|_| format!("{} is not a number", text)),
+    value => Err(format!("{} is not a number", value)),
+  }
+}
+
+fn count(arg: Option<&str>, default: usize) -> Result<usize, String> {
+  match arg {
+    Some(arg) => arg
+      .parse()
+      .map_err(|_| format!("{} is not a whole number", arg)),
+    None => Ok(default),
+  }
+}
+
+fn convert(value: Value, conversion: fn(f64) -> f64) -> Result<Value, String> {
+  Ok(json!(conversion(number(&value)?)))
+}
+
+/// Text of a value as shown in a rendered template.
+/// Numbers use the decimal separator of the locale, and missing values are empty.
+fn text(value: &Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::String(text) => text.clone(),
+    Value::Number(number) if number.is_f64() => {
+      locale::current().number(number.as_f64().unwrap_or_default())
+    }
+    value => value.to_string(),
+  }
+}
+
+impl FromStr for Template {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let error = |message: String| Error::InvalidArgument {
+      arg: s.to_string(),
+      message,
+    };
+
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+      match c {
+        '{' if chars.peek() == Some(&'{') => {
+          chars.next();
+          literal.push('{');
+        }
+        '}' if chars.peek() == Some(&'}') => {
+          chars.next();
+          literal.push('}');
+        }
+        '{' => {
+          let mut field = String::new();
+          loop {
+            match chars.next() {
+              Some('}') => break,
+              Some(c) => field.push(c),
+              None => return Err(error("Template has an unclosed {.".to_string())),
+            }
+          }
+          if !literal.is_empty() {
+            parts.push(Part::Text(std::mem::take(&mut literal)));
+          }
+          parts.push(parse_field(&field).map_err(error)?);
+        }
+        '}' => return Err(error("Template has an unmatched }.".to_string())),
+        c => literal.push(c),
+      }
+    }
+    if !literal.is_empty() {
+      parts.push(Part::Text(literal));
+    }
+    Ok(Template { parts })
+  }
+}
+
+fn parse_field(field: &str) -> Result<Part, String> {
+  let mut segments = field.split('|').map(str::trim);
+  let path = segments.next().unwrap_or_default();
+  if path.is_empty() {
+    return Err("Template has an empty field.".to_string());
+  }
+
+  let filters = segments
+    .map(|segment| {
+      let (name, arg) = match segment.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg.to_string())),
+        None => (segment, None),
+      };
+      FILTERS
+        .iter()
+        .find(|filter| filter.name == name)
+        .map(|filter| (filter, arg))
+        .ok_or_else(|| {
+          format!(
+            "Unknown filter {}, should be one of {}.",
+            name,
+            FILTERS.map(|filter| filter.name).join(", ")
+          )
+        })
+    })
+    .collect::<Result<_, _>>()?;
+
+  Ok(Part::Field {
+    path: path.split('.').map(str::to_string).collect(),
+    filters,
+  })
+}
+
+impl Template {
+  /// Render the template against a context.
+  /// Fields that are not in the context are an error, so mistyped names are noticed.
+  pub fn render(&self, context: &Value) -> Result<String, Error> {
+    let mut result = String::new();
+    for part in &self.parts {
+      match part {
+        Part::Text(text) => result.push_str(text),
+        Part::Field { path, filters } => {
+          let name = path.join(".");
+          let mut value = lookup(context, path)
+            .ok_or_else(|| Error::InvalidArgument {
+              arg: name.clone(),
+              message: "Template field is not available here.".to_string(),
+            })?
+            .clone();
+          for (filter, arg) in filters {
+            value =
+              (filter.apply)(value, arg.as_deref()).map_err(|message| Error::InvalidArgument {
+                arg: format!("{}|{}", name, filter.name),
+                message,
+              })?;
+          }
+          result.push_str(&text(&value));
+        }
+      }
+    }
+    Ok(result)
+  }
+}
+
+/// Follow a dotted path through objects, and arrays by index.
+fn lookup<'a>(context: &'a Value, path: &[String]) -> Option<&'a Value> {
+  path.iter().try_fold(context, |value, key| match value {
+    Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+    value => value.get(key),
+  })
+}
+
+fn unit(units: Units) -> (&'static str, &'static str) {
+  match units {
+    Units::Metric => ("C", "kph"),
+    Units::Imperial => ("F", "mph"),
+  }
+}
+
+fn to_value<T: Serialize>(model: &T) -> Value {
+  serde_json::to_value(model).unwrap_or(Value::Null)
+}
+
+/// Condition fields, with the provider neutral kind and its symbols.
+fn condition_context(condition: &Condition, is_day: bool) -> Value {
+  let kind = condition.kind();
+  let mut context = to_value(condition);
+  context["kind"] = to_value(&kind);
+  context["glyph"] = json!(kind.glyph(is_day));
+  context["emoji"] = json!(kind.emoji(is_day));
+  context["symbol"] = json!(kind.icon(is_day));
+  context
+}
+
+/// Context of the current weather.
+/// Every field of the weather, with the short names `temp`, `feels`, `unit`,
+/// `speed_unit` and `wind.speed`, `wind.dir`, `wind.degree` and `wind.gust`.
+pub fn weather_context(weather: &Weather) -> Value {
+  let (unit, speed_unit) = unit(weather.units);
+  let mut context = to_value(weather);
+  context["temp"] = json!(weather.temperature);
+  context["feels"] = json!(weather.feels_like);
+  context["unit"] = json!(unit);
+  context["speed_unit"] = json!(speed_unit);
+  context["wind"] = json!({
+    "speed": weather.wind_speed,
+    "dir": weather.wind_dir,
+    "degree": weather.wind_degree,
+    "gust": weather.wind_gust_speed,
+  });
+  context["condition"] = condition_context(&weather.condition, weather.is_day);
+  context
+}
+
+/// Context of a forecast day.
+/// Every field of the day, with the short names `high`, `low`, `rain`, `snow` and `unit`.
+pub fn day_context(day: &ForecastDay, units: Units) -> Value {
+  let (unit, speed_unit) = unit(units);
+  let mut context = to_value(day);
+  context["high"] = json!(day.max_temperature);
+  context["low"] = json!(day.min_temperature);
+  context["rain"] = json!(day.chance_of_rain);
+  context["snow"] = json!(day.chance_of_snow);
+  context["unit"] = json!(unit);
+  context["speed_unit"] = json!(speed_unit);
+  context["condition"] = condition_context(&day.condition, true);
+  context
+}
+
+/// Context of astronomy data, its fields as serialized.
+pub fn astronomy_context(astronomy: &Astronomy) -> Value {
+  to_value(astronomy)
+}
+
+/// Context of a model as serialized, such as an alert.
+pub fn context<T: Serialize>(model: &T) -> Value {
+  to_value(model)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn render(template: &str) -> Result<String, Error> {
+    template
+      .parse::<Template>()?
+      .render(&weather_context(&Weather::sample()))
+  }
+
+  #[test]
+  fn test_render() {
+    assert_eq!(
+      render("{temp}°{unit} {condition.glyph} {wind.dir}").unwrap(),
+      "5.1°C ≡ SSW"
+    );
+    assert_eq!(
+      render("{condition.text} ({condition.kind}, {condition.symbol})").unwrap(),
+      "Fog (fog, fog)"
+    );
+    assert_eq!(render("{{literal}} {humidity}%").unwrap(), "{literal} 100%");
+    assert_eq!(render("").unwrap(), "");
+  }
+
+  #[test]
+  fn test_filters() {
+    assert_eq!(render("{temp|round}").unwrap(), "5");
+    assert_eq!(render("{temp|c_to_f|round:1}").unwrap(), "41.2");
+    assert_eq!(render("[{wind.dir|lpad:5}]").unwrap(), "[  SSW]");
+    assert_eq!(render("[{wind.dir|lower|rpad:4}]").unwrap(), "[ssw ]");
+    assert_eq!(render("{pressure|mb_to_inhg|round:2}").unwrap(), "30.42");
+    assert_eq!(render("{wind.speed | kph_to_ms | round:1}").unwrap(), "1.0");
+    assert_eq!(render("{condition.icon|default:none}").unwrap(), "none");
+  }
+
+  #[test]
+  fn test_errors() {
+    assert!("{temp".parse::<Template>().is_err());
+    assert!("temp}".parse::<Template>().is_err());
+    assert!("{}".parse::<Template>().is_err());
+    assert!("{temp|bogus}".parse::<Template>().is_err());
+    assert!(render("{nothing.here}").is_err());
+    assert!(render("{wind.dir|round}").is_err());
+    assert!(render("{temp|round:x}").is_err());
+  }
+
+  #[test]
+  fn test_lookup_index() {
+    let template: Template = "{alerts.0.event}".parse().unwrap();
+    let context = json!({"alerts": [{"event": "Flood Warning"}]});
+    assert_eq!(template.render(&context).unwrap(), "Flood Warning");
+  }
+}
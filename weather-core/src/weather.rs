@@ -1,13 +1,15 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{error::Error, location::Location, Query};
+use crate::{
+  condition::ConditionKind, error::Error, http, keys, locale, location::Location, store, Query,
+};
 
-#[derive(Debug, Deserialize)]
-struct Response {
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Response {
   current: CurrentResponse,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct CurrentResponse {
   temp_c: f64,
   temp_f: f64,
@@ -38,20 +40,21 @@ struct CurrentResponse {
   gust_kph: f64,
 }
 
-#[derive(Debug, Deserialize)]
-struct ConditionResponse {
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ConditionResponse {
   text: String,
   icon: String,
   code: i32,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Units {
   Metric,
   Imperial,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Weather {
   pub is_day: bool,
   pub temperature: f64,
@@ -70,9 +73,11 @@ pub struct Weather {
   pub uv: f64,
   pub visibility: f64,
   pub dew_point: f64,
+  /// Units of the measurements, metric unless fetched otherwise.
+  pub units: Units,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Condition {
   pub text: String,
   pub icon: String,
@@ -80,6 +85,7 @@ pub struct Condition {
 }
 
 impl Weather {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     is_day: bool,
     temperature: f64,
@@ -117,14 +123,39 @@ impl Weather {
       uv,
       visibility,
       dew_point,
+      units: Units::Metric,
     }
   }
+
+  /// Foggy winter morning in London, shared by the tests of both crates.
+  #[cfg(any(test, feature = "test-support"))]
+  pub fn sample() -> Self {
+    Weather::new(
+      true,
+      5.1,
+      4.8,
+      5.1,
+      Condition::new("Fog".to_string(), String::new(), 1135),
+      3.6,
+      206,
+      "SSW".to_string(),
+      4.1,
+      5.1,
+      1030.0,
+      0.0,
+      100,
+      100,
+      0.0,
+      0.4,
+      4.4,
+    )
+  }
 }
 
 impl From<(Response, Units)> for Weather {
   fn from((response, units): (Response, Units)) -> Self {
-    if units == Units::Imperial {
-      return Weather::new(
+    let weather = if units == Units::Imperial {
+      Weather::new(
         response.current.is_day == 1,
         response.current.temp_f,
         response.current.feelslike_f,
@@ -142,9 +173,9 @@ impl From<(Response, Units)> for Weather {
         response.current.uv,
         response.current.vis_miles,
         response.current.dewpoint_f,
-      );
+      )
     } else {
-      return Weather::new(
+      Weather::new(
         response.current.is_day == 1,
         response.current.temp_c,
         response.current.feelslike_c,
@@ -162,33 +193,40 @@ impl From<(Response, Units)> for Weather {
         response.current.uv,
         response.current.vis_km,
         response.current.dewpoint_c,
-      );
-    }
+      )
+    };
+    Weather { units, ..weather }
   }
 }
 
+#[allow(clippy::to_string_trait_impl)]
 impl ToString for Weather {
   fn to_string(&self) -> String {
-    format!(
-      "Is Day: {}\n Temperature: {}\n Feels Like: {}\n Heat Index: {}\n Condition: {}\n Wind Speed: {}\n Wind Degree: {}\n Wind Direction: {}\n Wind Gust Speed: {}\n Wind Chill: {}\n Pressure: {}\n Precipitation: {}\n Humidity: {}\n Cloud: {}\n UV: {}\n Visibility: {}\n Dew Point: {}",
-      self.is_day,
-      self.temperature,
-      self.feels_like,
-      self.heat_index,
-      self.condition.to_string(),
-      self.wind_speed,
-      self.wind_degree,
-      self.wind_dir,
-      self.wind_gust_speed,
-      self.wind_chill,
-      self.pressure,
-      self.precipitation,
-      self.humidity,
-      self.cloud,
-      self.uv,
-      self.visibility,
-      self.dew_point
-    )
+    let locale = locale::current();
+    let number = |value: f64| locale.number(value);
+    [
+      ("Is Day", locale.boolean(self.is_day).to_string()),
+      ("Temperature", number(self.temperature)),
+      ("Feels Like", number(self.feels_like)),
+      ("Heat Index", number(self.heat_index)),
+      ("Condition", self.condition.to_string()),
+      ("Wind Speed", number(self.wind_speed)),
+      ("Wind Degree", self.wind_degree.to_string()),
+      ("Wind Direction", self.wind_dir.clone()),
+      ("Wind Gust Speed", number(self.wind_gust_speed)),
+      ("Wind Chill", number(self.wind_chill)),
+      ("Pressure", number(self.pressure)),
+      ("Precipitation", number(self.precipitation)),
+      ("Humidity", self.humidity.to_string()),
+      ("Cloud", self.cloud.to_string()),
+      ("UV", number(self.uv)),
+      ("Visibility", number(self.visibility)),
+      ("Dew Point", number(self.dew_point)),
+    ]
+    .iter()
+    .map(|(label, value)| format!("{}: {}", locale.label(label), value))
+    .collect::<Vec<_>>()
+    .join("\n ")
   }
 }
 
@@ -196,6 +234,11 @@ impl Condition {
   pub fn new(text: String, icon: String, code: i32) -> Self {
     Condition { text, icon, code }
   }
+
+  /// Provider neutral kind of the condition.
+  pub fn kind(&self) -> ConditionKind {
+    ConditionKind::from_code(self.code)
+  }
 }
 
 impl From<ConditionResponse> for Condition {
@@ -204,32 +247,46 @@ impl From<ConditionResponse> for Condition {
   }
 }
 
+#[allow(clippy::to_string_trait_impl)]
 impl ToString for Condition {
   fn to_string(&self) -> String {
-    format!("{}\n Icon: {}\n Code: {}", self.text, self.icon, self.code)
+    let locale = locale::current();
+    format!(
+      "{}\n {}: {}\n {}: {}",
+      self.text,
+      locale.label("Icon"),
+      self.icon,
+      locale.label("Code"),
+      self.code
+    )
   }
 }
 
+/// Weather of a response in `units`, recorded in the store when a recorder is set.
+pub(crate) fn observed(location: &Location, response: Response, units: Units) -> Weather {
+  store::observe(location, &Weather::from((response.clone(), Units::Metric)));
+  Weather::from((response, units))
+}
+
 pub async fn get_current_weather(location: Location, units: Units) -> Result<Weather, Error> {
   let location_query = match location.query() {
     Ok(query) => query,
     Err(err) => return Err(err),
   };
 
-  let address = std::env::var("WEATHER_API").unwrap();
-  let key = std::env::var("WEATHER_KEY").unwrap();
+  let address = http::address();
+  let key = keys::env_key();
 
-  let url = format!("{}/current.json?key={}&q={}", address, key, location_query);
+  let url = format!(
+    "{}/current.json?key={}&q={}{}",
+    address,
+    key,
+    location_query,
+    locale::current().query()
+  );
 
-  match reqwest::get(&url).await {
-    Ok(response) => {
-      let weather: Response = response.json::<Response>().await.unwrap();
-      Ok(Weather::from((weather, units)))
-    }
-    Err(err) => Err(Error::Fetch {
-      message: err.to_string(),
-    }),
-  }
+  let weather: Response = http::get_json(&url).await?;
+  Ok(observed(&location, weather, units))
 }
 
 pub async fn get_forecast_weather(
@@ -242,34 +299,30 @@ pub async fn get_forecast_weather(
     Err(err) => return Err(err),
   };
 
-  let address = std::env::var("WEATHER_API").unwrap();
-  let key = std::env::var("WEATHER_KEY").unwrap();
+  let address = http::address();
+  let key = keys::env_key();
 
   let url = format!(
-    "{}/forecast.json?key={}&q={}&days={}",
-    address, key, location_query, days
+    "{}/forecast.json?key={}&q={}&days={}{}",
+    address,
+    key,
+    location_query,
+    days,
+    locale::current().query()
   );
 
-  match reqwest::get(&url).await {
-    Ok(response) => {
-      let weather: Response = response.json().await.unwrap();
-      Ok(Weather::from((weather, units)))
-    }
-    Err(err) => Err(Error::Fetch {
-      message: err.to_string(),
-    }),
-  }
+  let weather: Response = http::get_json(&url).await?;
+  Ok(observed(&location, weather, units))
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use mockito::mock;
+  use crate::replay::{self, fixtures};
   use tokio;
 
   fn setup() {
     dotenv::dotenv().ok();
-    std::env::set_var("WEATHER_API", &mockito::server_url());
     std::env::set_var("WEATHER_KEY", "test_key");
   }
 
@@ -277,56 +330,6 @@ mod tests {
   #[tokio::test]
   async fn test_get_current_weather_metric() {
     setup();
-    let _m = mock("GET", "/current.json?key=test_key&q=51.5171,-0.1062")
-      .with_status(200)
-      .with_header("content-type", "application/json")
-      .with_body(
-        r#"{
-          "location": {
-            "name": "London",
-            "region": "City of London, Greater London",
-            "country": "United Kingdom",
-            "lat": 51.5171,
-            "lon": -0.1062,
-            "tz_id": "Europe/London",
-            "localtime": "2024-12-28 00:51"
-          },
-          "current": {
-            "temp_c": 5.1,
-            "temp_f": 41.2,
-            "is_day": 0,
-            "condition": {
-              "text": "Fog",
-              "icon": "//cdn.weatherapi.com/weather/64x64/night/248.png",
-              "code": 1135
-            },
-            "wind_mph": 2.2,
-            "wind_kph": 3.6,
-            "wind_degree": 206,
-            "wind_dir": "SSW",
-            "pressure_mb": 1030,
-            "pressure_in": 30.42,
-            "precip_mm": 0,
-            "precip_in": 0,
-            "humidity": 100,
-            "cloud": 100,
-            "feelslike_c": 4.8,
-            "feelslike_f": 40.7,
-            "windchill_c": 5.1,
-            "windchill_f": 41.2,
-            "heatindex_c": 5.1,
-            "heatindex_f": 41.2,
-            "dewpoint_c": 4.4,
-            "dewpoint_f": 39.9,
-            "vis_km": 0.4,
-            "vis_miles": 0,
-            "uv": 0,
-            "gust_mph": 2.5,
-            "gust_kph": 4.1
-          }
-        }"#,
-      )
-      .create();
 
     let location = Location {
       country: "United Kingdom".to_string(),
@@ -336,7 +339,11 @@ mod tests {
       lon: Some(-0.1062),
     };
 
-    let result = get_current_weather(location, Units::Metric).await;
+    let result = replay::scope(
+      fixtures("london"),
+      get_current_weather(location, Units::Metric),
+    )
+    .await;
     assert!(result.is_ok());
 
     let weather = result.unwrap();
@@ -348,56 +355,6 @@ mod tests {
   #[tokio::test]
   async fn test_get_current_weather_imperial() {
     setup();
-    let _m = mock("GET", "/current.json?key=test_key&q=51.5171,-0.1062")
-      .with_status(200)
-      .with_header("content-type", "application/json")
-      .with_body(
-        r#"{
-          "location": {
-            "name": "London",
-            "region": "City of London, Greater London",
-            "country": "United Kingdom",
-            "lat": 51.5171,
-            "lon": -0.1062,
-            "tz_id": "Europe/London",
-            "localtime": "2024-12-28 00:51"
-          },
-          "current": {
-            "temp_c": 5.1,
-            "temp_f": 41.2,
-            "is_day": 0,
-            "condition": {
-              "text": "Fog",
-              "icon": "//cdn.weatherapi.com/weather/64x64/night/248.png",
-              "code": 1135
-            },
-            "wind_mph": 2.2,
-            "wind_kph": 3.6,
-            "wind_degree": 206,
-            "wind_dir": "SSW",
-            "pressure_mb": 1030,
-            "pressure_in": 30.42,
-            "precip_mm": 0,
-            "precip_in": 0,
-            "humidity": 100,
-            "cloud": 100,
-            "feelslike_c": 4.8,
-            "feelslike_f": 40.7,
-            "windchill_c": 5.1,
-            "windchill_f": 41.2,
-            "heatindex_c": 5.1,
-            "heatindex_f": 41.2,
-            "dewpoint_c": 4.4,
-            "dewpoint_f": 39.9,
-            "vis_km": 0.4,
-            "vis_miles": 0,
-            "uv": 0,
-            "gust_mph": 2.5,
-            "gust_kph": 4.1
-          }
-        }"#,
-      )
-      .create();
 
     let location = Location {
       country: "United Kingdom".to_string(),
@@ -407,7 +364,11 @@ mod tests {
       lon: Some(-0.1062),
     };
 
-    let result = get_current_weather(location, Units::Imperial).await;
+    let result = replay::scope(
+      fixtures("london"),
+      get_current_weather(location, Units::Imperial),
+    )
+    .await;
     assert!(result.is_ok());
 
     let weather = result.unwrap();